@@ -1,14 +1,15 @@
 //! Overall container for data associated with the application itself (i.e. not user data). For example, file operations, 
 //! os interaction, etc. 
 
-/// Ideally this is just a bundle of owned structs which serve to actually manage the concerns listed above. 
-/// For the sake of testing, this should not perform any "untestible" os operations directly. Rather, 
-/// the sub-structs can be mocked as needed and passed in. 
+// Ideally this is just a bundle of owned structs which serve to actually manage the concerns listed above. 
+// For the sake of testing, this should not perform any "untestible" os operations directly. Rather, 
+// the sub-structs can be mocked as needed and passed in. 
 
 use std::error::Error;
 
 use ledger::Ledger;
 
+use crate::config::Config;
 use crate::store::FileStore;
 #[mockall_double::double]
 use crate::ui::TerminalInterface;
@@ -16,11 +17,12 @@ use crate::ui::TerminalInterface;
 pub struct Application {
     file_store: FileStore,
     interface: TerminalInterface,
+    config: Config,
 }
 
 impl Application {
-    pub fn new(interface: TerminalInterface, file_store: FileStore) -> Self {
-        Application { file_store, interface }
+    pub fn new(interface: TerminalInterface, file_store: FileStore, config: Config) -> Self {
+        Application { file_store, interface, config }
     }
 
     pub fn store_ledger(&self, ledger: &Ledger) -> Result<(), Box<dyn Error>> {
@@ -38,4 +40,12 @@ impl Application {
     pub fn interface(&mut self) -> &mut TerminalInterface {
         &mut self.interface
     }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
 }
\ No newline at end of file
@@ -1,8 +1,8 @@
-use ledger::Ledger;
+use ledger::{account::AccountType, common_types::{Currency, Money}, Ledger};
 
 use crate::app::Application;
 
-use super::{Cmd, CmdError, CmdErrorType, CmdResult, SyntaxErrorType};
+use super::{confirm, get_account, parse_date_or_today, Cmd, CmdError, CmdErrorType, CmdResult, ParsedArgs, SyntaxErrorType};
 
 
 pub struct Account {
@@ -19,7 +19,28 @@ impl Cmd for Account {
                 self.add_new_account(&args[1..], ledger, app)
             },
             Some(&"--list") => {
-                self.list_accounts(ledger, app)
+                self.list_accounts(&args[1..], ledger, app)
+            }
+            Some(&"--rename") => {
+                self.rename_account(&args[1..], ledger, app)
+            }
+            Some(&"--close") => {
+                let id = get_account(args.get(1).copied(), ledger).map_err(|e| self.new_error(e))?.get_id();
+                ledger.close_account(id).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Closed account '{}'", args[1])?;
+                Ok(CmdResult::Ok)
+            }
+            Some(&"--reopen") => {
+                let id = get_account(args.get(1).copied(), ledger).map_err(|e| self.new_error(e))?.get_id();
+                ledger.reopen_account(id).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Reopened account '{}'", args[1])?;
+                Ok(CmdResult::Ok)
+            }
+            Some(&"--delete") => {
+                self.delete_account(&args[1..], ledger, app)
+            }
+            Some(&"--net-worth") => {
+                self.show_net_worth(&args[1..], ledger, app)
             }
             Some(unhandled_subcommand) => {
                 Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::InvalidSubcommand(unhandled_subcommand.to_string()))))
@@ -35,34 +56,257 @@ impl Cmd for Account {
     }
 
     fn help_text(&self) -> &'static str {
-"Usage: account [OPTION] ACCOUNT_NAME
+"Usage: account [OPTION] ACCOUNT_NAME [CURRENCY]
 Perform operations on user accounts. 
 
 Options:
-  --new    Create a new account with ACCOUNT_NAME
-  --list   List the existing accounts"
+  --new    Create a new account with ACCOUNT_NAME, holding the ISO 4217 CURRENCY (USD by default)
+           --type TYPE                Checking, savings, investment, cash, asset (the default), credit-card,
+                                      loan, liability, equity, income or expense
+           --opening-balance AMOUNT   The balance before any transactions. For credit cards, loans and other
+                                      liabilities, this is the amount owed.
+           --date DATE                When the opening balance was taken (today by default)
+  --list [--all]
+           List the open accounts, or every account including closed ones
+  --rename OLD_NAME NEW_NAME
+           Rename an account
+  --close ACCOUNT_NAME
+           Close an account. It keeps its transactions, but can't take new ones and is hidden from --list.
+  --reopen ACCOUNT_NAME
+           Reopen a closed account
+  --delete ACCOUNT_NAME [--move-to OTHER_ACCOUNT | --force]
+           Delete an account. If it has transactions, either move them to OTHER_ACCOUNT first, or pass --force
+           to delete them too. Asks for confirmation.
+  --net-worth CURRENCY [DATE]
+           Show every account total and their sum converted to CURRENCY, using the exchange rates
+           in effect on DATE (today by default)"
     }
 }
 
 impl Account {
 
     fn add_new_account(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
-        let name = args.first().ok_or(
+        let args = ParsedArgs::parse(args, &["--type", "--opening-balance", "--date"], &[])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let name = args.positional().first().ok_or(
             self.new_error(
                 CmdErrorType::Syntax(
                     SyntaxErrorType::MissingParam(
                         "Adding a new account requires an name".to_string()))))?;
 
-        ledger.add_new_account(String::from(*name));
-        writeln!(app.out(), "Created account '{}'", name)?;
+        let currency = match args.positional().get(1) {
+            Some(code) => Currency::from_code(code).map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?,
+            None => Currency::USD,
+        };
+        let account_type = match args.value("--type") {
+            Some(account_type) => account_type.parse().map_err(|e| self.new_error(CmdErrorType::Argument(e)))?,
+            None => AccountType::default(),
+        };
+        let opening_balance = args.value("--opening-balance")
+            .map(|amount| Money::parse(amount, currency, app.config().number_format))
+            .transpose()
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?;
+        let date = parse_date_or_today(args.value("--date"), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        match opening_balance {
+            Some(balance) => ledger.add_new_account_with_opening_balance(String::from(*name), currency, account_type, balance, date),
+            None => ledger.add_new_account_of_type(String::from(*name), currency, account_type),
+        }.map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "Created {} account '{}' ({})", account_type, name, currency)?;
+        Ok(CmdResult::Ok)
+    }
+
+    fn list_accounts(&self, args: &[&str], ledger: &Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &[], &["--all"]).map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        for account in ledger.get_accounts().iter().filter(|a| args.flag("--all") || !a.is_closed()) {
+            let closed = if account.is_closed() { "\t(closed)" } else { "" };
+            writeln!(
+                app.out(),
+                "  {}\t{}\t{}\t{}{}",
+                account.get_id(), account.get_name(), account.get_type(), account.get_total(), closed)?;
+        }
+
+        Ok(CmdResult::Ok)
+    }
+
+    fn rename_account(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let id = get_account(args.first().copied(), ledger).map_err(|e| self.new_error(e))?.get_id();
+        let name = args.get(1).ok_or(
+            self.new_error(
+                CmdErrorType::Syntax(
+                    SyntaxErrorType::MissingParam(
+                        "Renaming an account requires a new name".to_string()))))?;
+
+        ledger.rename_account(id, name.to_string()).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "Renamed account '{}' to '{}'", args[0], name)?;
+        Ok(CmdResult::Ok)
+    }
+
+    fn delete_account(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &["--move-to"], &["--force"])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let id = get_account(args.positional().first().copied(), ledger).map_err(|e| self.new_error(e))?.get_id();
+        let move_to = args.value("--move-to").map(|name| get_account(Some(name), ledger).map(|a| a.get_id())).transpose().map_err(|e| self.new_error(e))?;
+        let account = ledger.get_account(id).expect("Checked above");
+        let name = account.get_name().clone();
+        let count = account.get_transactions().len();
+
+        if count > 0 && move_to.is_none() && !args.flag("--force") {
+            return Err(self.new_error(CmdErrorType::Argument(format!(
+                "Account '{}' still has {} transactions. Use --move-to to move them to another account, or --force to \
+                delete them too.", name, count))));
+        }
+        let prompt = match (count, move_to) {
+            (0, _) => format!("Delete account '{}'?", name),
+            (_, Some(target)) => format!(
+                "Move {} transactions to '{}' and delete account '{}'?",
+                count, ledger.get_account(target).expect("Checked above").get_name(), name),
+            (_, None) => {
+                let linked: Vec<String> = ledger.get_linked_accounts(id).iter().map(|a| format!("'{}'", a.get_name())).collect();
+                if linked.is_empty() {
+                    format!("Delete account '{}' and its {} transactions?", name, count)
+                } else {
+                    format!(
+                        "Delete account '{}' and its {} transactions, along with the linked transactions in {}?",
+                        name, count, linked.join(", "))
+                }
+            },
+        };
+        if !confirm(app, &prompt)? {
+            writeln!(app.out(), "Cancelled")?;
+            return Ok(CmdResult::Ok);
+        }
+
+        if let Some(target) = move_to {
+            ledger.move_transactions(id, target).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        }
+        ledger.delete_account(id, args.flag("--force")).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "Deleted account '{}'", name)?;
         Ok(CmdResult::Ok)
     }
 
-    fn list_accounts(&self, ledger: &Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+    fn show_net_worth(&self, args: &[&str], ledger: &Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let code = args.first().ok_or(
+            self.new_error(
+                CmdErrorType::Syntax(
+                    SyntaxErrorType::MissingParam(
+                        "Showing net worth requires a reporting currency".to_string()))))?;
+        let currency = Currency::from_code(code).map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?;
+        let date = parse_date_or_today(args.get(1).copied(), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
         for account in ledger.get_accounts() {
-            writeln!(app.out(), "  {}\t{}", account.get_name(), account.get_total())?;
+            let converted = ledger.get_account_total_in(account, currency, date)
+                .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+            writeln!(app.out(), "  {}\t{}\t{}", account.get_name(), account.balance_at(date), converted)?;
         }
 
+        let net_worth = ledger.get_net_worth(currency, date).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "Net worth as of {}: {}", date, net_worth)?;
         Ok(CmdResult::Ok)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    fn test_ledger() -> Ledger {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        let time: chrono::DateTime<chrono::Utc> = "2000-1-1T00:00:00Z".parse().expect("Failed to parse");
+        ledger.add_transaction(checking, ledger::transaction::Transaction::new(
+            Money::new(1000, Currency::USD), time, "Widgets Inc.".to_string(), None)).unwrap();
+        ledger
+    }
+
+    #[test]
+    fn rename_close_reopen() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let mut ledger = test_ledger();
+        let cmd = Account::new();
+        assert!(cmd.execute(&["--rename", "checking", "Everyday"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--rename", "everyday", "Savings"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--rename", "nope", "Other"], &mut ledger, &mut app).is_err());
+
+        assert!(cmd.execute(&["--close", "everyday"], &mut ledger, &mut app).is_ok());
+        assert!(ledger.get_account_by_name("Everyday").unwrap().is_closed());
+        assert!(cmd.execute(&["--close", "everyday"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--reopen", "everyday"], &mut ledger, &mut app).is_ok());
+        assert!(!ledger.get_account_by_name("Everyday").unwrap().is_closed());
+    }
+
+    #[test]
+    fn delete() {
+        let mut ledger = test_ledger();
+        let (mut app, _) = test_app_with_output(&["n", "y", "yes"]);
+        let cmd = Account::new();
+        assert!(cmd.execute(&["--delete", "checking"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--delete", "checking", "--force"], &mut ledger, &mut app).is_ok());
+        assert!(ledger.get_account_by_name("checking").is_some());
+
+        assert!(cmd.execute(&["--delete", "checking", "--move-to", "savings"], &mut ledger, &mut app).is_ok());
+        assert!(ledger.get_account_by_name("checking").is_none());
+        assert_eq!(ledger.get_account_by_name("savings").unwrap().get_total(), Money::new(1000, Currency::USD));
+
+        assert!(cmd.execute(&["--delete", "savings", "--force"], &mut ledger, &mut app).is_ok());
+        assert!(ledger.get_accounts().is_empty());
+    }
+
+    #[test]
+    fn delete_lists_linked_accounts() {
+        let mut ledger = test_ledger();
+        let checking = ledger.get_account_by_name("checking").unwrap().get_id();
+        let savings = ledger.get_account_by_name("savings").unwrap().get_id();
+        let time: chrono::DateTime<chrono::Utc> = "2000-1-1T00:00:00Z".parse().expect("Failed to parse");
+        ledger.transfer(checking, savings, Money::new(500, Currency::USD), time, "".to_string()).unwrap();
+
+        let (mut app, output) = test_app_with_output(&["n"]);
+        let cmd = Account::new();
+        assert!(cmd.execute(&["--delete", "checking", "--force"], &mut ledger, &mut app).is_ok());
+        assert!(ledger.get_account_by_name("checking").is_some());
+
+        let output = output.lock().unwrap();
+        assert!(
+            output.contains("Delete account 'Checking' and its 2 transactions, along with the linked transactions in 'Savings'?"),
+            "{}", output);
+    }
+
+    #[test]
+    fn new_account() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let mut ledger = Ledger::new_empty();
+        let cmd = Account::new();
+        assert!(cmd.execute(&["--new", "Checking"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(
+            &["--new", "Visa", "USD", "--type", "credit-card", "--opening-balance", "250", "--date", "2024-01-01"],
+            &mut ledger,
+            &mut app).is_ok());
+
+        let visa = ledger.get_account_by_name("visa").unwrap();
+        assert_eq!(visa.get_type(), AccountType::CreditCard);
+        assert_eq!(visa.get_total(), Money::new(-25000, Currency::USD));
+        assert_eq!(ledger.get_account_by_name("checking").unwrap().get_type(), AccountType::Asset);
+
+        assert!(cmd.execute(&["--new", "Piggy bank", "--type", "pig"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--new", "Piggy bank", "--opening-balance", "lots"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--new", "Piggy bank", "--opening-balance", "12 EUR"], &mut ledger, &mut app).is_err());
+        assert!(ledger.get_account_by_name("piggy bank").is_none());
+    }
+
+    #[test]
+    fn new_account_invalid_name() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let mut ledger = Ledger::new_empty();
+        let cmd = Account::new();
+        assert!(cmd.execute(&["--new", "Checking"], &mut ledger, &mut app).is_ok());
+
+        for name in ["CHECKING", "  ", "Tab\there"] {
+            let err = cmd.execute(&["--new", name], &mut ledger, &mut app).unwrap_err();
+            assert!(matches!(err.error_type, CmdErrorType::Argument(_)), "{:?}", err);
+        }
+        assert_eq!(ledger.get_accounts().len(), 1);
+    }
+}
@@ -0,0 +1,144 @@
+use colored::Colorize;
+use ledger::Ledger;
+
+use crate::{app::Application, dates::{self, Interval}, ui};
+
+use super::{get_account, parse_date_or_today, Cmd, CmdError, CmdErrorType, CmdResult, ParsedArgs, SyntaxErrorType};
+
+/// Command to show account balances as of a date, or over time
+pub struct Balance { }
+
+impl Cmd for Balance {
+    fn new() -> Balance {
+        Balance { }
+    }
+
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        match args.first() {
+            Some(&"--history") => {
+                self.show_history(&args[1..], ledger, app)
+            },
+            Some(unhandled_subcommand) if unhandled_subcommand.starts_with("--") => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::InvalidSubcommand(unhandled_subcommand.to_string()))))
+            },
+            _ => {
+                self.show_balance(args, ledger, app)
+            },
+        }
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["balance", "bal"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: balance ACCOUNT [DATE]
+       balance --history ACCOUNT [OPTIONS]
+Shows the balance of ACCOUNT at the end of DATE (today by default), including everything up to and on that day.
+
+With --history, shows the balance at the end of each period, followed by a chart of how it changed.
+
+Options for --history:
+  --interval INTERVAL     month (the default) or week. Weeks end on Sunday.
+  --from DATE, --to DATE  The range to show. By default it runs from the account's first transaction to today."
+    }
+}
+
+impl Balance {
+
+    fn show_balance(&self, args: &[&str], ledger: &Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let account = get_account(args.first().copied(), ledger).map_err(|e| self.new_error(e))?;
+        let date = parse_date_or_today(args.get(1).copied(), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "Balance of '{}' at the end of {}: {}", account.get_name(), date, account.balance_at(date))?;
+        Ok(CmdResult::Ok)
+    }
+
+    fn show_history(&self, args: &[&str], ledger: &Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &["--interval", "--from", "--to"], &[])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let account = get_account(args.positional().first().copied(), ledger).map_err(|e| self.new_error(e))?;
+        let interval: Interval = args.value("--interval").unwrap_or("month").parse()
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        let parse_date = |arg: Option<&str>| parse_date_or_today(arg, app).map_err(|e| self.new_error(CmdErrorType::Argument(e)));
+        let to = parse_date(args.value("--to"))?;
+        let from = match args.value("--from") {
+            Some(from) => parse_date(Some(from))?,
+            None => account.get_start_date().unwrap_or(to).min(to),
+        };
+
+        let history: Vec<_> = dates::period_ends(from, to, interval).into_iter()
+            .map(|date| (date, account.balance_at(date)))
+            .collect();
+        for (date, balance) in &history {
+            writeln!(app.out(), "  {}  {:>14}", date, balance.to_string())?;
+        }
+
+        let values: Vec<i64> = history.iter().map(|(_, balance)| balance.minor_units()).collect();
+        if values.len() > 1 {
+            let line = ui::sparkline(&values);
+            let line = if values.last() >= values.first() { line.green() } else { line.red() };
+            writeln!(app.out(), "  {}", line)?;
+        }
+        Ok(CmdResult::Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger::common_types::{Currency, Money};
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    fn test_ledger() -> Ledger {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        for (date, amount) in [("2024-01-10", 10000), ("2024-02-10", -2500), ("2024-03-10", 5000)] {
+            let time: chrono::DateTime<chrono::Utc> = format!("{}T12:00:00Z", date).parse().expect("Failed to parse");
+            ledger.add_transaction(checking, ledger::transaction::Transaction::new(
+                Money::new(amount, Currency::USD), time, "Widgets Inc.".to_string(), None)).unwrap();
+        }
+        ledger
+    }
+
+    #[test]
+    fn balance_on_date() {
+        let mut ledger = test_ledger();
+        let (mut app, output) = test_app_with_output(&[]);
+        assert!(Balance::new().execute(&["checking", "2024-02-10"], &mut ledger, &mut app).is_ok());
+        assert!(output.lock().unwrap().contains("2024-02-10: $75.00"));
+        assert!(Balance::new().execute(&["savings"], &mut ledger, &mut app).is_err());
+    }
+
+    #[test]
+    fn history() {
+        let mut ledger = test_ledger();
+        let (mut app, output) = test_app_with_output(&[]);
+        let args = ["--history", "checking", "--to", "2024-03-31"];
+        assert!(Balance::new().execute(&args, &mut ledger, &mut app).is_ok());
+
+        let output = output.lock().unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4, "{}", output);
+        assert!(lines[0].starts_with("  2024-01-31") && lines[0].ends_with("$100.00"), "{}", output);
+        assert!(lines[1].starts_with("  2024-02-29") && lines[1].ends_with("$75.00"), "{}", output);
+        assert!(lines[2].starts_with("  2024-03-31") && lines[2].ends_with("$125.00"), "{}", output);
+        assert!(lines[3].contains("▄▁█"), "{}", output);
+    }
+
+    #[test]
+    fn history_by_week() {
+        let mut ledger = test_ledger();
+        let (mut app, output) = test_app_with_output(&[]);
+        let args = ["--history", "checking", "--interval", "week", "--from", "2024-01-01", "--to", "2024-01-14"];
+        assert!(Balance::new().execute(&args, &mut ledger, &mut app).is_ok());
+        let output = output.lock().unwrap();
+        assert!(output.contains("2024-01-07           $0.00"), "{}", output);
+        assert!(output.contains("2024-01-14         $100.00"), "{}", output);
+
+        let args = ["--history", "checking", "--interval", "daily"];
+        assert!(Balance::new().execute(&args, &mut ledger, &mut app).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use colored::Colorize;
+use ledger::{common_types::{Currency, Money}, Ledger};
+
+use crate::app::Application;
+
+use super::{get_category_id, parse_month_or_this_month, Cmd, CmdError, CmdErrorType, CmdResult, ParsedArgs, SyntaxErrorType};
+
+/// Command to set monthly category budgets and compare them with what was spent
+pub struct Budget { }
+
+impl Cmd for Budget {
+    fn new() -> Budget {
+        Budget { }
+    }
+
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        match args.first() {
+            Some(&"--set") => {
+                self.set_budget(&args[1..], ledger, app)
+            },
+            Some(&"--remove") => {
+                let name = args.get(1).ok_or(
+                    self.new_error(
+                        CmdErrorType::Syntax(
+                            SyntaxErrorType::MissingParam(
+                                "Must provide a category name".to_string()))))?;
+                let category = get_category_id(name, ledger).map_err(|e| self.new_error(e))?;
+                ledger.remove_budget(&category).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Removed the budget for '{}'", ledger.get_transaction_categories().display_name(&category))?;
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--show") => {
+                self.show_budget(args.get(1).copied(), ledger, app)
+            },
+            Some(unhandled_subcommand) if unhandled_subcommand.starts_with("--") => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::InvalidSubcommand(unhandled_subcommand.to_string()))))
+            },
+            // Without a subcommand, show the budget
+            _ => {
+                self.show_budget(args.first().copied(), ledger, app)
+            },
+        }
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["budget"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: budget [--show] [MONTH]
+       budget --set CATEGORY [AMOUNT] [OPTIONS]
+       budget --remove CATEGORY
+Shows each budgeted category's budget for MONTH (this month by default) next to what was spent in it, including its
+subcategories. Overspent categories are shown in red. MONTH may be written like 2024-03, or as any date in the month.
+
+--set budgets AMOUNT a month for CATEGORY from the given month on, until it's set again for a later month. Earlier
+months keep what they had.
+
+Options for --set:
+  --month MONTH           The first month the amount applies to, this month by default
+  --currency CURRENCY     The currency of a new budget, USD by default. A budget stays in the currency it was
+                          first set in.
+  --rollover              Carry what's left at the end of each month, or how far it went over, into the next month.
+                          This applies to every month since the budget started.
+  --no-rollover           Start each month afresh (the default)
+AMOUNT can be left out when changing only the rollover.
+
+--remove removes a category's budget for every month. Merging or deleting a category removes its budget too."
+    }
+}
+
+impl Budget {
+    fn set_budget(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &["--month", "--currency"], &["--rollover", "--no-rollover"])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let rollover = match (args.flag("--rollover"), args.flag("--no-rollover")) {
+            (true, true) => return Err(self.new_error(CmdErrorType::Argument("Give either --rollover or --no-rollover, not both".to_string()))),
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            (false, false) => None,
+        };
+        let positional = args.positional();
+        let name = positional.first().ok_or(
+            self.new_error(
+                CmdErrorType::Syntax(
+                    SyntaxErrorType::MissingParam(
+                        "Invalid format. Usage: `budget --set CATEGORY AMOUNT`".to_string()))))?;
+        let category = get_category_id(name, ledger).map_err(|e| self.new_error(e))?;
+        let display_name = ledger.get_transaction_categories().display_name(&category);
+
+        match positional.get(1) {
+            Some(amount) => {
+                let currency = match (ledger.get_budgets().get_budget(&category), args.value("--currency")) {
+                    (_, Some(code)) => Currency::from_code(code).map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?,
+                    (Some(budget), None) => budget.get_currency(),
+                    (None, None) => Currency::USD,
+                };
+                let amount = Money::parse(amount, currency, app.config().number_format)
+                    .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?;
+                let month = parse_month_or_this_month(args.value("--month"), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                ledger.set_budget(&category, month, amount).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Budgeted {} a month for '{}' from {}", amount, display_name, month.format("%B %Y"))?;
+            },
+            None if rollover.is_none() => {
+                return Err(self.new_error(
+                    CmdErrorType::Syntax(
+                        SyntaxErrorType::MissingParam(
+                            "Must provide an amount, --rollover or --no-rollover".to_string()))));
+            },
+            None => {},
+        }
+        if let Some(rollover) = rollover {
+            ledger.set_budget_rollover(&category, rollover).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+            let state = if rollover { "rolls over into the next month" } else { "no longer rolls over" };
+            writeln!(app.out(), "What's left of the budget for '{}' {}", display_name, state)?;
+        }
+        Ok(CmdResult::Ok)
+    }
+
+    fn show_budget(&self, month: Option<&str>, ledger: &Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let month = parse_month_or_this_month(month, app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        let lines = ledger.get_budget_report(month).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        if lines.is_empty() {
+            writeln!(app.out(), "Nothing is budgeted for {}. Set a budget with `budget --set`.", month.format("%B %Y"))?;
+            return Ok(CmdResult::Ok);
+        }
+
+        let categories = ledger.get_transaction_categories();
+        writeln!(app.out(), "Budget for {}", month.format("%B %Y"))?;
+        writeln!(app.out(), "  {:<20}  {:>14}  {:>14}  {:>14}  {:>14}", "Category", "Budgeted", "Rolled over", "Spent", "Remaining")?;
+        // Subcategories are indented under their parents, whose spending includes them
+        for line in lines {
+            let depth = categories.ancestors(&line.category).len();
+            let name = categories.get_category(&line.category).map_or_else(|| line.category.to_string(), |c| c.name().clone());
+            let row = format!(
+                "  {:<20}  {:>14}  {:>14}  {:>14}  {:>14}",
+                format!("{}{}", "  ".repeat(depth), name), line.budgeted.to_string(), line.carried.to_string(),
+                line.spent.to_string(), line.get_remaining().to_string());
+            if line.is_overspent() {
+                writeln!(app.out(), "{}", row.red())?;
+            } else {
+                writeln!(app.out(), "{}", row)?;
+            }
+        }
+        Ok(CmdResult::Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use ledger::transaction::Transaction;
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    #[test]
+    fn set_and_show() {
+        let (mut app, output) = test_app_with_output(&[]);
+
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let groceries = ledger.get_transaction_categories_mut().create_category("Food:Groceries".to_string()).unwrap();
+        for (date, amount) in [(10, -30000), (20, -15000)] {
+            let date = NaiveDate::from_ymd_opt(2024, 3, date).unwrap();
+            let transaction = Transaction::new_on_date(Money::new(amount, Currency::USD), date, "".to_string(), Some(groceries.clone()));
+            ledger.add_transaction(checking, transaction).unwrap();
+        }
+
+        let cmd = Budget::new();
+        assert!(cmd.execute(&["--set", "groceries", "400", "--month", "2024-02"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--set", "food", "1,000", "--month", "2024-03-15", "--rollover"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--set", "food", "--no-rollover"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--set", "food"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--set", "groceries", "100", "--currency", "EUR"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["2024-03"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--remove", "food"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--remove", "food"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--show", "2024-01"], &mut ledger, &mut app).is_ok());
+
+        let output = output.lock().unwrap();
+        assert!(output.contains("Budgeted $400.00 a month for 'Food:Groceries' from February 2024"), "{}", output);
+        assert!(output.contains("What's left of the budget for 'Food' no longer rolls over"), "{}", output);
+        assert!(output.contains("  Food                        $1000.00           $0.00         $450.00         $550.00"), "{}", output);
+        assert!(output.contains("    Groceries                  $400.00           $0.00         $450.00         -$50.00"), "{}", output);
+        assert!(output.contains("Nothing is budgeted for January 2024"), "{}", output);
+    }
+}
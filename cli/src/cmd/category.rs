@@ -1,4 +1,4 @@
-use super::{Cmd, CmdErrorType, SyntaxErrorType};
+use super::{confirm, get_category_id, Cmd, CmdErrorType, ParsedArgs, SyntaxErrorType};
 
 pub struct Category { }
 
@@ -12,10 +12,9 @@ impl Cmd for Category {
             Some(&"--new") => {
                 match args.get(1) {
                     Some(name) => {
-                        let category_id = name.trim().to_ascii_lowercase();
-                        ledger  
+                        ledger
                             .get_transaction_categories_mut()
-                            .create_category(category_id)
+                            .create_category(name.to_string())
                             .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
                         Ok(super::CmdResult::Ok)
                     },
@@ -27,10 +26,57 @@ impl Cmd for Category {
                     },
                 }
             },
+            Some(&"--rename") => {
+                let (Some(old_name), Some(new_name)) = (args.get(1), args.get(2)) else {
+                    return Err(self.new_error(
+                        CmdErrorType::Syntax(
+                            SyntaxErrorType::MissingParam(
+                                "Invalid format. Usage: `category --rename OLD_NAME NEW_NAME`".to_string()))));
+                };
+                let id = get_category_id(old_name, ledger).map_err(|e| self.new_error(e))?;
+                ledger.get_transaction_categories_mut().rename_category(&id, new_name.to_string())
+                    .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Renamed category '{}' to '{}'", old_name, new_name.trim())?;
+                Ok(super::CmdResult::Ok)
+            },
+            Some(&"--merge") => {
+                let (Some(from_name), Some(into_name)) = (args.get(1), args.get(2)) else {
+                    return Err(self.new_error(
+                        CmdErrorType::Syntax(
+                            SyntaxErrorType::MissingParam(
+                                "Invalid format. Usage: `category --merge FROM INTO`".to_string()))));
+                };
+                let from = get_category_id(from_name, ledger).map_err(|e| self.new_error(e))?;
+                let into = get_category_id(into_name, ledger).map_err(|e| self.new_error(e))?;
+                let from_name = ledger.get_transaction_categories().display_name(&from);
+                let into_name = ledger.get_transaction_categories().display_name(&into);
+                let moved = ledger.merge_categories(&from, &into).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Moved {} transactions from '{}' to '{}', and removed '{}'", moved, from_name, into_name, from_name)?;
+                Ok(super::CmdResult::Ok)
+            },
+            Some(&"--delete") => {
+                let args = ParsedArgs::parse(&args[1..], &[], &["--uncategorize"])
+                    .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+                let name = args.positional().first().ok_or(
+                    self.new_error(
+                        CmdErrorType::Syntax(
+                            SyntaxErrorType::MissingParam(
+                                "Must provide transaction category name".to_string()))))?;
+                let id = get_category_id(name, ledger).map_err(|e| self.new_error(e))?;
+                let name = ledger.get_transaction_categories().display_name(&id);
+                if args.flag("--uncategorize")
+                    && !confirm(app, &format!("Delete category '{}' and leave its transactions uncategorized?", name))? {
+                    writeln!(app.out(), "Cancelled")?;
+                    return Ok(super::CmdResult::Ok);
+                }
+                let count = ledger.delete_category(&id, args.flag("--uncategorize"))
+                    .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Deleted category '{}' ({} transactions uncategorized)", name, count)?;
+                Ok(super::CmdResult::Ok)
+            },
             Some(&"--list") => {
-                let categories = ledger.get_transaction_categories();
-                for category in categories.categories() {
-                    writeln!(app.out(), "  {}", category.name())?;
+                for (depth, category) in ledger.get_transaction_categories().tree() {
+                    writeln!(app.out(), "  {}{}", "  ".repeat(depth), category.name())?;
                 }
                 Ok(super::CmdResult::Ok)
             },
@@ -49,10 +95,84 @@ impl Cmd for Category {
 
     fn help_text(&self) -> &'static str {
 "Usage: category [OPTION] CATEGORY_NAME
-Add new transaction caregories or list existing ones
+Add, rename or list transaction categories. Names can have any mix of case, spaces and emoji, but two categories
+with the same parent can't have the same name ignoring case.
+
+Categories can have subcategories. Refer to a subcategory by its path, like Food:Groceries, or just by its name if
+no other category has that name. Transactions can be in a parent category as well as in a subcategory.
 
 Options:
-  --new   Create a new category with CATEGORY_NAME.
-  --list  List existing transaction categories"
+  --new PATH               Create a new category. Parents in PATH that don't exist yet are created too.
+  --rename PATH NAME       Rename a category, keeping its parent. Its transactions keep it.
+  --merge FROM INTO        Move every transaction in FROM to INTO, then remove FROM.
+  --delete PATH [--uncategorize]
+                           Delete a category. Fails if any transactions are still in it, unless --uncategorize is
+                           given, in which case they're left uncategorized (after asking for confirmation).
+                           Categories with subcategories can't be merged or deleted.
+  --list                   List existing transaction categories, with subcategories under their parents"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger::{category::CategoryId, common_types::{Currency, Money}, transaction::Transaction, Ledger};
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    #[test]
+    fn new_and_rename() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let mut ledger = Ledger::new_empty();
+        let cmd = Category::new();
+        assert!(cmd.execute(&["--new", "Eating Out"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--new", "eating out"], &mut ledger, &mut app).is_err());
+
+        assert!(cmd.execute(&["--rename", "eating out", "Restaurants 🍝"], &mut ledger, &mut app).is_ok());
+        let categories = ledger.get_transaction_categories();
+        assert_eq!(categories.display_name(&CategoryId::from("eating-out".to_string())), "Restaurants 🍝");
+        assert!(cmd.execute(&["--rename", "eating out", "Food"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--rename", "restaurants 🍝"], &mut ledger, &mut app).is_err());
+    }
+
+    #[test]
+    fn subcategories() {
+        let (mut app, output) = test_app_with_output(&[]);
+
+        let mut ledger = Ledger::new_empty();
+        let cmd = Category::new();
+        for path in ["Food:Restaurants", "Food:Groceries", "Bills"] {
+            assert!(cmd.execute(&["--new", path], &mut ledger, &mut app).is_ok());
+        }
+        assert!(cmd.execute(&["--rename", "food:groceries", "Supermarket"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--list"], &mut ledger, &mut app).is_ok());
+        assert!(output.lock().unwrap().ends_with("  Bills\n  Food\n    Restaurants\n    Supermarket\n"));
+    }
+
+    #[test]
+    fn merge_and_delete() {
+        let (mut app, _) = test_app_with_output(&["y"]);
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let cmd = Category::new();
+        for path in ["Food:Groceries", "Supermarket", "Rent"] {
+            assert!(cmd.execute(&["--new", path], &mut ledger, &mut app).is_ok());
+        }
+        let supermarket = ledger.get_transaction_categories().find_by_name("supermarket").unwrap().get_id().clone();
+        let time: chrono::DateTime<chrono::Utc> = "2000-1-1T00:00:00Z".parse().expect("Failed to parse");
+        let id = ledger.add_transaction(checking, Transaction::new(
+            Money::new(-100, Currency::USD), time, "Corner store".to_string(), Some(supermarket))).unwrap();
+
+        assert!(cmd.execute(&["--merge", "food", "rent"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--merge", "supermarket", "groceries"], &mut ledger, &mut app).is_ok());
+        let groceries = ledger.get_transaction_categories().find_by_name("Food:Groceries").unwrap().get_id().clone();
+        assert_eq!(ledger.get_transaction(id).unwrap().get_category(), Some(&groceries));
+
+        assert!(cmd.execute(&["--delete", "rent"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--delete", "groceries"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--delete", "groceries", "--uncategorize"], &mut ledger, &mut app).is_ok());
+        assert_eq!(ledger.get_transaction(id).unwrap().get_category(), None);
+        assert!(ledger.get_transaction_categories().find_by_name("groceries").is_none());
     }
-}
\ No newline at end of file
+}
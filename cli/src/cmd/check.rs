@@ -0,0 +1,108 @@
+use ledger::Ledger;
+
+use crate::app::Application;
+
+use super::{Cmd, CmdError, CmdErrorType, CmdResult, ParsedArgs};
+
+/// Command to look for, and optionally fix, broken references in the ledger
+pub struct Check { }
+
+impl Cmd for Check {
+    fn new() -> Check {
+        Check { }
+    }
+
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &[], &["--repair"])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+
+        if args.flag("--repair") {
+            let repaired = ledger.repair();
+            for problem in &repaired {
+                writeln!(app.out(), "  {}", problem.repair_description())?;
+            }
+            writeln!(app.out(), "Repaired {} problems", repaired.len())?;
+            return Ok(CmdResult::Ok);
+        }
+
+        let problems = ledger.check();
+        for problem in &problems {
+            writeln!(app.out(), "  {}", problem)?;
+        }
+        if problems.is_empty() {
+            writeln!(app.out(), "No problems found")?;
+        } else {
+            writeln!(app.out(), "Found {} problems. Run `check --repair` to fix them.", problems.len())?;
+        }
+        Ok(CmdResult::Ok)
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["check"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: check [--repair]
+Looks for references to things that don't exist, like transactions in categories or with payees that have been
+removed. These can turn up in files saved by older versions.
+
+With --repair, fixes them: transactions in missing categories are left uncategorized, transactions with a missing
+payee are matched to a payee again from their description, subcategories whose parent is missing, or that end up
+under themselves, become top-level categories, and schedules that can't come due are deleted."
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ciborium::Value;
+    use ledger::{common_types::{Currency, Money}, transaction::Transaction};
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    // A ledger with a transaction in a category that's gone, like a file saved by an older version might have
+    fn damaged_ledger() -> Ledger {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let food = ledger.get_transaction_categories_mut().create_category("Food".to_string()).unwrap();
+        let time: chrono::DateTime<chrono::Utc> = "2000-1-1T00:00:00Z".parse().expect("Failed to parse");
+        ledger.add_transaction(checking, Transaction::new(Money::new(-500, Currency::USD), time, "Lunch".to_string(), Some(food))).unwrap();
+
+        let mut value = Value::serialized(&ledger).unwrap();
+        let Value::Map(fields) = &mut value else { panic!("Expected a map") };
+        let (_, categories) = fields.iter_mut().find(|(key, _)| key.as_text() == Some("categories")).unwrap();
+        let Value::Map(categories) = categories else { panic!("Expected a map") };
+        categories[0].1 = Value::Map(vec![]);
+        value.deserialized().unwrap()
+    }
+
+    #[test]
+    fn check_and_repair_missing_category() {
+        let (mut app, output) = test_app_with_output(&[]);
+
+        let mut ledger = damaged_ledger();
+        assert!(Check::new().execute(&[], &mut ledger, &mut app).is_ok());
+        assert!(Check::new().execute(&["--repair"], &mut ledger, &mut app).is_ok());
+        assert!(Check::new().execute(&[], &mut ledger, &mut app).is_ok());
+        assert_eq!(ledger.get_accounts()[0].get_transactions()[0].get_category(), None);
+
+        let output = output.lock().unwrap();
+        assert!(output.contains("  Transaction 1 is in category 'food', which doesn't exist\n"), "{}", output);
+        assert!(output.contains("Found 1 problems. Run `check --repair` to fix them."), "{}", output);
+        assert!(output.contains("  Moved transaction 1 from 'food' to uncategorized\nRepaired 1 problems"), "{}", output);
+        assert!(output.ends_with("No problems found\n"), "{}", output);
+    }
+
+    #[test]
+    fn check() {
+        let (mut app, output) = test_app_with_output(&[]);
+
+        let mut ledger = Ledger::new_empty();
+        assert!(Check::new().execute(&[], &mut ledger, &mut app).is_ok());
+        assert!(Check::new().execute(&["--repair"], &mut ledger, &mut app).is_ok());
+        let output = output.lock().unwrap();
+        assert!(output.contains("No problems found"), "{}", output);
+        assert!(output.contains("Repaired 0 problems"), "{}", output);
+    }
+}
@@ -0,0 +1,82 @@
+use ledger::Ledger;
+
+use crate::app::Application;
+
+use super::{Cmd, CmdError, CmdErrorType, CmdResult, SyntaxErrorType};
+
+/// Command to view and change application settings. Settings aren't saved anywhere: they start from the locale each
+/// time the application runs, and anything changed with `--set` only lasts for the session.
+pub struct Config { }
+
+impl Cmd for Config {
+    fn new() -> Config {
+        Config { }
+    }
+
+    fn execute(&self, args: &[&str], _ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        match args.first() {
+            Some(&"--set") => {
+                let (Some(key), Some(value)) = (args.get(1), args.get(2)) else {
+                    return Err(self.new_error(
+                        CmdErrorType::Syntax(SyntaxErrorType::MissingParam(
+                            "Invalid format. Usage: `config --set SETTING VALUE`".to_string()))))
+                };
+                app.config_mut().set(key, value).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Set {} to {} for this session", key, value)?;
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--list") => {
+                for (key, value) in app.config().settings() {
+                    writeln!(app.out(), "  {}\t{}", key, value)?;
+                }
+                Ok(CmdResult::Ok)
+            },
+            Some(unhandled_subcommand) => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::InvalidSubcommand(unhandled_subcommand.to_string()))))
+            }
+            None => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::MissingSubcommand)))
+            }
+        }
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["config"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: config [OPTION]
+View or change settings. Defaults come from the locale, and changes last until the application exits.
+
+Options:
+  --set SETTING VALUE  Change a setting
+  --list               List the current settings
+
+Settings:
+  number-format  standard (1,234.56) or european (1.234,56)
+  date-format    us (MM/DD/YYYY) or eu (DD/MM/YYYY)"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    #[test]
+    fn set_and_list() {
+        let (mut app, output) = test_app_with_output(&[]);
+        let mut ledger = Ledger::new_empty();
+
+        let cmd = Config::new();
+        assert!(cmd.execute(&["--set", "date-format", "eu"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--set", "date-format", "mars"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--set", "date-format"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--list"], &mut ledger, &mut app).is_ok());
+
+        let output = output.lock().unwrap();
+        assert!(output.contains("Set date-format to eu for this session"), "{}", output);
+        assert!(output.contains("  date-format\teu"), "{}", output);
+    }
+}
@@ -0,0 +1,208 @@
+use colored::Colorize;
+use ledger::{common_types::{Currency, Money}, Ledger};
+
+use crate::app::Application;
+
+use super::{get_category_id, parse_month_or_this_month, Cmd, CmdError, CmdErrorType, CmdResult, ParsedArgs, SyntaxErrorType};
+
+/// Command for envelope (zero-based) budgeting: assigning income to category envelopes and moving money between them
+pub struct Envelope { }
+
+impl Cmd for Envelope {
+    fn new() -> Envelope {
+        Envelope { }
+    }
+
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        match args.first() {
+            Some(&"--enable") => {
+                let args = ParsedArgs::parse(&args[1..], &["--currency"], &[])
+                    .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+                let currency = match args.value("--currency") {
+                    Some(code) => Currency::from_code(code).map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?,
+                    None => ledger.get_envelopes().get_currency(),
+                };
+                ledger.set_envelope_mode(Some(currency)).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Envelope budgeting is on, in {}", currency)?;
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--disable") => {
+                ledger.set_envelope_mode(None).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Envelope budgeting is off. Assigned money is kept for if it's turned on again.")?;
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--assign") => {
+                self.assign(&args[1..], ledger, app)
+            },
+            Some(&"--move") => {
+                self.move_money(&args[1..], ledger, app)
+            },
+            Some(&"--show") => {
+                self.show_envelopes(args.get(1).copied(), ledger, app)
+            },
+            Some(unhandled_subcommand) if unhandled_subcommand.starts_with("--") => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::InvalidSubcommand(unhandled_subcommand.to_string()))))
+            },
+            // Without a subcommand, show the envelopes
+            _ => {
+                self.show_envelopes(args.first().copied(), ledger, app)
+            },
+        }
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["envelope", "env"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: envelope [--show] [MONTH]
+       envelope --enable [--currency CURRENCY]
+       envelope --disable
+       envelope --assign CATEGORY AMOUNT [--month MONTH]
+       envelope --move FROM TO AMOUNT [--month MONTH]
+Zero-based budgeting, where every category is an envelope. Money that comes in uncategorized, along with the
+accounts' opening balances, is ready to assign, and the goal is to assign all of it to envelopes, so that ready to
+assign is zero. Spending in a category comes out of its envelope, and whatever's left in an envelope carries over
+into the next month.
+
+Without a subcommand (or with --show), shows what was assigned to each envelope in MONTH (this month by default),
+the net amount of its transactions that month, and what's available in it. Overspent envelopes are shown in red.
+MONTH may be written like 2024-03, or as any date in the month.
+
+Options:
+  --enable                Turn envelope budgeting on. --currency picks the currency envelopes are kept in (USD by
+                          default), which can't change once money has been assigned.
+  --disable               Turn envelope budgeting off, keeping what's been assigned
+  --assign CATEGORY AMOUNT
+                          Assign AMOUNT from ready to assign to CATEGORY's envelope. A negative AMOUNT returns money
+                          to ready to assign.
+  --move FROM TO AMOUNT   Move AMOUNT from one envelope to another
+  --month MONTH           The month to assign or move money in, this month by default"
+    }
+}
+
+impl Envelope {
+    fn assign(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &["--month"], &[])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let (Some(name), Some(amount)) = (args.positional().first(), args.positional().get(1)) else {
+            return Err(self.new_error(
+                CmdErrorType::Syntax(
+                    SyntaxErrorType::MissingParam(
+                        "Invalid format. Usage: `envelope --assign CATEGORY AMOUNT`".to_string()))));
+        };
+        let category = get_category_id(name, ledger).map_err(|e| self.new_error(e))?;
+        let amount = self.parse_amount(amount, ledger, app)?;
+        let month = parse_month_or_this_month(args.value("--month"), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        ledger.assign_to_envelope(&category, month, amount).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(
+            app.out(),
+            "Assigned {} to '{}' in {}",
+            amount, ledger.get_transaction_categories().display_name(&category), month.format("%B %Y"))?;
+        let report = ledger.get_envelope_report(month).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        self.show_ready_to_assign(report.ready_to_assign, app)
+    }
+
+    fn move_money(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &["--month"], &[])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let positional = args.positional();
+        let (Some(from), Some(to), Some(amount)) = (positional.first(), positional.get(1), positional.get(2)) else {
+            return Err(self.new_error(
+                CmdErrorType::Syntax(
+                    SyntaxErrorType::MissingParam(
+                        "Invalid format. Usage: `envelope --move FROM TO AMOUNT`".to_string()))));
+        };
+        let from = get_category_id(from, ledger).map_err(|e| self.new_error(e))?;
+        let to = get_category_id(to, ledger).map_err(|e| self.new_error(e))?;
+        let amount = self.parse_amount(amount, ledger, app)?;
+        let month = parse_month_or_this_month(args.value("--month"), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        ledger.move_between_envelopes(&from, &to, month, amount).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        let categories = ledger.get_transaction_categories();
+        writeln!(app.out(), "Moved {} from '{}' to '{}'", amount, categories.display_name(&from), categories.display_name(&to))?;
+        Ok(CmdResult::Ok)
+    }
+
+    fn show_envelopes(&self, month: Option<&str>, ledger: &Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let month = parse_month_or_this_month(month, app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        let report = ledger.get_envelope_report(month).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        let categories = ledger.get_transaction_categories();
+        writeln!(app.out(), "Envelopes for {}", month.format("%B %Y"))?;
+        writeln!(app.out(), "  {:<20}  {:>14}  {:>14}  {:>14}", "Envelope", "Assigned", "Activity", "Available")?;
+        for line in &report.envelopes {
+            let row = format!(
+                "  {:<20}  {:>14}  {:>14}  {:>14}",
+                categories.display_name(&line.category), line.assigned.to_string(), line.activity.to_string(),
+                line.available.to_string());
+            if line.available.is_negative() {
+                writeln!(app.out(), "{}", row.red())?;
+            } else {
+                writeln!(app.out(), "{}", row)?;
+            }
+        }
+        self.show_ready_to_assign(report.ready_to_assign, app)
+    }
+
+    // Zero is the goal, so anything else stands out
+    fn show_ready_to_assign(&self, ready: Money, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let amount = ready.to_string();
+        let amount = if ready.is_zero() {
+            amount.green()
+        } else if ready.is_negative() {
+            amount.red()
+        } else {
+            amount.yellow()
+        };
+        writeln!(app.out(), "Ready to assign: {}", amount)?;
+        Ok(CmdResult::Ok)
+    }
+
+    fn parse_amount(&self, amount: &str, ledger: &Ledger, app: &Application) -> Result<Money, CmdError> {
+        Money::parse(amount, ledger.get_envelopes().get_currency(), app.config().number_format)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use ledger::transaction::Transaction;
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    #[test]
+    fn assign_move_and_show() {
+        let (mut app, output) = test_app_with_output(&[]);
+
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let food = ledger.get_transaction_categories_mut().create_category("Food".to_string()).unwrap();
+        ledger.get_transaction_categories_mut().create_category("Rent".to_string()).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        for (amount, category) in [(200000, None), (-45000, Some(food))] {
+            ledger.add_transaction(checking, Transaction::new_on_date(Money::new(amount, Currency::USD), date, "".to_string(), category)).unwrap();
+        }
+
+        let cmd = Envelope::new();
+        assert!(cmd.execute(&["--assign", "food", "400", "--month", "2024-03"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--enable"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--assign", "food", "400", "--month", "2024-03"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--assign", "rent", "1,600", "--month", "2024-03"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--move", "rent", "food", "50", "--month", "2024-03"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--move", "rent", "food", "-50"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--enable", "--currency", "EUR"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["2024-03"], &mut ledger, &mut app).is_ok());
+
+        let output = output.lock().unwrap();
+        assert!(output.contains("Assigned $400.00 to 'Food' in March 2024"), "{}", output);
+        assert!(output.contains("Moved $50.00 from 'Rent' to 'Food'"), "{}", output);
+        assert!(output.contains("  Food                         $450.00        -$450.00           $0.00"), "{}", output);
+        assert!(output.contains("  Rent                        $1550.00           $0.00        $1550.00"), "{}", output);
+        assert!(output.ends_with("Ready to assign: $0.00\n"), "{}", output);
+    }
+}
@@ -2,13 +2,13 @@ use ledger::Ledger;
 
 use crate::{cmd::CmdResult, app::Application};
 
-use super::{CmdError, CmdErrorType};
+use super::{schedule::Schedule, Cmd, CmdError, CmdErrorType};
 
 /// Command to load user data from disk
 pub struct Load {
 }
 
-impl super::Cmd for Load {
+impl Cmd for Load {
     fn new() -> Load {
         Load{}
     }
@@ -24,6 +24,9 @@ impl super::Cmd for Load {
             },
         }
         writeln!(app.out(), "Loaded!")?;
+
+        // Anything that came due while the ledger wasn't open gets posted now
+        Schedule::new().post_due(ledger, app)?;
         Ok(CmdResult::Ok)
     }
 
@@ -33,13 +36,16 @@ impl super::Cmd for Load {
 
     fn help_text(&self) -> &'static str {
 "Usage: load
-Loads saved data from disk."
+Loads saved data from disk, then posts any scheduled transactions that have come due since it was last loaded."
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{cmd::Cmd, store::mock::MockFileStore, ui::MockTerminalInterface};
+    use crate::{cmd::test_app_with_store_and_output, config::Config, store::mock::MockFileStore, ui::MockTerminalInterface};
+
+    use chrono::NaiveDate;
+    use ledger::{common_types::{Currency, Money}, schedule::{Frequency, ScheduleEnd}};
 
     use super::*;
 
@@ -52,18 +58,71 @@ mod tests {
             .times(1)
             .returning(|| {
                 let mut test_ledger = Ledger::new_empty();
-                test_ledger.add_new_account(String::from("test_account"));
+                test_ledger.add_new_account(String::from("test_account"), Currency::USD).unwrap();
                 Ok(test_ledger)
             });
 
         interface.expect_write()
             .returning(|s| Ok(s.len()));
 
-        let mut application_mock = Application::new(interface, file_store);
+        let mut application_mock = Application::new(interface, file_store, Config::default());
 
         let load_cmd = Load::new();
         let mut actual_ledger = Ledger::new_empty();
-        assert!(load_cmd.execute(&vec![], &mut actual_ledger, &mut application_mock).is_ok());
-        assert!(actual_ledger.get_account_by_name_mut(&String::from("test_account")).is_some());
+        assert!(load_cmd.execute(&[], &mut actual_ledger, &mut application_mock).is_ok());
+        assert!(actual_ledger.get_account_by_name_mut("test_account").is_some());
+    }
+
+    #[test]
+    fn load_posts_due_schedules() {
+        let mut file_store = MockFileStore::default();
+
+        file_store.expect_load_ledger()
+            .times(1)
+            .returning(|| {
+                let mut test_ledger = Ledger::new_empty();
+                let account = test_ledger.add_new_account(String::from("Checking"), Currency::USD).unwrap();
+                let start = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+                let schedule = ledger::schedule::Schedule::new(
+                    account, Money::new(-999, Currency::USD), "Music".to_string(), None, Frequency::Yearly, start);
+                test_ledger.add_schedule(schedule.with_end(ScheduleEnd::After(2))).unwrap();
+                Ok(test_ledger)
+            });
+
+        let (mut application_mock, output) = test_app_with_store_and_output(file_store, &[]);
+        let mut actual_ledger = Ledger::new_empty();
+        assert!(Load::new().execute(&[], &mut actual_ledger, &mut application_mock).is_ok());
+        assert_eq!(actual_ledger.get_accounts()[0].get_transactions().len(), 2);
+        let output = output.lock().unwrap();
+        assert!(output.contains("Posted 2 scheduled transactions that came due:"), "{}", output);
+        assert!(output.contains("  2001-01-01  Checking                 -$9.99  Music"), "{}", output);
+    }
+
+    #[test]
+    fn load_reports_schedules_it_cannot_post() {
+        let mut file_store = MockFileStore::default();
+
+        file_store.expect_load_ledger()
+            .times(1)
+            .returning(|| {
+                let mut test_ledger = Ledger::new_empty();
+                let account = test_ledger.add_new_account(String::from("Checking"), Currency::USD).unwrap();
+                let start = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+                let schedule = ledger::schedule::Schedule::new(
+                    account, Money::new(-999, Currency::USD), "Music".to_string(), None, Frequency::Yearly, start);
+                test_ledger.add_schedule(schedule.with_end(ScheduleEnd::After(2))).unwrap();
+                // The schedule keeps the account open, and double-entry mode keeps it from posting
+                assert!(test_ledger.close_account(account).is_err());
+                test_ledger.set_double_entry(true);
+                Ok(test_ledger)
+            });
+
+        let (mut application_mock, output) = test_app_with_store_and_output(file_store, &[]);
+        let mut actual_ledger = Ledger::new_empty();
+        assert!(Load::new().execute(&[], &mut actual_ledger, &mut application_mock).is_ok());
+        assert!(actual_ledger.get_accounts()[0].get_transactions().is_empty());
+        assert_eq!(actual_ledger.get_due_schedules(NaiveDate::from_ymd_opt(2001, 1, 1).unwrap()).len(), 2);
+        let output = output.lock().unwrap();
+        assert!(output.contains("Loaded!\nCouldn't post 2 scheduled transactions that came due:\n  2000-01-01  schedule 1: "), "{}", output);
     }
 }
@@ -1,15 +1,30 @@
 
 use std::{error::Error, fmt::Display};
 
-use ledger::Ledger;
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use ledger::{account::Account, category::CategoryId, Ledger};
 use super::app::Application;
+use super::dates;
+use super::ui::InputEvent;
 
 pub mod account;
+pub mod balance;
+pub mod budget;
 pub mod category;
+pub mod check;
+pub mod config;
+pub mod envelope;
 pub mod exit;
 pub mod load;
+pub mod payee;
+pub mod rate;
+pub mod reconcile;
+pub mod report;
+pub mod schedule;
 pub mod store;
+pub mod tag;
 pub mod transaction;
+pub mod transfer;
 
 #[derive(Debug)]
 pub struct CmdError {    
@@ -31,6 +46,7 @@ pub enum CmdErrorType {
 pub enum SyntaxErrorType {
     MissingSubcommand, 
     InvalidSubcommand(String),
+    InvalidOption(String),
     MissingParam(String),
 }
 
@@ -65,6 +81,9 @@ impl Display for CmdError {
                     invalid_cmd,
                     name)
             },
+            CmdErrorType::Syntax(SyntaxErrorType::InvalidOption(invalid_option)) => {
+                write!(f, "Invalid option '{}' for command {}.", invalid_option, name)
+            },
             CmdErrorType::Syntax(SyntaxErrorType::MissingParam(msg)) => {
                 write!(f, "{}", msg)
             }
@@ -85,10 +104,174 @@ pub trait Cmd {
     fn help_text(&self) -> &'static str;
 
     fn primary_name(&self) -> &'static str {
-        return self.names()[0];
+        self.names()[0]
     }
 
     fn new_error(&self, error_type: CmdErrorType) -> CmdError {
         CmdError { cmd_name: Some(self.primary_name().to_string()), error_type }
     }
-}
\ No newline at end of file
+}
+
+/// Look up the account named by a command argument, failing if the argument is missing or there's no such account.
+pub(crate) fn get_account<'a>(name: Option<&str>, ledger: &'a Ledger) -> Result<&'a Account, CmdErrorType> {
+    let name = name.ok_or(CmdErrorType::Syntax(SyntaxErrorType::MissingParam("An account name is required".to_string())))?;
+    ledger.get_account_by_name(name)
+        .ok_or_else(|| CmdErrorType::Argument(format!("Could not find account named '{}'", name)))
+}
+
+/// Look up the ID of the category named by a command argument.
+pub(crate) fn get_category_id(name: &str, ledger: &Ledger) -> Result<CategoryId, CmdErrorType> {
+    ledger.get_transaction_categories().find_by_name(name)
+        .map(|c| c.get_id().clone())
+        .ok_or_else(|| CmdErrorType::Argument(format!("No category named '{}'", name)))
+}
+
+/// Parse a date given as a command argument, or use today's date if it was omitted.
+pub fn parse_date_or_today(arg: Option<&str>, app: &Application) -> Result<NaiveDate, String> {
+    let today = chrono::Local::now().date_naive();
+    match arg {
+        Some(s) => dates::parse_date(s, today, app.config().date_format),
+        None => Ok(today),
+    }
+}
+
+/// Parse a month given as a command argument, like 2024-03 or any date in the month, or use this month if it was
+/// omitted. Returns the first day of the month.
+pub fn parse_month_or_this_month(arg: Option<&str>, app: &Application) -> Result<NaiveDate, String> {
+    let date = match arg.and_then(|a| NaiveDate::parse_from_str(&format!("{}-01", a.trim()), "%Y-%m-%d").ok()) {
+        Some(date) => date,
+        None => parse_date_or_today(arg, app)?,
+    };
+    Ok(ledger::budget::month_start(date))
+}
+
+/// The time to record for something that happened on the date given as a command argument, or right now if the
+/// date was omitted.
+pub fn parse_time_or_now(arg: Option<&str>, app: &Application) -> Result<DateTime<FixedOffset>, String> {
+    let now = chrono::Local::now();
+    match arg {
+        Some(_) => Ok(dates::time_on_date(parse_date_or_today(arg, app)?, now)),
+        None => Ok(now.fixed_offset()),
+    }
+}
+
+/// Command arguments split into positional arguments and `--name [VALUE]` options. 
+pub struct ParsedArgs<'a> {
+    positional: Vec<&'a str>,
+    options: Vec<(&'a str, Option<&'a str>)>,
+}
+
+impl<'a> ParsedArgs<'a> {
+    /// `value_options` take the argument after them as their value, while `flag_options` stand alone. Any other
+    /// argument starting with `--` is an error. Options may be given more than once. 
+    pub fn parse(args: &[&'a str], value_options: &[&str], flag_options: &[&str]) -> Result<ParsedArgs<'a>, SyntaxErrorType> {
+        let mut positional = Vec::new();
+        let mut options = Vec::new();
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if value_options.contains(arg) {
+                let value = iter.next().ok_or_else(
+                    || SyntaxErrorType::MissingParam(format!("Option '{}' needs a value", arg)))?;
+                options.push((*arg, Some(*value)));
+            } else if flag_options.contains(arg) {
+                options.push((*arg, None));
+            } else if arg.starts_with("--") {
+                return Err(SyntaxErrorType::InvalidOption(arg.to_string()));
+            } else {
+                positional.push(*arg);
+            }
+        }
+
+        Ok(ParsedArgs { positional, options })
+    }
+
+    pub fn positional(&self) -> &[&'a str] {
+        &self.positional
+    }
+
+    /// The value of the last occurrence of an option. 
+    pub fn value(&self, name: &str) -> Option<&'a str> {
+        self.values(name).pop()
+    }
+
+    /// The values of every occurrence of an option, in order. 
+    pub fn values(&self, name: &str) -> Vec<&'a str> {
+        self.options.iter().filter(|(n, _)| *n == name).filter_map(|(_, v)| *v).collect()
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(n, _)| *n == name)
+    }
+}
+
+/// Ask the user a yes/no question. Anything other than "y" or "yes" counts as no. 
+pub fn confirm(app: &mut Application, prompt: &str) -> Result<bool, CmdError> {
+    writeln!(app.out(), "{} [y/N]", prompt)?;
+    match app.interface().get_event() {
+        InputEvent::Text(answer) => {
+            let answer = answer.trim();
+            Ok(answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes"))
+        },
+        _ => Ok(false),
+    }
+}
+
+/// An application for command tests that keeps everything written to it, and answers prompts with `answers` in
+/// order. Once they run out, prompts are interrupted.
+#[cfg(test)]
+pub(crate) fn test_app_with_output(answers: &[&str]) -> (Application, std::sync::Arc<std::sync::Mutex<String>>) {
+    test_app_with_store_and_output(crate::store::mock::MockFileStore::default(), answers)
+}
+
+#[cfg(test)]
+pub(crate) fn test_app_with_store_and_output(
+    file_store: crate::store::mock::MockFileStore,
+    answers: &[&str],
+) -> (Application, std::sync::Arc<std::sync::Mutex<String>>) {
+    let output = std::sync::Arc::new(std::sync::Mutex::new(String::new()));
+    let mut interface = crate::ui::MockTerminalInterface::new();
+    let captured = output.clone();
+    interface.expect_write().returning(move |s| {
+        captured.lock().unwrap().push_str(&String::from_utf8_lossy(s));
+        Ok(s.len())
+    });
+    let mut answers: Vec<String> = answers.iter().map(|a| a.to_string()).collect();
+    answers.reverse();
+    interface.expect_get_event().returning(move || answers.pop().map(InputEvent::Text).unwrap_or(InputEvent::Interrupt));
+    (Application::new(interface, file_store, crate::config::Config::default()), output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let args = ParsedArgs::parse(
+            &["one", "--value", "-12", "two", "--flag", "--value", "x"],
+            &["--value"],
+            &["--flag", "--other"]).unwrap();
+
+        assert_eq!(args.positional(), &["one", "two"]);
+        assert_eq!(args.value("--value"), Some("x"));
+        assert_eq!(args.values("--value"), vec!["-12", "x"]);
+        assert!(args.flag("--flag"));
+        assert!(!args.flag("--other"));
+        assert_eq!(args.value("--missing"), None);
+    }
+
+    #[test]
+    fn parse_args_unknown_option() {
+        assert!(matches!(
+            ParsedArgs::parse(&["--nope"], &[], &[]),
+            Err(SyntaxErrorType::InvalidOption(_))));
+    }
+
+    #[test]
+    fn parse_args_missing_value() {
+        assert!(matches!(
+            ParsedArgs::parse(&["--value"], &["--value"], &[]),
+            Err(SyntaxErrorType::MissingParam(_))));
+    }
+}
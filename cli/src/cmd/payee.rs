@@ -0,0 +1,159 @@
+use ledger::{payee::PayeeId, Ledger};
+
+use crate::app::Application;
+
+use super::{Cmd, CmdError, CmdErrorType, CmdResult, ParsedArgs, SyntaxErrorType};
+
+/// Command to manage payees and the description patterns that map transactions to them
+pub struct Payee { }
+
+impl Cmd for Payee {
+    fn new() -> Payee {
+        Payee { }
+    }
+
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        match args.first() {
+            Some(&"--new") => {
+                let args = ParsedArgs::parse(&args[1..], &["--alias"], &[])
+                    .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+                let name = args.positional().first().ok_or(
+                    self.new_error(
+                        CmdErrorType::Syntax(
+                            SyntaxErrorType::MissingParam(
+                                "Must provide a payee name".to_string()))))?;
+                let aliases = args.values("--alias").into_iter().map(String::from).collect();
+                let before = self.count_matched(ledger);
+                ledger.add_payee(name.to_string(), aliases).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Added payee '{}', matching {} transactions", name.trim(), self.count_matched(ledger) - before)?;
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--alias") => {
+                let (Some(name), Some(pattern)) = (args.get(1), args.get(2)) else {
+                    return Err(self.new_error(
+                        CmdErrorType::Syntax(
+                            SyntaxErrorType::MissingParam(
+                                "Invalid format. Usage: `payee --alias NAME PATTERN`".to_string()))));
+                };
+                let id = self.get_payee_id(name, ledger)?;
+                let matched = ledger.add_payee_alias(id, pattern.to_string())
+                    .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Added alias '{}', matching {} more transactions", pattern, matched)?;
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--rename") => {
+                let (Some(old_name), Some(new_name)) = (args.get(1), args.get(2)) else {
+                    return Err(self.new_error(
+                        CmdErrorType::Syntax(
+                            SyntaxErrorType::MissingParam(
+                                "Invalid format. Usage: `payee --rename OLD_NAME NEW_NAME`".to_string()))));
+                };
+                let id = self.get_payee_id(old_name, ledger)?;
+                ledger.rename_payee(id, new_name.to_string()).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Renamed payee '{}' to '{}'", old_name, new_name.trim())?;
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--merge") => {
+                let (Some(from_name), Some(into_name)) = (args.get(1), args.get(2)) else {
+                    return Err(self.new_error(
+                        CmdErrorType::Syntax(
+                            SyntaxErrorType::MissingParam(
+                                "Invalid format. Usage: `payee --merge FROM INTO`".to_string()))));
+                };
+                let from = self.get_payee_id(from_name, ledger)?;
+                let into = self.get_payee_id(into_name, ledger)?;
+                let moved = ledger.merge_payees(from, into).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Moved {} transactions from '{}' to '{}', and removed '{}'", moved, from_name, into_name, from_name)?;
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--list") => {
+                for payee in ledger.get_payees().get_payees() {
+                    let count = ledger.get_accounts().iter()
+                        .flat_map(|a| a.get_transactions())
+                        .filter(|t| t.get_payee() == Some(payee.get_id()))
+                        .count();
+                    writeln!(app.out(), "  {:<30}  {:>5}  {}", payee.get_name(), count, payee.get_aliases().join(", "))?;
+                }
+                Ok(CmdResult::Ok)
+            },
+            Some(unhandled_subcommand) => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::InvalidSubcommand(unhandled_subcommand.to_string()))))
+            },
+            None => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::MissingSubcommand)))
+            },
+        }
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["payee"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: payee [OPTION]
+Manages payees, the people and businesses money goes to or comes from. Banks describe the same payee in many ways,
+so each payee has alias patterns. A transaction whose description is the payee's name or matches one of its aliases
+is given that payee when it's entered, and reports can group by payee. Names and patterns ignore case, and a * in a
+pattern matches anything, so `amzn mktp*` matches \"AMZN Mktp US*2K3\".
+
+Options:
+  --new NAME [--alias PATTERN]...
+                           Add a payee. Transactions without a payee that match it are given it.
+  --alias NAME PATTERN     Add an alias to a payee. Transactions without a payee that match it are given it.
+  --rename OLD_NAME NEW_NAME
+                           Rename a payee. Its transactions keep it.
+  --merge FROM INTO        Move every transaction from FROM to INTO, then remove FROM. Descriptions that matched
+                           FROM match INTO from then on.
+  --list                   List payees with how many transactions they have and their aliases"
+    }
+}
+
+impl Payee {
+    fn get_payee_id(&self, name: &str, ledger: &Ledger) -> Result<PayeeId, CmdError> {
+        ledger.get_payees().find_by_name(name)
+            .map(|p| p.get_id())
+            .ok_or(self.new_error(CmdErrorType::Argument(format!("No payee named '{}'", name))))
+    }
+
+    fn count_matched(&self, ledger: &Ledger) -> usize {
+        ledger.get_accounts().iter().flat_map(|a| a.get_transactions()).filter(|t| t.get_payee().is_some()).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger::{common_types::{Currency, Money}, transaction::Transaction};
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    #[test]
+    fn new_alias_merge_and_list() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let time: chrono::DateTime<chrono::Utc> = "2000-1-1T00:00:00Z".parse().expect("Failed to parse");
+        for description in ["AMZN Mktp US*2K3", "Amazon.com", "amazon", "www.amazon.co.uk"] {
+            let transaction = Transaction::new(Money::new(-100, Currency::USD), time, description.to_string(), None);
+            ledger.add_transaction(checking, transaction).unwrap();
+        }
+
+        let (mut app, output) = test_app_with_output(&[]);
+
+        let cmd = Payee::new();
+        assert!(cmd.execute(&["--new", "Amazon", "--alias", "amzn mktp*"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--new", "Amazon.com"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--new", "amazon"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--alias", "amazon", "*amazon.co.uk"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--merge", "amazon.com", "amazon"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--rename", "amazon", "Amazon 📦"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--rename", "amazon.com", "Amazon"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--list"], &mut ledger, &mut app).is_ok());
+
+        let output = output.lock().unwrap();
+        assert!(output.contains("Added payee 'Amazon', matching 2 transactions"), "{}", output);
+        assert!(output.contains("Added alias '*amazon.co.uk', matching 1 more transactions"), "{}", output);
+        assert!(output.contains("Moved 1 transactions from 'amazon.com' to 'amazon'"), "{}", output);
+        assert!(output.ends_with("  Amazon 📦                            4  amzn mktp*, *amazon.co.uk, Amazon.com\n"), "{}", output);
+    }
+}
@@ -0,0 +1,91 @@
+use std::{fs::File, io::BufReader};
+
+use ledger::{common_types::Currency, Ledger};
+
+use crate::app::Application;
+
+use super::{parse_date_or_today, Cmd, CmdError, CmdErrorType, CmdResult, SyntaxErrorType};
+
+/// Command to manage the exchange rates used to convert between currencies
+pub struct Rate { }
+
+impl Cmd for Rate {
+    fn new() -> Rate {
+        Rate { }
+    }
+
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        match args.first() {
+            Some(&"--set") => {
+                self.set_rate(&args[1..], ledger, app)
+            },
+            Some(&"--import") => {
+                self.import_rates(&args[1..], ledger, app)
+            },
+            Some(&"--list") => {
+                for rate in ledger.get_exchange_rates().rates() {
+                    writeln!(app.out(), "  {}\t{}/{}\t{}", rate.get_date(), rate.get_from(), rate.get_to(), rate.get_rate())?;
+                }
+                Ok(CmdResult::Ok)
+            },
+            Some(unhandled_subcommand) => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::InvalidSubcommand(unhandled_subcommand.to_string()))))
+            }
+            None => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::MissingSubcommand)))
+            }
+        }
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["rate", "fx"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: rate [OPTION]
+Manage exchange rates between currencies.
+
+Options:
+  --set FROM TO RATE [DATE]  Set the number of TO units one FROM unit buys on DATE (today by default)
+  --import FILE              Import rates from a CSV file with the columns date,from,to,rate
+  --list                     List the known exchange rates"
+    }
+}
+
+impl Rate {
+
+    fn set_rate(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        if args.len() < 3 {
+            return Err(self.new_error(
+                CmdErrorType::Syntax(SyntaxErrorType::MissingParam(
+                    "Invalid format. Usage: `rate --set FROM TO RATE [DATE]`".to_string()))))
+        }
+
+        let from = Currency::from_code(args[0]).map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?;
+        let to = Currency::from_code(args[1]).map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?;
+        let rate: f64 = args[2].parse().map_err(
+            |_| self.new_error(CmdErrorType::Argument(format!("Invalid exchange rate '{}'", args[2]))))?;
+        let date = parse_date_or_today(args.get(3).copied(), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        ledger.get_exchange_rates_mut()
+            .set_rate(from, to, date, rate)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "1 {} = {} {} on {}", from, rate, to, date)?;
+        Ok(CmdResult::Ok)
+    }
+
+    fn import_rates(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let path = args.first().ok_or(
+            self.new_error(
+                CmdErrorType::Syntax(
+                    SyntaxErrorType::MissingParam(
+                        "Importing exchange rates requires a file name".to_string()))))?;
+
+        let file = File::open(path).map_err(|e| self.new_error(CmdErrorType::Dependency(Box::new(e))))?;
+        let count = ledger.get_exchange_rates_mut()
+            .import_csv(BufReader::new(file))
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "Imported {} exchange rates", count)?;
+        Ok(CmdResult::Ok)
+    }
+}
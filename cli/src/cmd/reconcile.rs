@@ -0,0 +1,198 @@
+use ledger::{common_types::Money, transaction::{TransactionId, TransactionStatus}, Ledger};
+
+use crate::{app::Application, ui::InputEvent};
+
+use super::{get_account, parse_date_or_today, Cmd, CmdError, CmdErrorType, CmdResult, ParsedArgs, SyntaxErrorType};
+
+/// Command to check an account against a bank statement
+pub struct Reconcile { }
+
+impl Cmd for Reconcile {
+    fn new() -> Reconcile {
+        Reconcile { }
+    }
+
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &["--statement-date", "--statement-balance"], &[])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let (Some(account_name), Some(date), Some(balance)) = (
+            args.positional().first(),
+            args.value("--statement-date"),
+            args.value("--statement-balance"),
+        ) else {
+            return Err(self.new_error(
+                CmdErrorType::Syntax(SyntaxErrorType::MissingParam(
+                    "Invalid format. Usage: `reconcile ACCOUNT --statement-date DATE --statement-balance AMOUNT`".to_string()))));
+        };
+
+        let account = get_account(Some(account_name), ledger).map_err(|e| self.new_error(e))?;
+        let account_id = account.get_id();
+        let statement_date = parse_date_or_today(Some(date), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        let statement_balance = Money::parse(balance, account.get_currency(), app.config().number_format)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?;
+        let target = account.get_type().from_stated_balance(statement_balance)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        let mut cleared_balance = account.cleared_balance_at(statement_date);
+        let unreconciled: Vec<(TransactionId, String, Money, bool)> = account.get_running_balances().into_iter()
+            .map(|(t, _)| t)
+            .filter(|t| t.get_date() <= statement_date && t.get_status() != TransactionStatus::Reconciled)
+            .map(|t| (
+                t.get_id(),
+                format!("{:>5}  {:<10}  {:<30}  {:>14}", t.get_id(), t.get_date(), t.get_description(), t.get_amount().to_string()),
+                *t.get_amount(),
+                t.get_status() == TransactionStatus::Cleared))
+            .collect();
+        let difference_from = |cleared_balance: Money| target.checked_sub(cleared_balance)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())));
+
+        writeln!(app.out(), "Reconciling '{}' against the statement for {}", account.get_name(), statement_date)?;
+        writeln!(app.out(), "For each transaction, answer y if it's on the statement, n if it isn't, or q to stop. Leave the answer empty to keep it as it is.")?;
+        'transactions: for (id, row, amount, was_cleared) in unreconciled {
+            let mark = if was_cleared { "c" } else { " " };
+            writeln!(app.out(), "  {}  {}  (difference: {})", mark, row, difference_from(cleared_balance)?)?;
+            writeln!(app.out(), "Cleared? [y/n/q]")?;
+            let status = loop {
+                match app.interface().get_event() {
+                    InputEvent::Text(answer) => match answer.trim().to_ascii_lowercase().as_str() {
+                        "y" | "yes" => break TransactionStatus::Cleared,
+                        "n" | "no" => break TransactionStatus::Pending,
+                        "" => continue 'transactions,
+                        "q" | "quit" => break 'transactions,
+                        _ => writeln!(app.out(), "Please answer y, n or q. Cleared? [y/n/q]")?,
+                    },
+                    InputEvent::Interrupt => break 'transactions,
+                    _ => {},
+                }
+            };
+            let is_cleared = status == TransactionStatus::Cleared;
+            if is_cleared != was_cleared {
+                ledger.set_transaction_status(id, status).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                cleared_balance = if is_cleared { cleared_balance.checked_add(amount) } else { cleared_balance.checked_sub(amount) }
+                    .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?;
+            }
+        }
+
+        let difference = difference_from(cleared_balance)?;
+        if !difference.is_zero() {
+            writeln!(
+                app.out(),
+                "The cleared balance is {}, which is {} off the statement. Cleared transactions stay cleared, so you can \
+                carry on later.",
+                cleared_balance, difference)?;
+            return Ok(CmdResult::Ok);
+        }
+        let count = ledger.reconcile(account_id, statement_date, statement_balance)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "Balanced with the statement. Reconciled and locked {} transactions.", count)?;
+        Ok(CmdResult::Ok)
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["reconcile"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: reconcile ACCOUNT --statement-date DATE --statement-balance AMOUNT
+Checks ACCOUNT against a bank statement. Goes through each transaction up to DATE that hasn't been reconciled yet,
+asking whether it's on the statement, and shows how far the cleared transactions are from AMOUNT. For credit cards
+and loans, AMOUNT is the amount owed.
+
+Once the difference is zero, the cleared transactions are marked reconciled and locked, so they can't be edited or
+deleted by accident. To change one anyway, first mark it pending with `transaction --edit ID --status pending`.
+
+If the difference isn't zero, the answers are kept, so running reconcile again picks up where it left off."
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger::common_types::Currency;
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    fn test_ledger() -> (Ledger, Vec<TransactionId>) {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let ids = [("2024-01-05", 10000), ("2024-01-10", -2500), ("2024-01-20", -1000), ("2024-02-02", -500)].iter()
+            .map(|(date, amount)| {
+                let date = date.parse().unwrap();
+                let transaction = ledger::transaction::Transaction::new_on_date(
+                    Money::new(*amount, Currency::USD), date, "Widgets Inc.".to_string(), None);
+                ledger.add_transaction(checking, transaction).unwrap()
+            })
+            .collect();
+        (ledger, ids)
+    }
+
+    const ARGS: [&str; 5] = ["checking", "--statement-date", "2024-01-31", "--statement-balance", "75"];
+
+    #[test]
+    fn reconcile() {
+        let (mut ledger, ids) = test_ledger();
+        let (mut app, output) = test_app_with_output(&["y", "y", "n"]);
+        assert!(Reconcile::new().execute(&ARGS, &mut ledger, &mut app).is_ok());
+
+        let output = output.lock().unwrap();
+        assert!(output.contains("(difference: $75.00)"), "{}", output);
+        assert!(output.contains("(difference: -$25.00)"), "{}", output);
+        assert!(output.contains("Reconciled and locked 2 transactions"), "{}", output);
+        let status = |id| ledger.get_transaction(id).unwrap().get_status();
+        assert_eq!(status(ids[0]), TransactionStatus::Reconciled);
+        assert_eq!(status(ids[1]), TransactionStatus::Reconciled);
+        assert_eq!(status(ids[2]), TransactionStatus::Pending);
+        assert_eq!(status(ids[3]), TransactionStatus::Pending);
+        assert!(ledger.delete_transaction(ids[0]).is_err());
+    }
+
+    #[test]
+    fn reconcile_with_difference_keeps_progress() {
+        let (mut ledger, ids) = test_ledger();
+        let (mut app, output) = test_app_with_output(&["y", "q"]);
+        assert!(Reconcile::new().execute(&ARGS, &mut ledger, &mut app).is_ok());
+        assert!(output.lock().unwrap().contains("which is -$25.00 off the statement"));
+        assert_eq!(ledger.get_transaction(ids[0]).unwrap().get_status(), TransactionStatus::Cleared);
+
+        // The second run starts with the first transaction already cleared
+        let (mut app, output) = test_app_with_output(&["", "y", ""]);
+        assert!(Reconcile::new().execute(&ARGS, &mut ledger, &mut app).is_ok());
+        assert!(output.lock().unwrap().contains("Reconciled and locked 2 transactions"));
+
+        // Nothing left to go through, and nothing to reconcile
+        let (mut app, output) = test_app_with_output(&[]);
+        assert!(Reconcile::new().execute(&ARGS, &mut ledger, &mut app).is_ok());
+        assert!(output.lock().unwrap().contains("Reconciled and locked 0 transactions"));
+    }
+
+    #[test]
+    fn reconcile_asks_again_after_unknown_answer() {
+        let (mut ledger, ids) = test_ledger();
+        let (mut app, output) = test_app_with_output(&["maybe", "y", "y", "n"]);
+        assert!(Reconcile::new().execute(&ARGS, &mut ledger, &mut app).is_ok());
+
+        let output = output.lock().unwrap();
+        assert!(output.contains("Please answer y, n or q"), "{}", output);
+        assert!(output.contains("Reconciled and locked 2 transactions"), "{}", output);
+        assert_eq!(ledger.get_transaction(ids[0]).unwrap().get_status(), TransactionStatus::Reconciled);
+    }
+
+    #[test]
+    fn reconcile_difference_overflow() {
+        let (mut ledger, _) = test_ledger();
+        let (mut app, _) = test_app_with_output(&["y"]);
+        let args = ["checking", "--statement-date", "2024-01-31", "--statement-balance", "-92233720368547758"];
+        assert!(Reconcile::new().execute(&args, &mut ledger, &mut app).is_err());
+    }
+
+    #[test]
+    fn reconcile_invalid() {
+        let (mut ledger, _) = test_ledger();
+        let (mut app, _) = test_app_with_output(&[]);
+        let cmd = Reconcile::new();
+        assert!(cmd.execute(&["checking", "--statement-date", "2024-01-31"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["savings", "--statement-date", "2024-01-31", "--statement-balance", "75"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["checking", "--statement-date", "2024-01-31", "--statement-balance", "lots"], &mut ledger, &mut app).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use ledger::{common_types::Currency, transaction::TransactionFilter, Ledger};
+
+use crate::app::Application;
+
+use super::{parse_date_or_today, Cmd, CmdError, CmdErrorType, CmdResult, ParsedArgs};
+
+/// Command to summarize income and expenses
+pub struct Report { }
+
+impl Cmd for Report {
+    fn new() -> Report {
+        Report { }
+    }
+
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &["--from", "--to", "--currency", "--tag"], &["--by-payee"])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+
+        let currency = match args.value("--currency") {
+            Some(code) => Currency::from_code(code).map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?,
+            None => Currency::USD,
+        };
+        let parse_date = |arg: Option<&str>| {
+            arg.map(|a| parse_date_or_today(Some(a), app))
+                .transpose()
+                .map_err(|e| self.new_error(CmdErrorType::Argument(e)))
+        };
+        let filter = TransactionFilter {
+            from: parse_date(args.value("--from"))?,
+            to: parse_date(args.value("--to"))?,
+            tag: args.value("--tag").map(String::from),
+            ..Default::default()
+        };
+
+        let report = ledger.get_income_expense_report(currency, &filter)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        if args.flag("--by-payee") {
+            let payees = ledger.get_payees();
+            let mut totals: Vec<(String, String)> = report.get_payees().iter()
+                .filter_map(|(payee, total)| payee.map(|p| (payees.get_payee(p).map_or_else(|| p.to_string(), |p| p.get_name().clone()), total.to_string())))
+                .collect();
+            totals.sort_by_key(|(name, _)| name.to_lowercase());
+            for (name, total) in totals {
+                writeln!(app.out(), "  {:<20}  {:>14}", name, total)?;
+            }
+            if let Some((_, total)) = report.get_payees().iter().find(|(p, _)| p.is_none()) {
+                writeln!(app.out(), "  {:<20}  {:>14}", "(no payee)", total.to_string())?;
+            }
+        } else {
+            // Subcategories are indented under their parents, whose totals include them
+            let categories = ledger.get_transaction_categories();
+            for (category, total) in report.get_category_totals() {
+                let depth = categories.ancestors(category).len();
+                let name = categories.get_category(category).map_or_else(|| category.to_string(), |c| c.name().clone());
+                writeln!(app.out(), "  {:<20}  {:>14}", format!("{}{}", "  ".repeat(depth), name), total.to_string())?;
+            }
+            if let Some((_, total)) = report.get_categories().iter().find(|(c, _)| c.is_none()) {
+                writeln!(app.out(), "  {:<20}  {:>14}", "(uncategorized)", total.to_string())?;
+            }
+        }
+        writeln!(app.out(), "Income:   {:>14}", report.get_income().to_string())?;
+        writeln!(app.out(), "Expenses: {:>14}", report.get_expenses().to_string())?;
+        writeln!(app.out(), "Net:      {:>14}", report.get_net().to_string())?;
+        Ok(CmdResult::Ok)
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["report"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: report [OPTIONS]
+Shows income and expenses across all accounts, with the net amount for each category. A category's amount includes
+its subcategories, which are listed under it. Transfers between accounts aren't counted.
+
+Options:
+  --from DATE, --to DATE  Only count transactions in this date range (inclusive)
+  --tag TAG               Only count transactions tagged TAG
+  --by-payee              Show the net amount for each payee instead of each category
+  --currency CURRENCY     Convert everything to CURRENCY (USD by default), using the exchange rate on the
+                          date of each transaction"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger::common_types::Money;
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    #[test]
+    fn report() {
+        let (mut app, output) = test_app_with_output(&[]);
+
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let savings = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        let groceries = ledger.get_transaction_categories_mut().create_category("Food:Groceries".to_string()).unwrap();
+        let time: chrono::DateTime<chrono::Utc> = "2000-1-1T00:00:00Z".parse().expect("Failed to parse");
+        ledger.add_transaction(checking, ledger::transaction::Transaction::new(
+            Money::new(100000, Currency::USD), time, "Paycheck".to_string(), None)).unwrap();
+        ledger.add_transaction(checking, ledger::transaction::Transaction::new(
+            Money::new(-2500, Currency::USD), time, "Corner store".to_string(), Some(groceries))).unwrap();
+        ledger.transfer(checking, savings, Money::new(50000, Currency::USD), time, "Saving up".to_string()).unwrap();
+
+        assert!(Report::new().execute(&["--from", "1999-12-01"], &mut ledger, &mut app).is_ok());
+        let output = output.lock().unwrap();
+        assert!(output.contains("  Food                         -$25.00"), "{}", output);
+        assert!(output.contains("    Groceries                  -$25.00"), "{}", output);
+        assert!(output.contains("Income:         $1000.00"), "{}", output);
+        assert!(output.contains("Expenses:         $25.00"), "{}", output);
+    }
+
+    #[test]
+    fn by_payee() {
+        let (mut app, output) = test_app_with_output(&[]);
+
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        ledger.add_payee("Amazon".to_string(), vec!["amzn*".to_string(), "amazon.com".to_string()]).unwrap();
+        let time: chrono::DateTime<chrono::Utc> = "2000-1-1T00:00:00Z".parse().expect("Failed to parse");
+        for (amount, description) in [(-1000, "AMZN Mktp US*2K3"), (-500, "Amazon.com"), (-250, "Corner store")] {
+            ledger.add_transaction(checking, ledger::transaction::Transaction::new(
+                Money::new(amount, Currency::USD), time, description.to_string(), None)).unwrap();
+        }
+
+        assert!(Report::new().execute(&["--by-payee"], &mut ledger, &mut app).is_ok());
+        let output = output.lock().unwrap();
+        assert!(output.contains("  Amazon                       -$15.00\n  (no payee)                    -$2.50\n"), "{}", output);
+    }
+}
@@ -0,0 +1,328 @@
+use ledger::{
+    common_types::Money,
+    schedule::{Frequency, ScheduleEnd, ScheduleId, ScheduleUpdate},
+    Ledger};
+
+use crate::app::Application;
+
+use super::{confirm, get_account, get_category_id, parse_date_or_today, Cmd, CmdError, CmdErrorType, CmdResult, ParsedArgs, SyntaxErrorType};
+
+/// Command to manage schedules for recurring transactions, and post the ones that are due
+pub struct Schedule { }
+
+impl Cmd for Schedule {
+    fn new() -> Schedule {
+        Schedule { }
+    }
+
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        match args.first() {
+            Some(&"--new") => {
+                self.add_schedule(&args[1..], ledger, app)
+            },
+            Some(&"--edit") => {
+                self.edit_schedule(&args[1..], ledger, app)
+            },
+            Some(&"--skip") => {
+                let id = self.get_schedule_id(args.get(1), ledger)?;
+                let date = ledger.skip_schedule(id).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Skipped schedule {} on {}", id, date)?;
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--delete") => {
+                let id = self.get_schedule_id(args.get(1), ledger)?;
+                let description = ledger.get_schedule(id).expect("Checked above").get_description().clone();
+                if !confirm(app, &format!("Delete schedule {} ({})? Transactions it's posted are kept.", id, description))? {
+                    writeln!(app.out(), "Cancelled")?;
+                    return Ok(CmdResult::Ok);
+                }
+                ledger.delete_schedule(id).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Deleted schedule {}", id)?;
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--list") => {
+                self.list_schedules(ledger, app)
+            },
+            Some(&"--post") => {
+                if self.post_due(ledger, app)? == 0 {
+                    writeln!(app.out(), "No scheduled transactions are due")?;
+                }
+                Ok(CmdResult::Ok)
+            },
+            Some(unhandled_subcommand) => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::InvalidSubcommand(unhandled_subcommand.to_string()))))
+            },
+            None => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::MissingSubcommand)))
+            },
+        }
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["schedule", "sched"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: schedule --new ACCOUNT AMOUNT DESCRIPTION [CATEGORY] --every FREQUENCY [OPTIONS]
+       schedule --edit ID [OPTIONS]
+       schedule --skip ID
+       schedule --delete ID
+       schedule --list
+       schedule --post
+Manages schedules for transactions that repeat, like rent or a salary. When an occurrence comes due, a transaction
+is posted for it. Due occurrences are posted when the program starts, whenever the ledger is loaded, or with
+--post.
+
+FREQUENCY is daily, weekly, monthly, yearly, \"last business day\" (of each month), or a weekday of each month like
+\"2nd tuesday\" or \"last friday\". Monthly schedules that start on the 29th to 31st fall on the last day of
+shorter months.
+
+Options for --new:
+  --interval N            Come due every N days, weeks, months or years instead of every one
+  --start DATE            The first occurrence, today by default. For the weekday and business day frequencies, the
+                          first one on or after DATE.
+  --until DATE            Stop after DATE
+  --count N               Stop after N occurrences, counting skipped ones
+
+Options for --edit:
+  --amount AMOUNT, --description DESCRIPTION, --category CATEGORY, --no-category
+                          Change what's posted from now on
+  --until DATE, --count N, --forever
+                          Change when the schedule stops
+
+--skip skips the next occurrence without posting it. --delete asks for confirmation, and keeps the transactions the
+schedule has already posted."
+    }
+}
+
+impl Schedule {
+    /// Post every scheduled transaction that's due, then list what was posted and what couldn't be. Returns how
+    /// many were posted.
+    pub(crate) fn post_due(&self, ledger: &mut Ledger, app: &mut Application) -> Result<usize, CmdError> {
+        let today = parse_date_or_today(None, app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        let result = ledger.post_due_schedules(today);
+
+        if !result.posted.is_empty() {
+            writeln!(app.out(), "Posted {} scheduled transactions that came due:", result.posted.len())?;
+        }
+        for id in &result.posted {
+            let transaction = ledger.get_transaction(*id).expect("Posted transactions exist");
+            let account_name = ledger.get_account_for_transaction(*id).map_or("", |a| a.get_name());
+            writeln!(
+                app.out(),
+                "  {}  {:<15}  {:>14}  {}",
+                transaction.get_date(), account_name, transaction.get_amount().to_string(), transaction.get_description())?;
+        }
+        if !result.skipped.is_empty() {
+            writeln!(app.out(), "Couldn't post {} scheduled transactions that came due:", result.skipped.len())?;
+        }
+        for (id, date, reason) in &result.skipped {
+            writeln!(app.out(), "  {}  schedule {}: {}", date, id, reason)?;
+        }
+        Ok(result.posted.len())
+    }
+
+    fn add_schedule(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &["--every", "--interval", "--start", "--until", "--count"], &[])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let positional = args.positional();
+        let (Some(account_name), Some(amount), Some(description), Some(frequency)) =
+            (positional.first(), positional.get(1), positional.get(2), args.value("--every")) else {
+            return Err(self.new_error(
+                CmdErrorType::Syntax(
+                    SyntaxErrorType::MissingParam(
+                        "Invalid format. Usage: `schedule --new ACCOUNT AMOUNT DESCRIPTION [CATEGORY] --every FREQUENCY`".to_string()))));
+        };
+
+        let account = get_account(Some(account_name), ledger).map_err(|e| self.new_error(e))?;
+        let account_id = account.get_id();
+        let amount = Money::parse(amount, account.get_currency(), app.config().number_format)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?;
+        let category = positional.get(3).map(|c| get_category_id(c, ledger)).transpose().map_err(|e| self.new_error(e))?;
+        let frequency = frequency.parse::<Frequency>().map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        let interval = match args.value("--interval") {
+            Some(n) => self.parse_count(n)?,
+            None => 1,
+        };
+        let start = parse_date_or_today(args.value("--start"), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        let end = self.parse_end(&args, app)?.unwrap_or_default();
+
+        let schedule = ledger::schedule::Schedule::new(account_id, amount, description.to_string(), category, frequency, start)
+            .with_interval(interval)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?
+            .with_end(end);
+        let id = ledger.add_schedule(schedule).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        let schedule = ledger.get_schedule(id).expect("Just added");
+        match schedule.get_next_due() {
+            Some(date) => writeln!(app.out(), "Created schedule {}, first due on {}", id, date)?,
+            None => writeln!(app.out(), "Created schedule {}, which never comes due", id)?,
+        }
+        Ok(CmdResult::Ok)
+    }
+
+    fn edit_schedule(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(
+            args,
+            &["--amount", "--description", "--category", "--until", "--count"],
+            &["--no-category", "--forever"])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let id = self.get_schedule_id(args.positional().first(), ledger)?;
+        let currency = ledger.get_schedule(id).expect("Checked above").get_amount().currency();
+
+        let mut update = ScheduleUpdate::default();
+        if let Some(amount) = args.value("--amount") {
+            update.amount = Some(Money::parse(amount, currency, app.config().number_format)
+                .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?);
+        }
+        if let Some(description) = args.value("--description") {
+            update.description = Some(description.to_string());
+        }
+        match (args.value("--category"), args.flag("--no-category")) {
+            (Some(_), true) => return Err(self.new_error(CmdErrorType::Argument("Give either --category or --no-category, not both".to_string()))),
+            (Some(category), false) => update.category = Some(Some(get_category_id(category, ledger).map_err(|e| self.new_error(e))?)),
+            (None, true) => update.category = Some(None),
+            (None, false) => {},
+        }
+        update.end = match (self.parse_end(&args, app)?, args.flag("--forever")) {
+            (Some(_), true) => return Err(self.new_error(CmdErrorType::Argument("Give only one of --until, --count and --forever".to_string()))),
+            (_, true) => Some(ScheduleEnd::Never),
+            (end, false) => end,
+        };
+
+        ledger.update_schedule(id, update).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "Updated schedule {}", id)?;
+        Ok(CmdResult::Ok)
+    }
+
+    fn list_schedules(&self, ledger: &Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let categories = ledger.get_transaction_categories();
+        writeln!(
+            app.out(),
+            "  {:>4}  {:<15}  {:>14}  {:<25}  {:<15}  {:<30}  {:<10}",
+            "ID", "Account", "Amount", "Description", "Category", "Repeats", "Next due")?;
+        for schedule in ledger.get_schedules() {
+            let account_name = ledger.get_account(schedule.get_account()).map_or("", |a| a.get_name());
+            let category = schedule.get_category().map(|c| categories.display_name(c)).unwrap_or_default();
+            let next_due = schedule.get_next_due().map_or("ended".to_string(), |d| d.to_string());
+            writeln!(
+                app.out(),
+                "  {:>4}  {:<15}  {:>14}  {:<25}  {:<15}  {:<30}  {:<10}",
+                schedule.get_id(), account_name, schedule.get_amount().to_string(), schedule.get_description(),
+                category, schedule.describe_frequency(), next_due)?;
+        }
+        Ok(CmdResult::Ok)
+    }
+
+    // The end given by --until or --count, if either was
+    fn parse_end(&self, args: &ParsedArgs, app: &Application) -> Result<Option<ScheduleEnd>, CmdError> {
+        match (args.value("--until"), args.value("--count")) {
+            (Some(_), Some(_)) => Err(self.new_error(CmdErrorType::Argument("Give either --until or --count, not both".to_string()))),
+            (Some(until), None) => {
+                let date = parse_date_or_today(Some(until), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                Ok(Some(ScheduleEnd::On(date)))
+            },
+            (None, Some(count)) => Ok(Some(ScheduleEnd::After(self.parse_count(count)?))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn parse_count(&self, arg: &str) -> Result<u32, CmdError> {
+        arg.parse::<u32>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or(self.new_error(CmdErrorType::Argument(format!("'{}' is not a positive whole number", arg))))
+    }
+
+    fn get_schedule_id(&self, arg: Option<&&str>, ledger: &Ledger) -> Result<ScheduleId, CmdError> {
+        let arg = arg.ok_or(
+            self.new_error(
+                CmdErrorType::Syntax(
+                    SyntaxErrorType::MissingParam(
+                        "Must provide a schedule ID".to_string()))))?;
+
+        let id = arg.parse::<u64>()
+            .map(ScheduleId::from)
+            .map_err(|_| self.new_error(CmdErrorType::Argument(format!("'{}' is not a valid schedule ID", arg))))?;
+        if ledger.get_schedule(id).is_none() {
+            return Err(self.new_error(CmdErrorType::Argument(format!("No schedule with ID {}", id))));
+        }
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger::common_types::Currency;
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    #[test]
+    fn new_skip_edit_and_post() {
+        let (mut app, output) = test_app_with_output(&[]);
+
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        ledger.get_transaction_categories_mut().create_category("Housing".to_string()).unwrap();
+
+        let cmd = Schedule::new();
+        assert!(cmd.execute(
+            &["--new", "checking", "-1200", "Rent", "housing", "--every", "monthly", "--start", "2000-01-31", "--count", "3"],
+            &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--new", "checking", "100", "Paycheck", "--every", "fortnightly"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--new", "checking", "100", "Paycheck", "--every", "weekly", "--interval", "0"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--skip", "1"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--edit", "1", "--amount", "-1,250", "--no-category"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--list"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--post"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--post"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--skip", "1"], &mut ledger, &mut app).is_err());
+
+        let transactions = ledger.get_account(checking).unwrap().get_transactions();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].get_date().to_string(), "2000-02-29");
+        assert_eq!(transactions[1].get_amount(), &Money::new(-125000, Currency::USD));
+        assert_eq!(transactions[1].get_category(), None);
+
+        let output = output.lock().unwrap();
+        assert!(output.contains("Created schedule 1, first due on 2000-01-31"), "{}", output);
+        assert!(output.contains("Skipped schedule 1 on 2000-01-31"), "{}", output);
+        assert!(output.contains("every month                     2000-02-29"), "{}", output);
+        assert!(output.contains("Posted 2 scheduled transactions that came due:\n  2000-02-29  Checking"), "{}", output);
+        assert!(output.contains("No scheduled transactions are due"), "{}", output);
+    }
+
+    #[test]
+    fn edit_rejects_conflicting_options() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        ledger.get_transaction_categories_mut().create_category("Housing".to_string()).unwrap();
+
+        let cmd = Schedule::new();
+        assert!(cmd.execute(&["--new", "checking", "-1200", "Rent", "housing", "--every", "monthly"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--edit", "1", "--category", "housing", "--no-category"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--edit", "1", "--until", "2030-01-01", "--forever"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--edit", "1", "--count", "3", "--forever"], &mut ledger, &mut app).is_err());
+
+        let schedule = ledger.get_schedule(ScheduleId::from(1)).unwrap();
+        assert!(schedule.get_category().is_some());
+        assert_eq!(schedule.get_end(), ScheduleEnd::Never);
+        assert!(cmd.execute(&["--edit", "1", "--count", "3"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--edit", "1", "--forever"], &mut ledger, &mut app).is_ok());
+    }
+
+    #[test]
+    fn delete() {
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let (mut app, _) = test_app_with_output(&["y"]);
+
+        let cmd = Schedule::new();
+        assert!(cmd.execute(&["--new", "checking", "9.99", "Music", "--every", "2nd tuesday"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--delete", "1"], &mut ledger, &mut app).is_ok());
+        assert!(ledger.get_schedules().is_empty());
+        assert!(cmd.execute(&["--delete", "1"], &mut ledger, &mut app).is_err());
+    }
+}
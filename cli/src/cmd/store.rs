@@ -36,7 +36,9 @@ Saves data to disk."
 #[cfg(test)]
 mod tests {
 
-    use crate::{cmd::Cmd, store::mock::MockFileStore, ui::MockTerminalInterface};
+    use crate::{cmd::Cmd, config::Config, store::mock::MockFileStore, ui::MockTerminalInterface};
+
+    use ledger::common_types::Currency;
 
     use super::*;
 
@@ -46,7 +48,7 @@ mod tests {
         let mut file_store = MockFileStore::default();
 
         let mut test_ledger = Ledger::new_empty();
-        test_ledger.add_new_account(String::from("test_account"));
+        test_ledger.add_new_account(String::from("test_account"), Currency::USD).unwrap();
 
         file_store.expect_store_ledger()
             .times(1)
@@ -58,9 +60,9 @@ mod tests {
         interface.expect_write()
             .returning(|s| Ok(s.len()));
 
-        let mut application_mock = Application::new(interface, file_store);
+        let mut application_mock = Application::new(interface, file_store, Config::default());
 
         let store_cmd = Store::new();
-        assert!(store_cmd.execute(&vec![], &mut test_ledger, &mut application_mock).is_ok());
+        assert!(store_cmd.execute(&[], &mut test_ledger, &mut application_mock).is_ok());
     }
 }
@@ -0,0 +1,103 @@
+use ledger::Ledger;
+
+use crate::app::Application;
+
+use super::{confirm, Cmd, CmdError, CmdErrorType, CmdResult, SyntaxErrorType};
+
+/// Command to list, rename and remove tags across every transaction
+pub struct Tag { }
+
+impl Cmd for Tag {
+    fn new() -> Tag {
+        Tag { }
+    }
+
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        match args.first() {
+            Some(&"--list") => {
+                for (tag, count) in ledger.get_tags() {
+                    writeln!(app.out(), "  #{:<30}  {:>5}", tag, count)?;
+                }
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--rename") => {
+                let (Some(from), Some(to)) = (args.get(1), args.get(2)) else {
+                    return Err(self.new_error(
+                        CmdErrorType::Syntax(
+                            SyntaxErrorType::MissingParam(
+                                "Invalid format. Usage: `tag --rename OLD NEW`".to_string()))));
+                };
+                let count = ledger.rename_tag(from, to).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Renamed tag '{}' to '{}' on {} transactions", from, to, count)?;
+                Ok(CmdResult::Ok)
+            },
+            Some(&"--remove") => {
+                let tag = args.get(1).ok_or(
+                    self.new_error(
+                        CmdErrorType::Syntax(
+                            SyntaxErrorType::MissingParam(
+                                "Must provide a tag".to_string()))))?;
+                if !confirm(app, &format!("Remove tag '{}' from every transaction?", tag))? {
+                    writeln!(app.out(), "Cancelled")?;
+                    return Ok(CmdResult::Ok);
+                }
+                let count = ledger.remove_tag(tag).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+                writeln!(app.out(), "Removed tag '{}' from {} transactions", tag, count)?;
+                Ok(CmdResult::Ok)
+            },
+            Some(unhandled_subcommand) => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::InvalidSubcommand(unhandled_subcommand.to_string()))))
+            },
+            None => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::MissingSubcommand)))
+            },
+        }
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["tag"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: tag --list
+       tag --rename OLD NEW
+       tag --remove TAG
+Lists every tag with how many transactions have it, or renames or removes a tag on every transaction at once.
+Renaming a tag to one that's already in use merges them. --remove asks for confirmation first.
+
+Transactions are tagged with the --tag option of the transaction command."
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger::{common_types::{Currency, Money}, transaction::Transaction};
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    #[test]
+    fn rename_list_and_remove() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let time: chrono::DateTime<chrono::Utc> = "2000-1-1T00:00:00Z".parse().expect("Failed to parse");
+        for tags in [["vacation", "gift"], ["trip", "gift"]] {
+            let transaction = Transaction::new(Money::new(-100, Currency::USD), time, "".to_string(), None).with_tags(&tags).unwrap();
+            ledger.add_transaction(checking, transaction).unwrap();
+        }
+
+        let (mut app, output) = test_app_with_output(&["y"]);
+
+        let cmd = Tag::new();
+        assert!(cmd.execute(&["--rename", "#trip", "vacation"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--remove", "gift"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--list"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--rename", "vacation"], &mut ledger, &mut app).is_err());
+
+        let output = output.lock().unwrap();
+        assert!(output.contains("Renamed tag '#trip' to 'vacation' on 1 transactions"), "{}", output);
+        assert!(output.contains("Removed tag 'gift' from 2 transactions"), "{}", output);
+        assert!(output.ends_with("  #vacation                            2\n"), "{}", output);
+    }
+}
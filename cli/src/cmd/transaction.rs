@@ -1,9 +1,14 @@
-use chrono::{DateTime, Utc};
-use ledger::{account::Account, common_types::Money};
+use std::cmp::Ordering;
+
+use ledger::{
+    common_types::{Currency, Money},
+    category::{CategoryId, TransactionCategories},
+    transaction::{Split, TransactionFilter, TransactionId, TransactionStatus, TransactionUpdate},
+    Ledger};
 
 use crate::app::Application;
 
-use super::{Cmd, CmdErrorType, CmdResult, SyntaxErrorType};
+use super::{confirm, get_account, get_category_id, parse_date_or_today, parse_time_or_now, Cmd, CmdError, CmdErrorType, CmdResult, ParsedArgs, SyntaxErrorType};
 
 pub struct Transaction { }
 
@@ -12,51 +17,650 @@ impl Cmd for Transaction {
         Transaction {  }
     }
 
-    fn execute(&self, args: &[&str], ledger: &mut ledger::Ledger, _app: &mut Application) -> Result<super::CmdResult, super::CmdError> {
-        if args.len() < 3 {
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        match args.first() {
+            Some(&"--new") => {
+                self.add_transaction(&args[1..], ledger, app)
+            },
+            Some(&"--edit") => {
+                self.edit_transaction(&args[1..], ledger, app)
+            },
+            Some(&"--delete") => {
+                self.delete_transaction(&args[1..], ledger, app)
+            },
+            Some(&"--list") => {
+                self.list_transactions(&args[1..], ledger, app)
+            },
+            Some(unhandled_subcommand) if unhandled_subcommand.starts_with("--") => {
+                Err(self.new_error(CmdErrorType::Syntax(SyntaxErrorType::InvalidSubcommand(unhandled_subcommand.to_string()))))
+            },
+            // Without a subcommand, the arguments describe a new transaction
+            _ => {
+                self.add_transaction(args, ledger, app)
+            },
+        }
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["transaction", "tr"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: transaction [--new] ACCOUNT AMOUNT DESCRIPTION [CATEGORY] [--date DATE] [--split ...] [--tag TAG ...]
+       transaction --edit ID [OPTIONS]
+       transaction --delete ID
+       transaction --list ACCOUNT [OPTIONS]
+Creates, changes, removes or lists transaction entries.
+
+Without a subcommand (or with --new), creates a new transaction entry in ACCOUNT. AMOUNT is in the account's
+currency, and may be written like -1,234.56, (12.00), $12 or 12 USD. The decimal separator follows the locale.
+
+DATE defaults to today, and may be written like 2024-03-01, yesterday, -3d, last friday or 03/01/2024 (or
+01/03/2024, depending on the date-format setting).
+
+To split a transaction across categories, leave out CATEGORY and give --split AMOUNT=CATEGORY[;MEMO] once for
+each part, e.g. --split 60=food:groceries --split 25=household --split 15=pharmacy;Allergy pills. The split
+amounts must add up to AMOUNT. Leave the category empty (AMOUNT=;MEMO) for an uncategorized part.
+
+Tags label transactions across categories, e.g. --tag vacation-2026 --tag reimbursable. A leading # is optional,
+and case doesn't matter. Use the tag command to list, rename or remove tags.
+
+Options for --edit:
+  --amount AMOUNT             Change the amount
+  --date DATE                 Change the date
+  --description DESCRIPTION   Change the description
+  --category CATEGORY         Change the category
+  --no-category               Remove the category
+  --split AMOUNT=CATEGORY[;MEMO]
+                              Replace the splits (may be given more than once)
+  --no-splits                 Stop splitting the transaction
+  --tag TAG, --untag TAG      Add or remove a tag (may be given more than once)
+  --status STATUS             Mark the transaction pending or cleared. Reconciled transactions can't be edited
+                              until they're marked pending or cleared again, which has to be done on its own.
+
+--delete asks for confirmation before removing the transaction.
+
+Options for --list:
+  --from DATE, --to DATE      Only show transactions in this date range (inclusive)
+  --category CATEGORY         Only show transactions in CATEGORY or one of its subcategories
+  --min AMOUNT, --max AMOUNT  Only show transactions in this amount range
+  --search TEXT               Only show transactions whose description contains TEXT
+  --tag TAG                   Only show transactions tagged TAG
+  --sort COLUMN               Sort by id, date, description, category, amount or balance (date by default)
+  --reverse                   Reverse the sort order
+
+The column after the ID shows the status: blank for pending, c for cleared and R for reconciled.
+"
+    }
+}
+
+impl Transaction {
+
+    fn add_transaction(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &["--date", "--split", "--tag"], &[])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let positional = args.positional();
+        if positional.len() < 3 {
             return Err(self.new_error(
                 CmdErrorType::Syntax(SyntaxErrorType::MissingParam(
                     "Invalid format. Usage: `transaction [account_name] [amount] [description]`".to_string()))))
         }
 
-        let account_name = args[0].to_string();
-        let amount: f64 = args[1].parse().map_err(|e| self.new_error(CmdErrorType::Dependency(Box::new(e))))?;
-        let description = args[2].to_string();
-
-        let category_id = match args.get(3) {
-            Some(s) => {
-                let id = String::from(*s).into();
-                let category = ledger.get_transaction_categories().get_category(&id);
-                if category.is_none() {
-                    return Err(self.new_error(
-                        CmdErrorType::Argument(
-                            format!("No category named '{}'", id)
-                        )))
-                }
-                Some(id)
-            },
+        let description = positional[2].to_string();
+
+        let category_id = match positional.get(3) {
+            Some(s) => Some(get_category_id(s, ledger).map_err(|e| self.new_error(e))?),
             None => None,
         };
 
-        let account: &mut Account = ledger.get_account_by_name_mut(&account_name).ok_or(
-            self.new_error(CmdErrorType::Argument(format!("Could not find account named '{}'", account_name))))?;
+        let account = get_account(Some(positional[0]), ledger).map_err(|e| self.new_error(e))?;
+        let account_id = account.get_id();
 
-        // TODO: ummm...get this from somewhere
-        let time: DateTime<Utc> = Utc::now();
+        let amount = Money::parse(positional[1], account.get_currency(), app.config().number_format)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?;
+        let time = parse_time_or_now(args.value("--date"), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        let splits = self.parse_splits(&args.values("--split"), amount.currency(), ledger, app)?;
+        if !splits.is_empty() && category_id.is_some() {
+            return Err(self.new_error(CmdErrorType::Argument(
+                "A split transaction takes a category for each split instead of a single category".to_string())));
+        }
 
-        // TODO: get category
-        account.add_transaction(ledger::transaction::Transaction::new(Money::from_float(amount), time, description, category_id));
+        let transaction = ledger::transaction::Transaction::new(amount, time, description, category_id)
+            .with_splits(splits)
+            .and_then(|t| t.with_tags(&args.values("--tag")))
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        let id = ledger.add_transaction(account_id, transaction)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "Created transaction {}", id)?;
 
         Ok(CmdResult::Ok)
     }
 
-    fn names(&self) -> Vec<&'static str> {
-        vec!["transaction", "tr"]
+    fn edit_transaction(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(
+            args,
+            &["--amount", "--date", "--description", "--category", "--split", "--status", "--tag", "--untag"],
+            &["--no-category", "--no-splits"])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let id = self.get_transaction_id(args.positional().first(), ledger)?;
+        let currency = ledger.get_transaction(id).map(|t| t.get_amount().currency()).expect("Checked above");
+        let status = args.value("--status")
+            .map(|s| s.parse::<TransactionStatus>())
+            .transpose()
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        let mut update = TransactionUpdate::default();
+        if let Some(amount) = args.value("--amount") {
+            update.amount = Some(Money::parse(amount, currency, app.config().number_format)
+                .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?);
+        }
+        if args.value("--date").is_some() {
+            update.time = Some(parse_time_or_now(args.value("--date"), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?);
+        }
+        if let Some(description) = args.value("--description") {
+            update.description = Some(description.to_string());
+        }
+        match (args.value("--category"), args.flag("--no-category")) {
+            (Some(_), true) => return Err(self.new_error(CmdErrorType::Argument("Give either --category or --no-category, not both".to_string()))),
+            (Some(category), false) => update.category = Some(Some(get_category_id(category, ledger).map_err(|e| self.new_error(e))?)),
+            (None, true) => update.category = Some(None),
+            (None, false) => {},
+        }
+        let splits = args.values("--split");
+        if !splits.is_empty() {
+            update.splits = Some(self.parse_splits(&splits, currency, ledger, app)?);
+        }
+        if args.flag("--no-splits") {
+            update.splits = Some(Vec::new());
+        }
+        update.add_tags = args.values("--tag").into_iter().map(String::from).collect();
+        update.remove_tags = args.values("--untag").into_iter().map(String::from).collect();
+
+        let has_changes = update.amount.is_some() || update.time.is_some() || update.description.is_some()
+            || update.category.is_some() || update.splits.is_some() || !update.add_tags.is_empty()
+            || !update.remove_tags.is_empty();
+        if status == Some(TransactionStatus::Reconciled) {
+            return Err(self.new_error(CmdErrorType::Argument(
+                "Transactions can only be reconciled with the reconcile command".to_string())));
+        }
+        // Unlocking a reconciled transaction should be a deliberate step of its own, not a side effect of an edit
+        let reconciled = ledger.get_transaction(id).expect("Checked above").get_status() == TransactionStatus::Reconciled;
+        if status.is_some() && has_changes && reconciled {
+            return Err(self.new_error(CmdErrorType::Argument(format!(
+                "Transaction {} is reconciled. Change its --status on its own before editing it.", id))));
+        }
+
+        // The update goes first, so that if it fails the status is left as it was
+        if has_changes || status.is_none() {
+            ledger.update_transaction(id, update).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        }
+        if let Some(status) = status {
+            ledger.set_transaction_status(id, status).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        }
+        writeln!(app.out(), "Updated transaction {}", id)?;
+        Ok(CmdResult::Ok)
     }
 
-    fn help_text(&self) -> &'static str {
-"Usage: transaction ACCOUNT AMOUNT DESCRIPTION [CATEGORY]
-Creates a new transaction entry in ACCOUNT. 
-"
+    fn delete_transaction(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let id = self.get_transaction_id(args.first(), ledger)?;
+        let amount = *ledger.get_transaction(id).expect("Checked above").get_amount();
+        let account_name = ledger.get_account_for_transaction(id).expect("Checked above").get_name().clone();
+
+        if !confirm(app, &format!("Delete transaction {} ({} in '{}')?", id, amount, account_name))? {
+            writeln!(app.out(), "Cancelled")?;
+            return Ok(CmdResult::Ok);
+        }
+
+        ledger.delete_transaction(id).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "Deleted transaction {}", id)?;
+        Ok(CmdResult::Ok)
+    }
+
+    fn list_transactions(&self, args: &[&str], ledger: &Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(
+            args,
+            &["--from", "--to", "--category", "--min", "--max", "--search", "--tag", "--sort"],
+            &["--reverse"])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+
+        let account = get_account(args.positional().first().copied(), ledger).map_err(|e| self.new_error(e))?;
+
+        let parse_amount = |arg: Option<&str>| -> Result<Option<Money>, CmdError> {
+            arg.map(|a| Money::parse(a, account.get_currency(), app.config().number_format))
+                .transpose()
+                .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))
+        };
+        let parse_date = |arg: Option<&str>| {
+            arg.map(|a| parse_date_or_today(Some(a), app))
+                .transpose()
+                .map_err(|e| self.new_error(CmdErrorType::Argument(e)))
+        };
+        let filter = TransactionFilter {
+            from: parse_date(args.value("--from"))?,
+            to: parse_date(args.value("--to"))?,
+            // Matched below instead, so that subcategories are included
+            category: None,
+            min_amount: parse_amount(args.value("--min"))?,
+            max_amount: parse_amount(args.value("--max"))?,
+            description: args.value("--search").map(String::from),
+            tag: args.value("--tag").map(String::from),
+            currency: None,
+        };
+        let category = args.value("--category").map(|c| get_category_id(c, ledger)).transpose().map_err(|e| self.new_error(e))?;
+        let sort_column = SortColumn::parse(args.value("--sort").unwrap_or("date"))
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        let categories = ledger.get_transaction_categories();
+        let in_category = |id: Option<&CategoryId>| match (&category, id) {
+            (None, _) => true,
+            (Some(category), Some(id)) => id == category || categories.ancestors(id).contains(&category),
+            (Some(_), None) => false,
+        };
+        let mut rows: Vec<_> = account.get_running_balances().into_iter()
+            .filter(|(t, _)| filter.matches(t) && t.get_category_amounts().iter().any(|(id, _)| in_category(*id)))
+            .collect();
+        // The sort is stable and the rows start in date order, so ties stay in date order
+        rows.sort_by(|a, b| sort_column.compare(a, b, categories));
+        if args.flag("--reverse") {
+            rows.reverse();
+        }
+
+        writeln!(app.out(), "  {:>5}  {:<1}  {:<10}  {:<30}  {:<15}  {:>14}  {:>14}", "ID", "", "Date", "Description", "Category", "Amount", "Balance")?;
+        for (transaction, balance) in rows {
+            writeln!(
+                app.out(),
+                "  {:>5}  {:<1}  {:<10}  {:<30}  {:<15}  {:>14}  {:>14}",
+                transaction.get_id(),
+                status_column(transaction.get_status()),
+                transaction.get_date(),
+                transaction.get_description(),
+                category_column(transaction, categories),
+                transaction.get_amount().to_string(),
+                balance.to_string())?;
+            for split in transaction.get_splits() {
+                writeln!(
+                    app.out(),
+                    "  {:>5}  {:<1}  {:<10}    {:<28}  {:<15}  {:>14}",
+                    "", "", "",
+                    split.get_memo(),
+                    split.get_category().map(|c| categories.display_name(c)).unwrap_or_default(),
+                    split.get_amount().to_string())?;
+            }
+            if !transaction.get_tags().is_empty() {
+                let tags: Vec<String> = transaction.get_tags().iter().map(|t| format!("#{}", t)).collect();
+                writeln!(app.out(), "  {:>5}  {:<1}  {:<10}    {}", "", "", "", tags.join(" "))?;
+            }
+        }
+
+        Ok(CmdResult::Ok)
+    }
+
+    /// Parse a transaction ID argument, checking that the transaction exists.
+    fn get_transaction_id(&self, arg: Option<&&str>, ledger: &Ledger) -> Result<TransactionId, CmdError> {
+        let arg = arg.ok_or(
+            self.new_error(
+                CmdErrorType::Syntax(
+                    SyntaxErrorType::MissingParam(
+                        "Must provide a transaction ID".to_string()))))?;
+
+        let id = arg.parse::<u64>()
+            .map(TransactionId::from)
+            .map_err(|_| self.new_error(CmdErrorType::Argument(format!("'{}' is not a valid transaction ID", arg))))?;
+        if ledger.get_transaction(id).is_none() {
+            return Err(self.new_error(CmdErrorType::Argument(format!("No transaction with ID {}", id))));
+        }
+        Ok(id)
+    }
+
+    /// Parse `--split` arguments written as AMOUNT=CATEGORY[;MEMO]. The category may be left empty. Neither separator
+    /// can be part of a category path, which uses `:`.
+    fn parse_splits(&self, args: &[&str], currency: Currency, ledger: &Ledger, app: &Application) -> Result<Vec<Split>, CmdError> {
+        args.iter()
+            .map(|arg| {
+                let (amount, rest) = arg.split_once('=').ok_or(self.new_error(CmdErrorType::Argument(
+                    format!("Invalid split '{}', expected AMOUNT=CATEGORY[;MEMO]", arg))))?;
+                let (category, memo) = rest.split_once(';').unwrap_or((rest, ""));
+
+                let amount = Money::parse(amount, currency, app.config().number_format)
+                    .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?;
+                let category = match category {
+                    "" => None,
+                    name => Some(get_category_id(name, ledger).map_err(|e| self.new_error(e))?),
+                };
+                Ok(Split::new(amount, category, memo.to_string()))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum SortColumn {
+    Id,
+    Date,
+    Description,
+    Category,
+    Amount,
+    Balance,
+}
+
+impl SortColumn {
+    fn parse(s: &str) -> Result<SortColumn, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "id" => Ok(SortColumn::Id),
+            "date" => Ok(SortColumn::Date),
+            "description" => Ok(SortColumn::Description),
+            "category" => Ok(SortColumn::Category),
+            "amount" => Ok(SortColumn::Amount),
+            "balance" => Ok(SortColumn::Balance),
+            _ => Err(format!("Cannot sort by '{}'", s)),
+        }
+    }
+
+    fn compare(
+        &self,
+        (a, a_balance): &(&ledger::transaction::Transaction, Money),
+        (b, b_balance): &(&ledger::transaction::Transaction, Money),
+        categories: &TransactionCategories,
+    ) -> Ordering {
+        match self {
+            SortColumn::Id => a.get_id().cmp(&b.get_id()),
+            SortColumn::Date => a.chronological_key().cmp(&b.chronological_key()),
+            SortColumn::Description => a.get_description().to_lowercase().cmp(&b.get_description().to_lowercase()),
+            SortColumn::Category => {
+                category_column(a, categories).to_lowercase().cmp(&category_column(b, categories).to_lowercase())
+            },
+            SortColumn::Amount => a.get_amount().cmp(b.get_amount()),
+            SortColumn::Balance => a_balance.cmp(b_balance),
+        }
+    }
+}
+
+/// What to show in the category column. Split transactions and transfers don't have a single category, so they're
+/// marked as such instead.
+fn category_column(transaction: &ledger::transaction::Transaction, categories: &TransactionCategories) -> String {
+    match (transaction.get_category(), transaction.get_transfer()) {
+        _ if !transaction.get_splits().is_empty() => "(split)".to_string(),
+        (Some(category), _) => categories.display_name(category),
+        (None, Some(_)) => "(transfer)".to_string(),
+        (None, None) => String::new(),
+    }
+}
+
+fn status_column(status: TransactionStatus) -> &'static str {
+    match status {
+        TransactionStatus::Pending => "",
+        TransactionStatus::Cleared => "c",
+        TransactionStatus::Reconciled => "R",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger::{category::CategoryId, common_types::Currency};
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    fn test_ledger() -> (Ledger, TransactionId) {
+        let mut ledger = Ledger::new_empty();
+        let account = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let time: chrono::DateTime<chrono::Utc> = "2000-1-1T00:00:00Z".parse().expect("Failed to parse");
+        let transaction = ledger::transaction::Transaction::new(
+            Money::new(1000, Currency::USD), time, "Widgets Inc.".to_string(), None);
+        let id = ledger.add_transaction(account, transaction).unwrap();
+        (ledger, id)
+    }
+
+    #[test]
+    fn add_transaction() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let (mut ledger, _) = test_ledger();
+        let cmd = Transaction::new();
+        assert!(cmd.execute(&["checking", "-1,234.50", "Rent"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--new", "checking", "12", "Lunch"], &mut ledger, &mut app).is_ok());
+        assert_eq!(
+            ledger.get_account_by_name("Checking").unwrap().get_total(),
+            Money::new(1000 - 123450 + 1200, Currency::USD));
+    }
+
+    #[test]
+    fn list_transactions() {
+        let (mut app, output) = test_app_with_output(&[]);
+        let (mut ledger, _) = test_ledger();
+        let cmd = Transaction::new();
+        assert!(cmd.execute(&["checking", "-3", "Gadgets"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["checking", "20", "More widgets"], &mut ledger, &mut app).is_ok());
+
+        output.lock().unwrap().clear();
+        let args = ["--list", "checking", "--from", "2000-01-01", "--min", "5", "--search", "widget", "--sort", "amount", "--reverse"];
+        assert!(cmd.execute(&args, &mut ledger, &mut app).is_ok());
+
+        let output = output.lock().unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3, "{}", output);
+        assert!(lines[1].contains("More widgets") && lines[1].contains("$20.00") && lines[1].contains("$27.00"));
+        assert!(lines[2].contains("Widgets Inc.") && lines[2].contains("$10.00"));
+    }
+
+    #[test]
+    fn list_transactions_in_category() {
+        let (mut app, output) = test_app_with_output(&[]);
+        let (mut ledger, _) = test_ledger();
+        ledger.get_transaction_categories_mut().create_category("Food:Groceries".to_string()).unwrap();
+        ledger.get_transaction_categories_mut().create_category("Fun".to_string()).unwrap();
+        let cmd = Transaction::new();
+        assert!(cmd.execute(&["checking", "-5", "Bakery", "Food"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["checking", "-30", "Market", "Food:Groceries"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["checking", "-20", "Costco", "--split", "-15=Fun", "--split", "-5=Food:Groceries"],
+            &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["checking", "-12", "Cinema", "Fun"], &mut ledger, &mut app).is_ok());
+
+        output.lock().unwrap().clear();
+        assert!(cmd.execute(&["--list", "checking", "--category", "Food"], &mut ledger, &mut app).is_ok());
+        {
+            let output = output.lock().unwrap();
+            let lines: Vec<&str> = output.lines().collect();
+            // The split transaction shows a line for each split
+            assert_eq!(lines.len(), 6, "{}", output);
+            assert!(["Bakery", "Market", "Costco"].iter().zip(&lines[1..]).all(|(d, l)| l.contains(d)), "{}", output);
+            assert!(!output.contains("Cinema"), "{}", output);
+        }
+
+        // Only the subcategory, not its parent
+        output.lock().unwrap().clear();
+        assert!(cmd.execute(&["--list", "checking", "--category", "Food:Groceries"], &mut ledger, &mut app).is_ok());
+        let output = output.lock().unwrap();
+        assert_eq!(output.lines().count(), 5, "{}", output);
+        assert!(!output.contains("Bakery"), "{}", output);
+    }
+
+    #[test]
+    fn tags() {
+        let (mut app, output) = test_app_with_output(&[]);
+        let (mut ledger, id) = test_ledger();
+        let cmd = Transaction::new();
+        assert!(cmd.execute(&["checking", "-300", "Flight", "--tag", "#Vacation-2026", "--tag", "reimbursable"],
+            &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["checking", "-3", "Snacks", "--tag", "not valid"], &mut ledger, &mut app).is_err());
+        let id_arg = id.to_string();
+        assert!(cmd.execute(&["--edit", &id_arg, "--tag", "vacation-2026"], &mut ledger, &mut app).is_ok());
+
+        output.lock().unwrap().clear();
+        assert!(cmd.execute(&["--list", "checking", "--tag", "reimbursable"], &mut ledger, &mut app).is_ok());
+        {
+            let output = output.lock().unwrap();
+            let lines: Vec<&str> = output.lines().collect();
+            assert_eq!(lines.len(), 3, "{}", output);
+            assert!(lines[1].contains("Flight"), "{}", output);
+            assert!(lines[2].ends_with("#reimbursable #vacation-2026"), "{}", output);
+        }
+
+        assert!(cmd.execute(&["--edit", &id_arg, "--untag", "#vacation-2026"], &mut ledger, &mut app).is_ok());
+        assert!(ledger.get_transaction(id).unwrap().get_tags().is_empty());
+    }
+
+    #[test]
+    fn list_transactions_invalid() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let (mut ledger, _) = test_ledger();
+        let cmd = Transaction::new();
+        assert!(cmd.execute(&["--list"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--list", "nope"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--list", "checking", "--sort", "nope"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--list", "checking", "--from", "someday"], &mut ledger, &mut app).is_err());
+    }
+
+    #[test]
+    fn sort_column() {
+        assert_eq!(SortColumn::parse("Amount"), Ok(SortColumn::Amount));
+        assert!(SortColumn::parse("nope").is_err());
+    }
+
+    #[test]
+    fn add_transaction_with_date() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let (mut ledger, _) = test_ledger();
+        let cmd = Transaction::new();
+        assert!(cmd.execute(&["checking", "12", "Lunch", "--date", "2024-03-01"], &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["checking", "12", "Lunch", "--date", "someday"], &mut ledger, &mut app).is_err());
+
+        let account = ledger.get_account_by_name("checking").unwrap();
+        let lunch = account.get_transactions().iter().find(|t| t.get_description() == "Lunch").unwrap();
+        assert_eq!(lunch.get_date(), chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+    }
+
+    #[test]
+    fn split_transaction() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let (mut ledger, _) = test_ledger();
+        for category in ["groceries", "household"] {
+            ledger.get_transaction_categories_mut().create_category(category.to_string()).unwrap();
+        }
+        let cmd = Transaction::new();
+        let split_args = ["checking", "-100", "Costco", "--split", "-60=groceries", "--split", "-40=household;Paper towels"];
+        assert!(cmd.execute(&split_args, &mut ledger, &mut app).is_ok());
+
+        let account = ledger.get_account_by_name("checking").unwrap();
+        let costco = account.get_transactions().iter().find(|t| t.get_description() == "Costco").unwrap();
+        assert_eq!(costco.get_splits().len(), 2);
+        assert_eq!(costco.get_splits()[1].get_memo(), "Paper towels");
+        let costco_id = costco.get_id().to_string();
+
+        // Splits have to add up, and need real categories
+        assert!(cmd.execute(&["checking", "-100", "Costco", "--split", "-60=groceries", "--split", "-30=household"],
+            &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["checking", "-100", "Costco", "--split", "-100=nope"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["checking", "-100", "Costco", "--split", "-100"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["checking", "-100", "Costco", "groceries", "--split", "-100=groceries"],
+            &mut ledger, &mut app).is_err());
+
+        assert!(cmd.execute(&["--edit", &costco_id, "--amount", "-50"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--edit", &costco_id, "--amount", "-50", "--split", "-50=;Snacks"],
+            &mut ledger, &mut app).is_ok());
+        assert!(cmd.execute(&["--edit", &costco_id, "--no-splits", "--category", "groceries"],
+            &mut ledger, &mut app).is_ok());
+        let costco = ledger.get_transaction(TransactionId::from(costco_id.parse::<u64>().unwrap())).unwrap();
+        assert!(costco.get_splits().is_empty());
+        assert_eq!(costco.get_category(), Some(&CategoryId::from("groceries".to_string())));
+    }
+
+    #[test]
+    fn split_to_subcategory() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let (mut ledger, _) = test_ledger();
+        let groceries = ledger.get_transaction_categories_mut().create_category("Food:Groceries".to_string()).unwrap();
+        let cmd = Transaction::new();
+        let split_args = ["checking", "-100", "Costco", "--split", "-60=Food:Groceries;Fruit", "--split", "-40=Food"];
+        assert!(cmd.execute(&split_args, &mut ledger, &mut app).is_ok());
+
+        let account = ledger.get_account_by_name("checking").unwrap();
+        let costco = account.get_transactions().iter().find(|t| t.get_description() == "Costco").unwrap();
+        assert_eq!(costco.get_splits()[0].get_category(), Some(&groceries));
+        assert_eq!(costco.get_splits()[0].get_memo(), "Fruit");
+        // The old AMOUNT:CATEGORY form isn't read as a category and a memo anymore
+        assert!(cmd.execute(&["checking", "-60", "Costco", "--split", "-60:Food:Groceries"], &mut ledger, &mut app).is_err());
+    }
+
+    #[test]
+    fn edit_transaction() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let (mut ledger, id) = test_ledger();
+        let cmd = Transaction::new();
+        let id_arg = id.to_string();
+        assert!(cmd.execute(&["--edit", &id_arg, "--amount", "25.50", "--date", "2024-03-01"], &mut ledger, &mut app).is_ok());
+        assert_eq!(ledger.get_transaction(id).unwrap().get_amount(), &Money::new(2550, Currency::USD));
+        assert_eq!(ledger.get_transaction(id).unwrap().get_date(), chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+    }
+
+    #[test]
+    fn edit_status() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let (mut ledger, id) = test_ledger();
+        let cmd = Transaction::new();
+        let id_arg = id.to_string();
+        assert!(cmd.execute(&["--edit", &id_arg, "--status", "cleared"], &mut ledger, &mut app).is_ok());
+        assert_eq!(ledger.get_transaction(id).unwrap().get_status(), TransactionStatus::Cleared);
+        assert!(cmd.execute(&["--edit", &id_arg, "--status", "reconciled"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--edit", &id_arg, "--status", "bounced"], &mut ledger, &mut app).is_err());
+    }
+
+    #[test]
+    fn edit_status_with_changes() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let (mut ledger, id) = test_ledger();
+        let cmd = Transaction::new();
+        let id_arg = id.to_string();
+
+        // A failed edit leaves the status alone
+        assert!(cmd.execute(&["--edit", &id_arg, "--status", "cleared", "--split", "0.01="], &mut ledger, &mut app).is_err());
+        assert_eq!(ledger.get_transaction(id).unwrap().get_status(), TransactionStatus::Pending);
+        assert!(cmd.execute(&["--edit", &id_arg, "--status", "cleared", "--description", "Gadgets"], &mut ledger, &mut app).is_ok());
+        assert_eq!(ledger.get_transaction(id).unwrap().get_status(), TransactionStatus::Cleared);
+
+        let account = ledger.get_account_for_transaction(id).unwrap().get_id();
+        let date = ledger.get_transaction(id).unwrap().get_date();
+        let balance = ledger.get_account(account).unwrap().balance_at(date);
+        ledger.reconcile(account, date, balance).unwrap();
+        assert!(cmd.execute(&["--edit", &id_arg, "--status", "pending", "--description", "Gizmos"], &mut ledger, &mut app).is_err());
+        let transaction = ledger.get_transaction(id).unwrap();
+        assert_eq!(transaction.get_status(), TransactionStatus::Reconciled);
+        assert_eq!(transaction.get_description(), "Gadgets");
+    }
+
+    #[test]
+    fn edit_invalid_transaction() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let (mut ledger, _) = test_ledger();
+        let cmd = Transaction::new();
+        assert!(cmd.execute(&["--edit", "100", "--amount", "1"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--edit", "abc"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--edit", "1", "--amount", "1.001"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["--edit", "1", "--category", "nope"], &mut ledger, &mut app).is_err());
+
+        ledger.get_transaction_categories_mut().create_category("groceries".to_string()).unwrap();
+        assert!(cmd.execute(&["--edit", "1", "--category", "groceries", "--no-category"], &mut ledger, &mut app).is_err());
+        assert_eq!(ledger.get_transaction(TransactionId::from(1)).unwrap().get_category(), None);
+    }
+
+    #[test]
+    fn delete_transaction_confirmed() {
+        let (mut app, _) = test_app_with_output(&["y"]);
+        let (mut ledger, id) = test_ledger();
+        let cmd = Transaction::new();
+        let id_arg = id.to_string();
+        assert!(cmd.execute(&["--delete", &id_arg], &mut ledger, &mut app).is_ok());
+        assert!(ledger.get_transaction(id).is_none());
+    }
+
+    #[test]
+    fn delete_transaction_cancelled() {
+        let (mut app, _) = test_app_with_output(&["n"]);
+        let (mut ledger, id) = test_ledger();
+        let cmd = Transaction::new();
+        let id_arg = id.to_string();
+        assert!(cmd.execute(&["--delete", &id_arg], &mut ledger, &mut app).is_ok());
+        assert!(ledger.get_transaction(id).is_some());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,82 @@
+use ledger::{common_types::Money, Ledger};
+
+use crate::app::Application;
+
+use super::{get_account, parse_time_or_now, Cmd, CmdError, CmdErrorType, CmdResult, ParsedArgs, SyntaxErrorType};
+
+/// Command to move money between two accounts
+pub struct Transfer { }
+
+impl Cmd for Transfer {
+    fn new() -> Transfer {
+        Transfer { }
+    }
+
+    fn execute(&self, args: &[&str], ledger: &mut Ledger, app: &mut Application) -> Result<CmdResult, CmdError> {
+        let args = ParsedArgs::parse(args, &["--date"], &[])
+            .map_err(|e| self.new_error(CmdErrorType::Syntax(e)))?;
+        let positional = args.positional();
+        if positional.len() < 3 {
+            return Err(self.new_error(
+                CmdErrorType::Syntax(SyntaxErrorType::MissingParam(
+                    "Invalid format. Usage: `transfer FROM TO AMOUNT [DESCRIPTION]`".to_string()))))
+        }
+
+        let from = get_account(Some(positional[0]), ledger).map_err(|e| self.new_error(e))?;
+        let to = get_account(Some(positional[1]), ledger).map_err(|e| self.new_error(e))?;
+        let (from_id, to_id) = (from.get_id(), to.get_id());
+        let description = match positional.get(3) {
+            Some(description) => description.to_string(),
+            None => format!("Transfer from {} to {}", from.get_name(), to.get_name()),
+        };
+
+        let amount = Money::parse(positional[2], from.get_currency(), app.config().number_format)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e.to_string())))?;
+        let time = parse_time_or_now(args.value("--date"), app).map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+
+        let (withdrawal, deposit) = ledger.transfer(from_id, to_id, amount, time, description)
+            .map_err(|e| self.new_error(CmdErrorType::Argument(e)))?;
+        writeln!(app.out(), "Created transfer of {} (transactions {} and {})", amount, withdrawal, deposit)?;
+        Ok(CmdResult::Ok)
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        vec!["transfer", "tf"]
+    }
+
+    fn help_text(&self) -> &'static str {
+"Usage: transfer FROM TO AMOUNT [DESCRIPTION] [--date DATE]
+Moves AMOUNT from account FROM to account TO, which must hold the same currency. This creates a linked pair of
+transactions: editing or deleting either one with the transaction command changes both. Transfers aren't counted
+as income or expenses.
+
+DATE defaults to today, and is written the same way as for the transaction command."
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger::common_types::Currency;
+
+    use crate::cmd::test_app_with_output;
+
+    use super::*;
+
+    #[test]
+    fn transfer() {
+        let (mut app, _) = test_app_with_output(&[]);
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let savings = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        ledger.add_new_account("Girokonto".to_string(), Currency::EUR).unwrap();
+        let cmd = Transfer::new();
+
+        assert!(cmd.execute(&["checking", "savings", "500", "--date", "2024-03-01"], &mut ledger, &mut app).is_ok());
+        assert_eq!(ledger.get_account(checking).unwrap().get_total(), Money::new(-50000, Currency::USD));
+        assert_eq!(ledger.get_account(savings).unwrap().get_total(), Money::new(50000, Currency::USD));
+
+        assert!(cmd.execute(&["checking", "girokonto", "500"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["checking", "nowhere", "500"], &mut ledger, &mut app).is_err());
+        assert!(cmd.execute(&["checking", "savings"], &mut ledger, &mut app).is_err());
+    }
+}
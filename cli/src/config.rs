@@ -0,0 +1,124 @@
+//! User preferences for how the application reads and displays data. These describe the user's environment rather
+//! than their financial data, so they live with the application instead of in the ledger. They aren't persisted; each
+//! run starts again from the locale.
+
+use ledger::common_types::NumberFormat;
+
+use crate::dates::DateFormat;
+
+// Languages that write 1.234,56 rather than 1,234.56
+static DECIMAL_COMMA_LANGUAGES: [&str; 16] = [
+    "da", "de", "es", "fi", "fr", "id", "it", "nb", "nl", "nn", "pl", "pt", "ru", "sv", "tr", "cs",
+];
+
+#[derive(Debug, Default, Clone)]
+pub struct Config {
+    pub number_format: NumberFormat,
+    pub date_format: DateFormat,
+}
+
+impl Config {
+    /// Build a config from the locale environment variables, using defaults for anything that isn't set.
+    pub fn from_env() -> Config {
+        let locale_for = |category: &str| ["LC_ALL", category, "LANG"].iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty());
+
+        Config {
+            number_format: locale_for("LC_NUMERIC").as_deref().map_or(NumberFormat::default(), number_format_for_locale),
+            date_format: locale_for("LC_TIME").as_deref().map_or(DateFormat::default(), date_format_for_locale),
+        }
+    }
+
+    /// Change a setting by name, e.g. `set("date-format", "eu")`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid_value = || format!("Invalid value '{}' for {}", value, key);
+        match key {
+            "number-format" => {
+                self.number_format = match value.to_ascii_lowercase().as_str() {
+                    "standard" => NumberFormat::Standard,
+                    "european" => NumberFormat::European,
+                    _ => return Err(invalid_value()),
+                };
+            },
+            "date-format" => {
+                self.date_format = match value.to_ascii_lowercase().as_str() {
+                    "us" => DateFormat::Us,
+                    "eu" => DateFormat::Eu,
+                    _ => return Err(invalid_value()),
+                };
+            },
+            _ => return Err(format!("No setting named '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Every setting as a (name, value) pair, using the same names and values `set` takes.
+    pub fn settings(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("number-format", match self.number_format {
+                NumberFormat::Standard => "standard",
+                NumberFormat::European => "european",
+            }),
+            ("date-format", match self.date_format {
+                DateFormat::Us => "us",
+                DateFormat::Eu => "eu",
+            }),
+        ]
+    }
+}
+
+/// Pick a number format from a POSIX locale name like `de_DE.UTF-8`.
+fn number_format_for_locale(locale: &str) -> NumberFormat {
+    let language = locale.split(['_', '.', '@']).next().unwrap_or("");
+    if DECIMAL_COMMA_LANGUAGES.contains(&language) {
+        NumberFormat::European
+    } else {
+        NumberFormat::Standard
+    }
+}
+
+/// Month-first dates are really only a US thing, so anything else gets day-first dates.
+fn date_format_for_locale(locale: &str) -> DateFormat {
+    let name = locale.split(['.', '@']).next().unwrap_or("");
+    if name == "C" || name == "POSIX" || name.ends_with("_US") {
+        DateFormat::Us
+    } else {
+        DateFormat::Eu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_format_for_locales() {
+        assert_eq!(number_format_for_locale("en_US.UTF-8"), NumberFormat::Standard);
+        assert_eq!(number_format_for_locale("C"), NumberFormat::Standard);
+        assert_eq!(number_format_for_locale("de_DE.UTF-8"), NumberFormat::European);
+        assert_eq!(number_format_for_locale("fr_FR@euro"), NumberFormat::European);
+        assert_eq!(number_format_for_locale("nl"), NumberFormat::European);
+    }
+
+    #[test]
+    fn date_format_for_locales() {
+        assert_eq!(date_format_for_locale("en_US.UTF-8"), DateFormat::Us);
+        assert_eq!(date_format_for_locale("C"), DateFormat::Us);
+        assert_eq!(date_format_for_locale("en_GB.UTF-8"), DateFormat::Eu);
+        assert_eq!(date_format_for_locale("de_DE"), DateFormat::Eu);
+    }
+
+    #[test]
+    fn set() {
+        let mut config = Config::default();
+        config.set("date-format", "EU").unwrap();
+        config.set("number-format", "european").unwrap();
+        assert_eq!(config.date_format, DateFormat::Eu);
+        assert_eq!(config.number_format, NumberFormat::European);
+        assert_eq!(config.settings(), vec![("number-format", "european"), ("date-format", "eu")]);
+
+        assert!(config.set("date-format", "mars").is_err());
+        assert!(config.set("colour", "blue").is_err());
+    }
+}
@@ -0,0 +1,292 @@
+//! Parsing of dates typed in by the user, e.g. `2024-03-01`, `yesterday`, `-3d` or `last friday`.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Days, FixedOffset, Months, NaiveDate, TimeZone, Weekday};
+
+/// How numeric dates with separators like 03/01/2024 are read. ISO dates (2024-03-01) are always accepted.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum DateFormat {
+    /// MM/DD/YYYY
+    #[default]
+    Us,
+    /// DD/MM/YYYY or DD.MM.YYYY
+    Eu,
+}
+
+/// A length of time to group things by, like balances in a history.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Interval {
+    /// Weeks run Monday to Sunday
+    Week,
+    Month,
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "week" | "weekly" => Ok(Interval::Week),
+            "month" | "monthly" => Ok(Interval::Month),
+            _ => Err(format!("Unknown interval '{}', expected week or month", s)),
+        }
+    }
+}
+
+/// The last day of every period touching the range `from` to `to`, except that the last one is cut off at `to`.
+pub fn period_ends(from: NaiveDate, to: NaiveDate, interval: Interval) -> Vec<NaiveDate> {
+    let mut ends = Vec::new();
+    let mut date = from;
+    while date <= to {
+        let end = match interval {
+            Interval::Week => date.checked_add_days(Days::new(6 - date.weekday().num_days_from_monday() as u64)),
+            Interval::Month => date.with_day(1)
+                .and_then(|first_of_month| first_of_month.checked_add_months(Months::new(1)))
+                .and_then(|first_of_next_month| first_of_next_month.checked_sub_days(Days::new(1))),
+        };
+        // A period running past the last date chrono can represent stops there
+        let end = end.unwrap_or(NaiveDate::MAX).min(to);
+        ends.push(end);
+        let Some(next) = end.checked_add_days(Days::new(1)) else { break };
+        date = next;
+    }
+    ends
+}
+
+/// Parse a date relative to `today`. Accepts:
+///  - ISO dates: 2024-03-01
+///  - today, yesterday, tomorrow
+///  - offsets in days, weeks, months or years: -3d, +1w, -2m, -1y (days if the unit is left off)
+///  - weekdays: last friday, next monday, or just friday for the most recent one (today included)
+///  - numeric dates in the given format, with or without the year: 03/01/2024, 03/01
+pub fn parse_date(input: &str, today: NaiveDate, format: DateFormat) -> Result<NaiveDate, String> {
+    let invalid = || format!("Could not understand the date '{}'", input);
+    let normalized = input.trim().to_lowercase();
+
+    match normalized.as_str() {
+        "today" => return Ok(today),
+        "yesterday" => return Ok(today - Days::new(1)),
+        "tomorrow" => return Ok(today + Days::new(1)),
+        _ => {},
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(&normalized, "%Y-%m-%d") {
+        return Ok(date);
+    }
+
+    if let Some(date) = parse_relative(&normalized, today) {
+        return date.ok_or_else(invalid);
+    }
+
+    if let Some(date) = parse_weekday(&normalized, today) {
+        return Ok(date);
+    }
+
+    parse_numeric(&normalized, today, format).ok_or_else(invalid)
+}
+
+/// The time to record for something that happened on `date`. Today's date means right now; other days don't have
+/// a meaningful time, so they're recorded at noon local time, well clear of any daylight-savings changes.
+pub fn time_on_date<Tz: TimeZone>(date: NaiveDate, now: DateTime<Tz>) -> DateTime<FixedOffset> {
+    if date == now.date_naive() {
+        return now.fixed_offset();
+    }
+
+    let noon = date.and_hms_opt(12, 0, 0).expect("Noon is a valid time");
+    now.timezone().from_local_datetime(&noon)
+        .earliest()
+        .map(|t| t.fixed_offset())
+        .unwrap_or_else(|| {
+            let offset = now.fixed_offset().timezone();
+            offset.from_local_datetime(&noon).single().expect("Fixed offsets are never ambiguous")
+        })
+}
+
+/// Returns None if the input doesn't look like an offset at all, and Some(None) if it does but is out of range.
+fn parse_relative(s: &str, today: NaiveDate) -> Option<Option<NaiveDate>> {
+    let (sign, rest) = match s.chars().next()? {
+        '-' => (-1, &s[1..]),
+        '+' => (1, &s[1..]),
+        _ => return None,
+    };
+
+    let digits_len = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits_len == 0 {
+        return None;
+    }
+    let count: u32 = rest[..digits_len].parse().ok()?;
+    let unit = &rest[digits_len..];
+
+    let shifted = match (unit, sign) {
+        ("" | "d", 1) => today.checked_add_days(Days::new(count as u64)),
+        ("" | "d", _) => today.checked_sub_days(Days::new(count as u64)),
+        ("w", 1) => today.checked_add_days(Days::new(count as u64 * 7)),
+        ("w", _) => today.checked_sub_days(Days::new(count as u64 * 7)),
+        ("m", 1) => today.checked_add_months(Months::new(count)),
+        ("m", _) => today.checked_sub_months(Months::new(count)),
+        ("y", 1) => today.checked_add_months(Months::new(count.checked_mul(12)?)),
+        ("y", _) => today.checked_sub_months(Months::new(count.checked_mul(12)?)),
+        _ => return None,
+    };
+    Some(shifted)
+}
+
+fn parse_weekday(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let (direction, name) = match s.split_once(' ') {
+        Some(("last", name)) => ("last", name.trim()),
+        Some(("next", name)) => ("next", name.trim()),
+        Some(_) => return None,
+        None => ("", s),
+    };
+    let weekday: Weekday = name.parse().ok()?;
+
+    let today_index = today.weekday().num_days_from_monday() as i64;
+    let target_index = weekday.num_days_from_monday() as i64;
+    let days_back = (today_index - target_index).rem_euclid(7);
+    match direction {
+        "last" => Some(today - Days::new(if days_back == 0 {7} else {days_back} as u64)),
+        "next" => {
+            let days_forward = (target_index - today_index).rem_euclid(7);
+            Some(today + Days::new(if days_forward == 0 {7} else {days_forward} as u64))
+        },
+        _ => Some(today - Days::new(days_back as u64)),
+    }
+}
+
+fn parse_numeric(s: &str, today: NaiveDate, format: DateFormat) -> Option<NaiveDate> {
+    let parts: Vec<&str> = s.split(['/', '.']).collect();
+    if parts.len() < 2 || parts.len() > 3 || parts.iter().any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+
+    // Year first is unambiguous, e.g. 2024/03/01
+    if parts.len() == 3 && parts[0].len() == 4 {
+        return NaiveDate::from_ymd_opt(parts[0].parse().ok()?, parts[1].parse().ok()?, parts[2].parse().ok()?);
+    }
+
+    let (month, day) = match format {
+        DateFormat::Us => (parts[0], parts[1]),
+        DateFormat::Eu => (parts[1], parts[0]),
+    };
+    let year = match parts.get(2) {
+        Some(year) if year.len() == 2 => 2000 + year.parse::<i32>().ok()?,
+        Some(year) if year.len() == 4 => year.parse().ok()?,
+        Some(_) => return None,
+        None => today.year(),
+    };
+
+    NaiveDate::from_ymd_opt(year, month.parse().ok()?, day.parse().ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    // A Wednesday
+    fn today() -> NaiveDate {
+        date(2024, 3, 13)
+    }
+
+    fn parse(s: &str) -> Result<NaiveDate, String> {
+        parse_date(s, today(), DateFormat::Us)
+    }
+
+    #[test]
+    fn iso() {
+        assert_eq!(parse("2024-01-31"), Ok(date(2024, 1, 31)));
+        assert!(parse("2024-02-30").is_err());
+    }
+
+    #[test]
+    fn named_days() {
+        assert_eq!(parse("today"), Ok(today()));
+        assert_eq!(parse("Yesterday"), Ok(date(2024, 3, 12)));
+        assert_eq!(parse("tomorrow"), Ok(date(2024, 3, 14)));
+    }
+
+    #[test]
+    fn relative() {
+        assert_eq!(parse("-3d"), Ok(date(2024, 3, 10)));
+        assert_eq!(parse("-3"), Ok(date(2024, 3, 10)));
+        assert_eq!(parse("+1w"), Ok(date(2024, 3, 20)));
+        assert_eq!(parse("-1m"), Ok(date(2024, 2, 13)));
+        assert_eq!(parse("-1y"), Ok(date(2023, 3, 13)));
+        assert!(parse("-3x").is_err());
+        assert!(parse("-d").is_err());
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(parse("last friday"), Ok(date(2024, 3, 8)));
+        assert_eq!(parse("last wednesday"), Ok(date(2024, 3, 6)));
+        assert_eq!(parse("wednesday"), Ok(today()));
+        assert_eq!(parse("mon"), Ok(date(2024, 3, 11)));
+        assert_eq!(parse("next wed"), Ok(date(2024, 3, 20)));
+        assert_eq!(parse("next thursday"), Ok(date(2024, 3, 14)));
+        assert!(parse("last week").is_err());
+    }
+
+    #[test]
+    fn us_format() {
+        assert_eq!(parse("03/01/2024"), Ok(date(2024, 3, 1)));
+        assert_eq!(parse("3/1/24"), Ok(date(2024, 3, 1)));
+        assert_eq!(parse("12/25"), Ok(date(2024, 12, 25)));
+        assert!(parse("25/12/2024").is_err());
+    }
+
+    #[test]
+    fn eu_format() {
+        let parse = |s| parse_date(s, today(), DateFormat::Eu);
+        assert_eq!(parse("01/03/2024"), Ok(date(2024, 3, 1)));
+        assert_eq!(parse("25.12.2024"), Ok(date(2024, 12, 25)));
+        assert_eq!(parse("25.12"), Ok(date(2024, 12, 25)));
+        assert_eq!(parse("2024/12/25"), Ok(date(2024, 12, 25)));
+        assert!(parse("12/25/2024").is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        for s in ["", "someday", "1/2/3/4", "1//2", "1/2/345"] {
+            assert!(parse(s).is_err(), "'{}' should not parse", s);
+        }
+    }
+
+    #[test]
+    fn month_ends() {
+        assert_eq!(
+            period_ends(date(2024, 1, 15), date(2024, 3, 13), Interval::Month),
+            vec![date(2024, 1, 31), date(2024, 2, 29), date(2024, 3, 13)]);
+        assert_eq!(period_ends(date(2024, 3, 13), date(2024, 3, 1), Interval::Month), vec![]);
+        assert_eq!(period_ends(NaiveDate::MAX, NaiveDate::MAX, Interval::Month), vec![NaiveDate::MAX]);
+        assert_eq!(period_ends(NaiveDate::MAX, NaiveDate::MAX, Interval::Week), vec![NaiveDate::MAX]);
+    }
+
+    #[test]
+    fn week_ends() {
+        assert_eq!(
+            period_ends(date(2024, 2, 28), date(2024, 3, 13), Interval::Week),
+            vec![date(2024, 3, 3), date(2024, 3, 10), date(2024, 3, 13)]);
+        assert_eq!("Weekly".parse::<Interval>(), Ok(Interval::Week));
+        assert!("daily".parse::<Interval>().is_err());
+    }
+
+    #[test]
+    fn time_today_is_now() {
+        let now = DateTime::parse_from_rfc3339("2024-03-13T23:30:00-05:00").unwrap();
+        assert_eq!(time_on_date(today(), now), now);
+    }
+
+    #[test]
+    fn time_on_other_days_keeps_local_day() {
+        let now = DateTime::parse_from_rfc3339("2024-03-13T23:30:00-05:00").unwrap();
+        let time = time_on_date(date(2024, 3, 1), now);
+        assert_eq!(time.date_naive(), date(2024, 3, 1));
+        assert_eq!(time.offset(), now.offset());
+        assert_eq!(time.to_rfc3339(), "2024-03-01T12:00:00-05:00");
+    }
+}
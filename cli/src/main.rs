@@ -5,13 +5,16 @@
 
 mod app;
 mod cmd;
+mod config;
+mod dates;
 mod store;
 mod ui;
 
-use std::{collections::{HashMap, VecDeque}, error::Error, path::PathBuf, rc::Rc};
+use std::{collections::{HashMap, VecDeque}, error::Error, io::ErrorKind, path::PathBuf, rc::Rc};
 
 use app::Application;
 use cmd::{Cmd, CmdError, CmdErrorType, CmdResult};
+use config::Config;
 use ledger::Ledger;
 use store::FileStore;
 #[mockall_double::double]
@@ -24,8 +27,9 @@ const MAX_HISTORY_LENGTH: usize = 100;
 fn main() -> Result<(), Box<dyn Error>> {
     let interface = TerminalInterface::create()?;
     let file_store = FileStore::new(&default_file_store_location());
-    let application = Application::new(interface, file_store);
+    let application = Application::new(interface, file_store, Config::from_env());
     let mut cli_app = CliRunner::create(command_list(), application)?;
+    cli_app.load_saved_ledger()?;
 
     if let Err(e) = cli_app.run() {
         eprintln!("Encountered fatal error: {e}");
@@ -40,11 +44,23 @@ fn main() -> Result<(), Box<dyn Error>> {
 fn command_list() -> Vec<Rc<dyn Cmd>> {
     vec![
         Rc::new(cmd::account::Account::new()),
+        Rc::new(cmd::balance::Balance::new()),
+        Rc::new(cmd::budget::Budget::new()),
         Rc::new(cmd::category::Category::new()),
+        Rc::new(cmd::check::Check::new()),
+        Rc::new(cmd::config::Config::new()),
+        Rc::new(cmd::envelope::Envelope::new()),
         Rc::new(cmd::exit::Exit::new()),
         Rc::new(cmd::load::Load::new()),
+        Rc::new(cmd::payee::Payee::new()),
+        Rc::new(cmd::rate::Rate::new()),
+        Rc::new(cmd::reconcile::Reconcile::new()),
+        Rc::new(cmd::report::Report::new()),
+        Rc::new(cmd::schedule::Schedule::new()),
         Rc::new(cmd::store::Store::new()),
+        Rc::new(cmd::tag::Tag::new()),
         Rc::new(cmd::transaction::Transaction::new()),
+        Rc::new(cmd::transfer::Transfer::new()),
     ]
 }
 
@@ -80,12 +96,24 @@ impl CliRunner {
         Ok(CliRunner {
             cmd_map,
             cmd_list: cmds,
-            ledger: Ledger::new_empty(), // TODO: load exiting one
+            // Replaced by the saved one in `load_saved_ledger`
+            ledger: Ledger::new_empty(),
             app,
             input_history: VecDeque::new(),
         })
     }
 
+    /// Start from the saved ledger, if one has been stored, and post anything that came due since it was last used.
+    fn load_saved_ledger(&mut self) -> Result<(), CmdError> {
+        match self.app.load_ledger() {
+            Ok(ledger) => self.ledger = ledger,
+            Err(e) if e.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == ErrorKind::NotFound) => return Ok(()),
+            Err(e) => return Err(CmdError{cmd_name: None, error_type: CmdErrorType::Dependency(e)}),
+        }
+        cmd::schedule::Schedule::new().post_due(&mut self.ledger, &mut self.app)?;
+        Ok(())
+    }
+
     // TODO: Write tests for this function
     fn run(&mut self) -> Result<(), Box<dyn Error>> {
 
@@ -270,6 +298,7 @@ mod cli_app_tests {
 
     use std::cell::RefCell;
     
+    use ledger::{common_types::{Currency, Money}, schedule::{Frequency, Schedule, ScheduleEnd}};
     use mockall::{Sequence, predicate::eq};
 
     use super::*;
@@ -277,7 +306,8 @@ mod cli_app_tests {
     fn create_test_app(interface: TerminalInterface) -> Application {
         Application::new(
             interface, 
-            store::mock::MockFileStore::default()) 
+            store::mock::MockFileStore::default(),
+            Config::default()) 
     }
 
     #[test]
@@ -285,6 +315,40 @@ mod cli_app_tests {
         let _ = CliRunner::create(vec![], create_test_app(TerminalInterface::new()));
     }
 
+    #[test]
+    fn load_saved_ledger_posts_due_schedules() {
+        let mut interface = TerminalInterface::new();
+        interface.expect_write().returning(|s| Ok(s.len()));
+        let mut file_store = store::mock::MockFileStore::default();
+        file_store.expect_load_ledger().times(1).returning(|| {
+            let mut ledger = Ledger::new_empty();
+            let account = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+            let start = chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+            let schedule = Schedule::new(account, Money::new(-999, Currency::USD), "Music".to_string(), None, Frequency::Yearly, start);
+            ledger.add_schedule(schedule.with_end(ScheduleEnd::After(2))).unwrap();
+            Ok(ledger)
+        });
+        let mut runner = CliRunner::create(vec![], Application::new(interface, file_store, Config::default())).unwrap();
+
+        runner.load_saved_ledger().unwrap();
+        assert_eq!(runner.ledger.get_accounts()[0].get_transactions().len(), 2);
+    }
+
+    #[test]
+    fn load_saved_ledger_starts_empty_without_one() {
+        let mut file_store = store::mock::MockFileStore::default();
+        file_store.expect_load_ledger().times(1)
+            .returning(|| Err(Box::new(std::io::Error::from(ErrorKind::NotFound))));
+        let mut runner = CliRunner::create(vec![], Application::new(TerminalInterface::new(), file_store, Config::default())).unwrap();
+        assert!(runner.load_saved_ledger().is_ok());
+        assert!(runner.ledger.get_accounts().is_empty());
+
+        let mut file_store = store::mock::MockFileStore::default();
+        file_store.expect_load_ledger().times(1).returning(|| Err(Box::from("Unsupported file version v9, cannot load file")));
+        let mut runner = CliRunner::create(vec![], Application::new(TerminalInterface::new(), file_store, Config::default())).unwrap();
+        assert!(runner.load_saved_ledger().is_err());
+    }
+
     #[test]
     fn tokenize_empty_string() {
         let s = String::from("");
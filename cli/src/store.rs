@@ -1,3 +1,9 @@
+#[cfg(test)]
+pub use mock::MockFileStore as FileStore;
+
+#[cfg(not(test))]
+pub use store::FileStore;
+
 #[cfg(test)] 
 pub mod mock {
     use std::error::Error;
@@ -11,8 +17,3 @@ pub mod mock {
         }
     }
 }
-#[cfg(test)]
-pub use mock::MockFileStore as FileStore;
-
-#[cfg(not(test))]
-pub use store::FileStore;
\ No newline at end of file
@@ -98,6 +98,22 @@ impl std::io::Write for TerminalInterface {
     }
 }
 
+static SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A one-line chart of the values, using block characters of increasing height.
+pub fn sparkline(values: &[i64]) -> String {
+    let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+    let range = (*max as i128) - (*min as i128);
+    values.iter()
+        .map(|v| {
+            let index = if range == 0 { 0 } else { ((*v as i128 - *min as i128) * 7 / range) as usize };
+            SPARK_CHARS[index]
+        })
+        .collect()
+}
+
 #[cfg(test)] 
 mockall::mock! {
     pub TerminalInterface {
@@ -109,4 +125,17 @@ mockall::mock! {
         fn write(&mut self, buf: &[u8]) -> std::io::Result<usize>;
         fn flush(&mut self) -> std::io::Result<()>;
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparklines() {
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[5, 5]), "▁▁");
+        assert_eq!(sparkline(&[-100, 0, 100]), "▁▄█");
+        assert_eq!(sparkline(&[i64::MIN, i64::MAX]), "▁█");
+    }
+}
@@ -1,27 +1,342 @@
-use crate::{transaction::Transaction, common_types::Money};
+use std::{fmt::Display, str::FromStr};
+
+use chrono::NaiveDate;
+
+use crate::{category::CategoryId, transaction::{Transaction, TransactionId, TransactionStatus, TransactionUpdate}, common_types::{Currency, Money}};
+
+/// Uniquely identifies an account within a ledger. Unlike the name, it never changes. 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub struct AccountId(u64);
+
+impl From<u64> for AccountId {
+    fn from(value: u64) -> Self {
+        AccountId(value)
+    }
+}
+
+impl From<AccountId> for u64 {
+    fn from(value: AccountId) -> Self {
+        value.0
+    }
+}
+
+impl Display for AccountId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// What an account represents. Money in positive amounts is a debit and negative amounts are a credit, so asset and
+/// expense accounts normally have positive totals, and liability, equity and income accounts negative ones.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub enum AccountType {
+    Checking,
+    Savings,
+    Investment,
+    Cash,
+    #[default]
+    Asset,
+    CreditCard,
+    Loan,
+    Liability,
+    Equity,
+    Income,
+    Expense,
+}
+
+static ACCOUNT_TYPE_NAMES: [(AccountType, &str); 11] = [
+    (AccountType::Checking, "checking"),
+    (AccountType::Savings, "savings"),
+    (AccountType::Investment, "investment"),
+    (AccountType::Cash, "cash"),
+    (AccountType::Asset, "asset"),
+    (AccountType::CreditCard, "credit-card"),
+    (AccountType::Loan, "loan"),
+    (AccountType::Liability, "liability"),
+    (AccountType::Equity, "equity"),
+    (AccountType::Income, "income"),
+    (AccountType::Expense, "expense"),
+];
+
+impl AccountType {
+    /// Whether the account holds something the user owns or owes, as opposed to tracking where money came from or
+    /// went to.
+    pub fn is_balance_sheet(&self) -> bool {
+        !matches!(self, AccountType::Equity | AccountType::Income | AccountType::Expense)
+    }
+
+    /// Whether the account holds money the user owes, like a credit card or a loan.
+    pub fn is_liability(&self) -> bool {
+        matches!(self, AccountType::CreditCard | AccountType::Loan | AccountType::Liability)
+    }
+
+    /// Turns a balance as the bank states it into the ledger's sign convention. For liabilities, the bank states the
+    /// amount owed, which the ledger keeps negative.
+    pub fn from_stated_balance(&self, balance: Money) -> Result<Money, String> {
+        if self.is_liability() {
+            balance.checked_neg().map_err(|e| e.to_string())
+        } else {
+            Ok(balance)
+        }
+    }
+
+    /// Every type, in the order they're usually listed.
+    pub fn all() -> impl Iterator<Item = AccountType> {
+        ACCOUNT_TYPE_NAMES.iter().map(|(t, _)| *t)
+    }
+}
+
+impl Display for AccountType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, name) = ACCOUNT_TYPE_NAMES.iter().find(|(t, _)| t == self).expect("Every type has a name");
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for AccountType {
+    type Err = String;
+
+    /// Accepts the names `Display` gives, ignoring case, and with `_` or a space allowed in place of `-`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_ascii_lowercase().replace(['_', ' '], "-");
+        ACCOUNT_TYPE_NAMES.iter()
+            .find(|(_, name)| *name == normalized)
+            .map(|(t, _)| *t)
+            .ok_or_else(|| format!("Unknown account type '{}'", s))
+    }
+}
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct Account {
+    id: AccountId,
     // For now, there's just a list of transactions. TODO: make this be not stupid. 
     transactions: Vec<Transaction>,
-    name: String
+    name: String,
+    currency: Currency,
+    #[serde(default)]
+    account_type: AccountType,
+    // The balance before any of the account's transactions, and the day it was taken
+    #[serde(default)]
+    opening_balance: Option<(Money, NaiveDate)>,
+    // Closed accounts keep their history, but don't take new transactions
+    #[serde(default)]
+    closed: bool,
+    // The date and balance of the last statement the account was reconciled against
+    #[serde(default)]
+    last_reconciled: Option<(NaiveDate, Money)>,
 }
 
 impl Account {
-    pub fn new_empty(name: String) -> Account {
-        Account {name, transactions: Vec::new()}
+    pub(crate) fn new_empty(id: AccountId, name: String, currency: Currency, account_type: AccountType) -> Account {
+        Account {id, name, transactions: Vec::new(), currency, account_type, opening_balance: None, closed: false, last_reconciled: None}
     }   
 
+    pub fn get_id(&self) -> AccountId {
+        self.id
+    }
+
     pub fn get_name(&self) -> &String {
         &self.name
     }
 
+    pub(crate) fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub(crate) fn set_closed(&mut self, closed: bool) {
+        self.closed = closed;
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_type(&self) -> AccountType {
+        self.account_type
+    }
+
+    /// The balance the account started with, if one was set, and the day it was taken. For liabilities it's
+    /// negative, like the total.
+    pub fn get_opening_balance(&self) -> Option<(Money, NaiveDate)> {
+        self.opening_balance
+    }
+
+    /// The opening balance plus every transaction since. Liabilities have a negative total when money is owed.
     pub fn get_total(&self) -> Money {
-        self.transactions.iter().map(|t| t.get_amount()).sum()
+        // Every amount was checked against the account currency on the way in, so this can't mismatch.
+        self.transactions_since_opening().fold(self.get_starting_balance(), |total, t| total + *t.get_amount())
     }
 
-    pub fn add_transaction(&mut self, transaction: Transaction) {
+    /// The balance at the end of `date`: the opening balance if it was taken by then, plus every transaction since it
+    /// up to and including that day.
+    pub fn balance_at(&self, date: NaiveDate) -> Money {
+        self.transactions_since_opening()
+            .filter(|t| t.get_date() <= date)
+            .fold(self.opening_balance_at(date), |total, t| total + *t.get_amount())
+    }
+
+    /// Like `balance_at`, but only counting transactions that have cleared or been reconciled. This is what the
+    /// balance on a bank statement for `date` should be.
+    pub fn cleared_balance_at(&self, date: NaiveDate) -> Money {
+        self.transactions_since_opening()
+            .filter(|t| t.get_date() <= date && t.get_status() != TransactionStatus::Pending)
+            .fold(self.opening_balance_at(date), |total, t| total + *t.get_amount())
+    }
+
+    /// The date and balance of the last statement the account was reconciled against.
+    pub fn get_last_reconciled(&self) -> Option<(NaiveDate, Money)> {
+        self.last_reconciled
+    }
+
+    pub(crate) fn set_last_reconciled(&mut self, date: NaiveDate, balance: Money) {
+        self.last_reconciled = Some((date, balance));
+    }
+
+    /// The earliest day the account has a balance for: its opening balance or first transaction, whichever is first.
+    pub fn get_start_date(&self) -> Option<NaiveDate> {
+        let first_transaction = self.transactions.iter().map(|t| t.get_date()).min();
+        let opened = self.opening_balance.map(|(_, date)| date);
+        first_transaction.into_iter().chain(opened).min()
+    }
+
+    /// The total without the opening balance.
+    pub fn get_transaction_total(&self) -> Money {
+        self.transactions.iter().fold(Money::zero(self.currency), |total, t| total + *t.get_amount())
+    }
+
+    // The opening balance if it was taken by the end of `date`, otherwise zero
+    fn opening_balance_at(&self, date: NaiveDate) -> Money {
+        match self.opening_balance {
+            Some((balance, opened)) if opened <= date => balance,
+            _ => Money::zero(self.currency),
+        }
+    }
+
+    // Transactions dated before the opening balance was taken are already part of it, so they don't count again
+    fn transactions_since_opening(&self) -> impl Iterator<Item = &Transaction> {
+        self.transactions.iter().filter(|t| self.is_since_opening(t))
+    }
+
+    fn is_since_opening(&self, transaction: &Transaction) -> bool {
+        self.opening_balance.is_none_or(|(_, opened)| transaction.get_date() >= opened)
+    }
+
+    fn get_starting_balance(&self) -> Money {
+        self.opening_balance.map_or(Money::zero(self.currency), |(balance, _)| balance)
+    }
+
+    pub fn get_transactions(&self) -> &Vec<Transaction> {
+        &self.transactions
+    }
+
+    /// Every transaction in date order, paired with the account balance just after it, starting from the opening
+    /// balance. Transactions at the same time are in the order they were added. Ones from before the opening balance
+    /// was taken are already part of it, so they leave the balance as it is.
+    pub fn get_running_balances(&self) -> Vec<(&Transaction, Money)> {
+        let mut sorted: Vec<&Transaction> = self.transactions.iter().collect();
+        sorted.sort_by_key(|t| t.chronological_key());
+
+        let mut balance = self.get_starting_balance();
+        sorted.into_iter()
+            .map(|t| {
+                if self.is_since_opening(t) {
+                    balance += *t.get_amount();
+                }
+                (t, balance)
+            })
+            .collect()
+    }
+
+    pub fn get_transaction(&self, id: TransactionId) -> Option<&Transaction> {
+        self.transactions.iter().find(|t| t.get_id() == id)
+    }
+
+    pub(crate) fn transactions_mut(&mut self) -> impl Iterator<Item = &mut Transaction> {
+        self.transactions.iter_mut()
+    }
+
+    /// Fails if the balance isn't in the account's currency.
+    pub(crate) fn set_opening_balance(&mut self, balance: Money, date: NaiveDate) -> Result<(), String> {
+        if balance.currency() != self.currency {
+            return Err(format!(
+                "Account '{}' holds {}, but the opening balance is in {}", self.name, self.currency, balance.currency()));
+        }
+        self.opening_balance = Some((balance, date));
+        Ok(())
+    }
+
+    /// Fails if the account is closed, or the transaction isn't in the same currency as the account. 
+    pub(crate) fn add_transaction(&mut self, transaction: Transaction) -> Result<(), String> {
+        self.check_new_transaction(&transaction)?;
         self.transactions.push(transaction);
+        Ok(())
+    }
+
+    /// Fails if `add_transaction` would.
+    pub(crate) fn check_new_transaction(&self, transaction: &Transaction) -> Result<(), String> {
+        self.check_open()?;
+        let currency = transaction.get_amount().currency();
+        if currency != self.currency {
+            return Err(format!(
+                "Account '{}' holds {}, but the transaction is in {}", self.name, self.currency, currency));
+        }
+        Ok(())
+    }
+
+    /// Fails if the transaction isn't in this account, the new amount isn't in the account's currency, or the
+    /// splits wouldn't add up.
+    pub(crate) fn update_transaction(&mut self, id: TransactionId, update: TransactionUpdate) -> Result<(), String> {
+        if let Some(currency) = update.amount.map(|a| a.currency()) {
+            if currency != self.currency {
+                return Err(format!(
+                    "Account '{}' holds {}, but the new amount is in {}", self.name, self.currency, currency));
+            }
+        }
+
+        let transaction = self.transactions.iter_mut().find(|t| t.get_id() == id)
+            .ok_or_else(|| format!("Account '{}' has no transaction {}", self.name, id))?;
+        transaction.check_update(&update)?;
+        transaction.apply_update(update);
+        Ok(())
+    }
+
+    pub(crate) fn set_transaction_status(&mut self, id: TransactionId, status: TransactionStatus) -> Result<(), String> {
+        let transaction = self.transactions.iter_mut().find(|t| t.get_id() == id)
+            .ok_or_else(|| format!("Account '{}' has no transaction {}", self.name, id))?;
+        transaction.set_status(status);
+        Ok(())
+    }
+
+    /// Move every transaction and split in `from` into `to`, returning how many transactions changed.
+    pub(crate) fn replace_category(&mut self, from: &CategoryId, to: Option<&CategoryId>) -> usize {
+        self.transactions.iter_mut().map(|t| t.replace_category(from, to)).filter(|changed| *changed).count()
+    }
+
+    /// Rename a tag on every transaction, or remove it if `to` is `None`, returning how many transactions had it.
+    pub(crate) fn replace_tag(&mut self, from: &str, to: Option<&str>) -> usize {
+        self.transactions.iter_mut().map(|t| t.replace_tag(from, to)).filter(|changed| *changed).count()
+    }
+
+    /// Fails if the account is closed.
+    pub(crate) fn check_open(&self) -> Result<(), String> {
+        if self.closed {
+            return Err(format!("Account '{}' is closed", self.name));
+        }
+        Ok(())
+    }
+
+    /// Remove every transaction from the account, returning them.
+    pub(crate) fn take_transactions(&mut self) -> Vec<Transaction> {
+        std::mem::take(&mut self.transactions)
+    }
+
+    pub(crate) fn remove_transaction(&mut self, id: TransactionId) -> Option<Transaction> {
+        let index = self.transactions.iter().position(|t| t.get_id() == id)?;
+        Some(self.transactions.remove(index))
     }
 }
 #[cfg(test)]
@@ -31,7 +346,11 @@ mod tests {
     use super::*;
 
     fn test_transaction(amount: f64) -> Transaction {
-        let amount = Money::from_float(amount);
+        test_transaction_in(amount, Currency::USD)
+    }
+
+    fn test_transaction_in(amount: f64, currency: Currency) -> Transaction {
+        let amount = Money::from_float(amount, currency);
         let time = "2000-1-1T00:00:00Z".parse::<DateTime<Utc>>().expect("Failed to parse");
         let description = "Widgets Inc.".to_string();
 
@@ -40,30 +359,191 @@ mod tests {
 
     #[test]
     fn new_empty() {
-        let account = Account::new_empty("Hello checking".to_string());
+        let account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
         assert_eq!(account.transactions.len(), 0);
-        assert_eq!(account.name, "Hello checking")
+        assert_eq!(account.name, "Hello checking");
+        assert_eq!(account.get_id(), AccountId(1));
     }
 
     #[test]
     fn add_transaction() {
-        let mut account = Account::new_empty("Hello checking".to_string());
-        account.add_transaction(test_transaction(100.0));
+        let mut account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
+        account.add_transaction(test_transaction(100.0)).unwrap();
         assert_eq!(account.transactions.len(), 1);
     }
 
+    #[test]
+    fn get_transaction() {
+        let mut account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
+        let mut transaction = test_transaction(100.0);
+        transaction.set_id(TransactionId::from(7));
+        account.add_transaction(transaction).unwrap();
+        assert_eq!(account.get_transaction(TransactionId::from(7)).unwrap().get_amount(), &Money::new(10000, Currency::USD));
+        assert!(account.get_transaction(TransactionId::from(8)).is_none());
+    }
+
+    #[test]
+    fn update_transaction() {
+        let mut account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
+        let mut transaction = test_transaction(100.0);
+        transaction.set_id(TransactionId::from(7));
+        account.add_transaction(transaction).unwrap();
+
+        let update = |amount| TransactionUpdate { amount: Some(amount), ..Default::default() };
+        assert!(account.update_transaction(TransactionId::from(7), update(Money::new(5, Currency::EUR))).is_err());
+        assert!(account.update_transaction(TransactionId::from(8), update(Money::new(5, Currency::USD))).is_err());
+        account.update_transaction(TransactionId::from(7), update(Money::new(5, Currency::USD))).unwrap();
+        assert_eq!(account.get_total(), Money::new(5, Currency::USD));
+    }
+
+    #[test]
+    fn remove_transaction() {
+        let mut account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
+        let mut transaction = test_transaction(100.0);
+        transaction.set_id(TransactionId::from(7));
+        account.add_transaction(transaction).unwrap();
+
+        assert!(account.remove_transaction(TransactionId::from(8)).is_none());
+        assert_eq!(account.remove_transaction(TransactionId::from(7)).unwrap().get_id(), TransactionId::from(7));
+        assert_eq!(account.transactions.len(), 0);
+    }
+
+    #[test]
+    fn get_running_balances() {
+        let mut account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
+        let at = |time: &str| time.parse::<DateTime<Utc>>().expect("Failed to parse");
+        let amounts = [(100.0, "2000-01-03T00:00:00Z"), (-30.0, "2000-01-01T00:00:00Z"), (5.0, "2000-01-02T00:00:00Z")];
+        for (i, (amount, time)) in amounts.iter().enumerate() {
+            let mut transaction = Transaction::new(
+                Money::from_float(*amount, Currency::USD), at(time), "Widgets Inc.".to_string(), None);
+            transaction.set_id(TransactionId::from(i as u64 + 1));
+            account.add_transaction(transaction).unwrap();
+        }
+
+        let balances: Vec<(TransactionId, Money)> = account.get_running_balances().iter()
+            .map(|(t, balance)| (t.get_id(), *balance))
+            .collect();
+        assert_eq!(balances, vec![
+            (TransactionId::from(2), Money::from_float(-30.0, Currency::USD)),
+            (TransactionId::from(3), Money::from_float(-25.0, Currency::USD)),
+            (TransactionId::from(1), Money::from_float(75.0, Currency::USD))]);
+    }
+
     #[test]
     fn get_total_empty() {
-        let account = Account::new_empty("Hello checking".to_string());
-        assert_eq!(account.get_total(), Money::from_float(0.0))
+        let account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
+        assert_eq!(account.get_total(), Money::from_float(0.0, Currency::USD))
+    }
+
+    #[test]
+    fn get_total_in_account_currency() {
+        let mut account = Account::new_empty(AccountId(1), "Girokonto".to_string(), Currency::EUR, AccountType::Asset);
+        account.add_transaction(test_transaction_in(12.5, Currency::EUR)).unwrap();
+        assert_eq!(account.get_total(), Money::new(1250, Currency::EUR))
+    }
+
+    #[test]
+    fn balance_at() {
+        let date = |d| NaiveDate::from_ymd_opt(2000, 1, d).unwrap();
+        let mut account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
+        assert_eq!(account.get_start_date(), None);
+        account.set_opening_balance(Money::new(10000, Currency::USD), date(2)).unwrap();
+        for (day, amount) in [(1, 100), (3, 200), (5, 400)] {
+            let time = DateTime::parse_from_rfc3339(&format!("2000-01-{:02}T12:00:00Z", day)).unwrap();
+            account.add_transaction(Transaction::new(Money::new(amount, Currency::USD), time, "".to_string(), None)).unwrap();
+        }
+
+        // The transaction on the 1st is already part of the opening balance
+        assert_eq!(account.balance_at(date(1)), Money::zero(Currency::USD));
+        assert_eq!(account.balance_at(date(2)), Money::new(10000, Currency::USD));
+        assert_eq!(account.balance_at(date(4)), Money::new(10200, Currency::USD));
+        assert_eq!(account.balance_at(date(31)), account.get_total());
+        assert_eq!(account.get_start_date(), Some(date(1)));
+    }
+
+    #[test]
+    fn cleared_balance_at() {
+        let date = |d| NaiveDate::from_ymd_opt(2000, 1, d).unwrap();
+        let mut account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
+        account.set_opening_balance(Money::new(10000, Currency::USD), date(1)).unwrap();
+        for (id, day, amount) in [(1, 2, 100), (2, 3, 200), (3, 5, 400)] {
+            let time = DateTime::parse_from_rfc3339(&format!("2000-01-{:02}T12:00:00Z", day)).unwrap();
+            let mut transaction = Transaction::new(Money::new(amount, Currency::USD), time, "".to_string(), None);
+            transaction.set_id(TransactionId::from(id));
+            account.add_transaction(transaction).unwrap();
+        }
+        account.set_transaction_status(TransactionId::from(1), TransactionStatus::Reconciled).unwrap();
+        account.set_transaction_status(TransactionId::from(3), TransactionStatus::Cleared).unwrap();
+
+        assert_eq!(account.cleared_balance_at(date(4)), Money::new(10100, Currency::USD));
+        assert_eq!(account.cleared_balance_at(date(5)), Money::new(10500, Currency::USD));
+    }
+
+    #[test]
+    fn add_transaction_closed() {
+        let mut account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
+        account.set_closed(true);
+        assert!(account.add_transaction(test_transaction(100.0)).is_err());
+        account.set_closed(false);
+        assert!(account.add_transaction(test_transaction(100.0)).is_ok());
+    }
+
+    #[test]
+    fn add_transaction_wrong_currency() {
+        let mut account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
+        assert!(account.add_transaction(test_transaction_in(100.0, Currency::JPY)).is_err());
+        assert_eq!(account.transactions.len(), 0);
     }
 
     #[test]
     fn get_total() {
-        let mut account = Account::new_empty("Hello checking".to_string());
-        account.add_transaction(test_transaction(100.0));
-        account.add_transaction(test_transaction(-50.0));
-        account.add_transaction(test_transaction(0.01));
-        assert_eq!(account.get_total(), Money::from_float(50.01))
+        let mut account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Asset);
+        account.add_transaction(test_transaction(100.0)).unwrap();
+        account.add_transaction(test_transaction(-50.0)).unwrap();
+        account.add_transaction(test_transaction(0.01)).unwrap();
+        assert_eq!(account.get_total(), Money::from_float(50.01, Currency::USD))
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn account_type_names() {
+        assert_eq!("Expense".parse::<AccountType>(), Ok(AccountType::Expense));
+        assert_eq!("credit card".parse::<AccountType>(), Ok(AccountType::CreditCard));
+        assert_eq!("Credit_Card".parse::<AccountType>(), Ok(AccountType::CreditCard));
+        for account_type in AccountType::all() {
+            assert_eq!(account_type.to_string().parse::<AccountType>(), Ok(account_type));
+        }
+        assert!("stuff".parse::<AccountType>().is_err());
+    }
+
+    #[test]
+    fn account_type_classes() {
+        assert!(AccountType::Savings.is_balance_sheet());
+        assert!(!AccountType::Savings.is_liability());
+        assert!(AccountType::Loan.is_balance_sheet());
+        assert!(AccountType::Loan.is_liability());
+        assert!(!AccountType::Income.is_balance_sheet());
+    }
+
+    #[test]
+    fn opening_balance() {
+        let date = NaiveDate::from_ymd_opt(1999, 12, 31).unwrap();
+        let mut account = Account::new_empty(AccountId(1), "Hello checking".to_string(), Currency::USD, AccountType::Checking);
+        assert!(account.set_opening_balance(Money::new(100, Currency::EUR), date).is_err());
+        account.set_opening_balance(Money::new(10000, Currency::USD), date).unwrap();
+        account.add_transaction(test_transaction(-50.0)).unwrap();
+
+        assert_eq!(account.get_total(), Money::new(5000, Currency::USD));
+        assert_eq!(account.get_transaction_total(), Money::new(-5000, Currency::USD));
+        assert_eq!(account.get_running_balances()[0].1, Money::new(5000, Currency::USD));
+        assert_eq!(account.get_opening_balance(), Some((Money::new(10000, Currency::USD), date)));
+
+        // Anything before the opening balance is already counted in it
+        let before = Transaction::new_on_date(Money::new(-2500, Currency::USD), date.pred_opt().unwrap(), "".to_string(), None);
+        account.add_transaction(before).unwrap();
+        assert_eq!(account.get_total(), Money::new(5000, Currency::USD));
+        assert_eq!(account.balance_at(date), Money::new(10000, Currency::USD));
+        assert_eq!(account.cleared_balance_at(date), Money::new(10000, Currency::USD));
+        assert_eq!(account.get_running_balances()[0].1, Money::new(10000, Currency::USD));
+        assert_eq!(account.get_running_balances()[1].1, Money::new(5000, Currency::USD));
+    }
+}
@@ -0,0 +1,154 @@
+//! Monthly spending budgets per category. A budget's amount can change from month to month, and a budget can roll
+//! whatever's left over (or overspent) into the next month.
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::{category::CategoryId, common_types::{Currency, Money}};
+
+/// The first day of the month `date` is in. Budgets are keyed by these.
+pub fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("Every month has a first day")
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct CategoryBudget {
+    category: CategoryId,
+    // The amount for each month it was set in, which holds until the next one. Keyed by the start of the month.
+    amounts: BTreeMap<NaiveDate, Money>,
+    // Whether what's left at the end of a month carries into the next one
+    rollover: bool,
+}
+
+impl CategoryBudget {
+    pub fn get_category(&self) -> &CategoryId {
+        &self.category
+    }
+
+    pub fn get_rollover(&self) -> bool {
+        self.rollover
+    }
+
+    /// The first month the budget applies to.
+    pub fn get_start(&self) -> NaiveDate {
+        *self.amounts.keys().next().expect("Budgets always have an amount")
+    }
+
+    /// The amount budgeted for the month `date` is in, or None if that's before the budget starts.
+    pub fn amount_for(&self, date: NaiveDate) -> Option<Money> {
+        self.amounts.range(..=month_start(date)).next_back().map(|(_, amount)| *amount)
+    }
+
+    /// Every month of a budget is in the same currency.
+    pub fn get_currency(&self) -> Currency {
+        self.amounts.values().next().expect("Budgets always have an amount").currency()
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Budgets {
+    budgets: Vec<CategoryBudget>,
+}
+
+impl Budgets {
+    pub fn new_empty() -> Budgets {
+        Budgets {budgets: Vec::new()}
+    }
+
+    pub fn get_budgets(&self) -> &Vec<CategoryBudget> {
+        &self.budgets
+    }
+
+    pub fn get_budget(&self, category: &CategoryId) -> Option<&CategoryBudget> {
+        self.budgets.iter().find(|b| b.category == *category)
+    }
+
+    /// Budget `amount` for the category from the month `date` is in onwards, until it's set again for a later month.
+    /// Fails if the amount is negative, or in a different currency from the category's other months.
+    pub(crate) fn set_budget(&mut self, category: &CategoryId, date: NaiveDate, amount: Money) -> Result<(), String> {
+        if amount.is_negative() {
+            return Err("A budget can't be negative".to_string());
+        }
+        match self.budgets.iter_mut().find(|b| b.category == *category) {
+            Some(budget) => {
+                let currency = budget.get_currency();
+                if amount.currency() != currency {
+                    return Err(format!("The budget for '{}' is in {}, not {}", category, currency, amount.currency()));
+                }
+                budget.amounts.insert(month_start(date), amount);
+            },
+            None => self.budgets.push(CategoryBudget {
+                category: category.clone(),
+                amounts: BTreeMap::from([(month_start(date), amount)]),
+                rollover: false,
+            }),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_rollover(&mut self, category: &CategoryId, rollover: bool) -> Result<(), String> {
+        let budget = self.budgets.iter_mut().find(|b| b.category == *category)
+            .ok_or_else(|| format!("Category '{}' doesn't have a budget", category))?;
+        budget.rollover = rollover;
+        Ok(())
+    }
+
+    /// Remove a category's budget, returning whether it had one.
+    pub(crate) fn remove_budget(&mut self, category: &CategoryId) -> bool {
+        let count = self.budgets.len();
+        self.budgets.retain(|b| b.category != *category);
+        self.budgets.len() != count
+    }
+}
+
+/// How a category did against its budget in one month.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BudgetLine {
+    pub category: CategoryId,
+    pub budgeted: Money,
+    // What rolled over from earlier months, which is negative if they were overspent
+    pub carried: Money,
+    // Net spending in the category and its subcategories. Refunds lower it.
+    pub spent: Money,
+}
+
+impl BudgetLine {
+    /// What's left to spend this month, which is negative if the category is overspent.
+    pub fn get_remaining(&self) -> Money {
+        self.budgeted + self.carried - self.spent
+    }
+
+    pub fn is_overspent(&self) -> bool {
+        self.get_remaining().is_negative()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn amounts_by_month() {
+        let groceries = CategoryId::from("groceries".to_string());
+        let mut budgets = Budgets::new_empty();
+        budgets.set_budget(&groceries, date(2024, 2, 15), Money::new(40000, Currency::USD)).unwrap();
+        budgets.set_budget(&groceries, date(2024, 4, 1), Money::new(45000, Currency::USD)).unwrap();
+        assert!(budgets.set_budget(&groceries, date(2024, 5, 1), Money::new(100, Currency::EUR)).is_err());
+        assert!(budgets.set_budget(&groceries, date(2024, 5, 1), Money::new(-100, Currency::USD)).is_err());
+
+        let budget = budgets.get_budget(&groceries).unwrap();
+        assert_eq!(budget.get_start(), date(2024, 2, 1));
+        assert_eq!(budget.amount_for(date(2024, 1, 31)), None);
+        assert_eq!(budget.amount_for(date(2024, 3, 31)), Some(Money::new(40000, Currency::USD)));
+        assert_eq!(budget.amount_for(date(2025, 1, 1)), Some(Money::new(45000, Currency::USD)));
+
+        assert!(budgets.set_rollover(&CategoryId::from("rent".to_string()), true).is_err());
+        assert!(budgets.remove_budget(&groceries));
+        assert!(!budgets.remove_budget(&groceries));
+    }
+}
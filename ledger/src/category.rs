@@ -1,5 +1,10 @@
 use std::{collections::HashMap, fmt::Display};
 
+/// Separates the names of a category and its parents in a path, like `Food:Groceries`.
+pub const PATH_SEPARATOR: char = ':';
+
+/// Identifies a category. Transactions refer to categories by ID, so it never changes, even when the category is
+/// renamed.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, serde::Deserialize, serde::Serialize)]
 pub struct CategoryId(String);
 
@@ -18,18 +23,35 @@ impl Display for CategoryId {
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct TransactionCategory {
     id: CategoryId,
+    // Older files only have the ID, which doubles as the name
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    parent: Option<CategoryId>,
 }
 
 impl TransactionCategory {
-    // TODO allow for a separate name
+    pub fn get_id(&self) -> &CategoryId {
+        &self.id
+    }
+
+    /// The name shown to the user. Unlike the ID, it can have any case, spaces or emoji, and can be changed. It
+    /// doesn't include the names of the parent categories.
     pub fn name(&self) -> &String {
-        &self.id.0
+        self.name.as_ref().unwrap_or(&self.id.0)
+    }
+
+    /// The category this is a subcategory of, if any.
+    pub fn get_parent(&self) -> Option<&CategoryId> {
+        self.parent.as_ref()
     }
 }
 
+/// The categories transactions can be put in. Categories can have subcategories, which can have their own, and so on.
+/// Names only have to be unique among categories with the same parent.
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct TransactionCategories {
-    category_set: HashMap<CategoryId, TransactionCategory>, 
+    category_set: HashMap<CategoryId, TransactionCategory>,
 }
 
 impl TransactionCategories {
@@ -37,17 +59,77 @@ impl TransactionCategories {
         TransactionCategories { category_set: HashMap::new() }
     }
 
-    pub fn create_category(&mut self, id: String) -> Result<(), String> {
-        let id = CategoryId(id);
-        if self.category_set.contains_key(&id) {
-            return Err(format!("Category {} already exists", id.0));
+    /// Create a category, returning its ID. `path` is the category's name, after the names of its parents if it's a
+    /// subcategory, like `Food:Groceries`. Any parents that don't exist yet are created too. The ID is based on the
+    /// path, but stays the same if the category is renamed later.
+    pub fn create_category(&mut self, path: String) -> Result<CategoryId, String> {
+        let names: Vec<&str> = path.split(PATH_SEPARATOR).map(str::trim).collect();
+        for name in &names {
+            check_name_format(name)?;
+        }
+        if let Some(existing) = self.find_by_path(&names) {
+            return Err(format!("Category {} already exists", self.display_name(&existing.id)));
         }
 
-        self.category_set.insert(id.clone(), TransactionCategory {id});
+        let mut parent: Option<CategoryId> = None;
+        for name in names {
+            let existing = self.children(parent.as_ref()).into_iter()
+                .find(|c| c.name().to_lowercase() == name.to_lowercase())
+                .map(|c| c.id.clone());
+            let id = match existing {
+                Some(id) => id,
+                None => self.insert_category(name.to_string(), parent),
+            };
+            parent = Some(id);
+        }
+        Ok(parent.expect("A path has at least one name"))
+    }
 
+    fn insert_category(&mut self, name: String, parent: Option<CategoryId>) -> CategoryId {
+        let slug = name.to_lowercase().split_whitespace().collect::<Vec<_>>().join("-");
+        let base_id = match &parent {
+            Some(parent) => format!("{}{}{}", parent, PATH_SEPARATOR, slug),
+            None => slug,
+        };
+        let id = (1..)
+            .map(|n| if n == 1 { CategoryId(base_id.clone()) } else { CategoryId(format!("{}-{}", base_id, n)) })
+            .find(|id| !self.category_set.contains_key(id))
+            .expect("There's always an unused ID");
+        self.category_set.insert(id.clone(), TransactionCategory {id: id.clone(), name: Some(name), parent});
+        id
+    }
+
+    /// Change the name of a category, keeping its parent. Transactions refer to it by ID, so they keep the category.
+    pub fn rename_category(&mut self, id: &CategoryId, name: String) -> Result<(), String> {
+        let name = name.trim().to_string();
+        let category = self.get_category(id).ok_or_else(|| format!("No category with ID '{}'", id))?;
+        check_name_format(&name)?;
+        let sibling = self.children(category.parent.as_ref()).into_iter()
+            .find(|c| c.id != *id && c.name().to_lowercase() == name.to_lowercase());
+        if let Some(sibling) = sibling {
+            return Err(format!("Category {} already exists", self.display_name(&sibling.id)));
+        }
+        self.category_set.get_mut(id).expect("Checked above").name = Some(name);
         Ok(())
     }
 
+    pub(crate) fn remove_category(&mut self, id: &CategoryId) -> Option<TransactionCategory> {
+        self.category_set.remove(id)
+    }
+
+    /// Make a category top-level.
+    pub(crate) fn clear_parent(&mut self, id: &CategoryId) {
+        if let Some(category) = self.category_set.get_mut(id) {
+            category.parent = None;
+        }
+    }
+
+    // Lets tests put categories in states the other functions won't, like a damaged file might
+    #[cfg(test)]
+    pub(crate) fn set_parent(&mut self, id: &CategoryId, parent: CategoryId) {
+        self.category_set.get_mut(id).expect("Test category exists").parent = Some(parent);
+    }
+
     pub fn categories(&self) -> impl Iterator<Item = &TransactionCategory> {
         self.category_set.values()
     }
@@ -55,4 +137,178 @@ impl TransactionCategories {
     pub fn get_category(&self, id: &CategoryId) -> Option<&TransactionCategory> {
         self.category_set.get(id)
     }
-}
\ No newline at end of file
+
+    /// The subcategories of `parent`, or the top-level categories for `None`, sorted by name.
+    pub fn children(&self, parent: Option<&CategoryId>) -> Vec<&TransactionCategory> {
+        let mut children: Vec<&TransactionCategory> = self.category_set.values()
+            .filter(|c| c.parent.as_ref() == parent)
+            .collect();
+        children.sort_by_key(|c| c.name().to_lowercase());
+        children
+    }
+
+    /// Every category, each followed by its subcategories, paired with how deep it is (0 for top-level ones).
+    pub fn tree(&self) -> Vec<(usize, &TransactionCategory)> {
+        let mut tree = Vec::new();
+        let mut stack: Vec<(usize, &TransactionCategory)> = self.children(None).into_iter().rev().map(|c| (0, c)).collect();
+        while let Some((depth, category)) = stack.pop() {
+            tree.push((depth, category));
+            stack.extend(self.children(Some(&category.id)).into_iter().rev().map(|c| (depth + 1, c)));
+        }
+        tree
+    }
+
+    /// The parent of a category, then its parent, and so on up to a top-level category. If the parents loop back
+    /// around, which only a damaged file can do, this stops before repeating one.
+    pub fn ancestors(&self, id: &CategoryId) -> Vec<&CategoryId> {
+        let mut ancestors = Vec::new();
+        let mut current = self.get_category(id).and_then(|c| c.parent.as_ref());
+        while let Some(parent) = current {
+            if parent == id || ancestors.contains(&parent) {
+                break;
+            }
+            ancestors.push(parent);
+            current = self.get_category(parent).and_then(|c| c.parent.as_ref());
+        }
+        ancestors
+    }
+
+    /// Whether following a category's parents leads back around to the category itself.
+    pub fn is_own_ancestor(&self, id: &CategoryId) -> bool {
+        let last = self.ancestors(id).last().copied().unwrap_or(id);
+        self.get_category(last).and_then(|c| c.parent.as_ref()) == Some(id)
+    }
+
+    /// Look up a category by its path, like `Food:Groceries`, ignoring case. A subcategory can also be found by its
+    /// name alone, as long as no other category has that name.
+    pub fn find_by_name(&self, name: &str) -> Option<&TransactionCategory> {
+        let names: Vec<&str> = name.split(PATH_SEPARATOR).map(str::trim).collect();
+        self.find_by_path(&names).or_else(|| {
+            let name = name.trim().to_lowercase();
+            let mut matches = self.category_set.values().filter(|c| c.name().to_lowercase() == name);
+            match (matches.next(), matches.next()) {
+                (Some(category), None) => Some(category),
+                _ => None,
+            }
+        })
+    }
+
+    fn find_by_path(&self, names: &[&str]) -> Option<&TransactionCategory> {
+        let mut found: Option<&TransactionCategory> = None;
+        for name in names {
+            let name = name.to_lowercase();
+            found = Some(self.children(found.map(|c| &c.id)).into_iter().find(|c| c.name().to_lowercase() == name)?);
+        }
+        found
+    }
+
+    /// The name to show for a category ID: its path, like `Food:Groceries`. Falls back to the ID itself if there's no
+    /// such category.
+    pub fn display_name(&self, id: &CategoryId) -> String {
+        let name = |id: &CategoryId| self.get_category(id).map_or_else(|| id.to_string(), |c| c.name().clone());
+        let mut names: Vec<String> = self.ancestors(id).into_iter().rev().map(name).collect();
+        names.push(name(id));
+        names.join(&PATH_SEPARATOR.to_string())
+    }
+}
+
+// Names can't be blank, have control characters, or have the path separator in them
+fn check_name_format(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Category names can't be blank".to_string());
+    }
+    if name.chars().any(char::is_control) {
+        return Err("Category names can't contain control characters".to_string());
+    }
+    if name.contains(PATH_SEPARATOR) {
+        return Err(format!("Category names can't contain '{}'", PATH_SEPARATOR));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_category() {
+        let mut categories = TransactionCategories::new_empty();
+        let id = categories.create_category(" Eating Out 🍔 ".to_string()).unwrap();
+        assert_eq!(id, CategoryId::from("eating-out-🍔".to_string()));
+        assert_eq!(categories.get_category(&id).unwrap().name(), "Eating Out 🍔");
+        assert_eq!(categories.find_by_name("eating out 🍔").unwrap().get_id(), &id);
+
+        assert!(categories.create_category("EATING OUT 🍔".to_string()).is_err());
+        assert!(categories.create_category("  ".to_string()).is_err());
+        assert!(categories.create_category("Tab\there".to_string()).is_err());
+    }
+
+    #[test]
+    fn rename_category() {
+        let mut categories = TransactionCategories::new_empty();
+        let food = categories.create_category("Food".to_string()).unwrap();
+        let rent = categories.create_category("Rent".to_string()).unwrap();
+        categories.rename_category(&food, "Groceries".to_string()).unwrap();
+        assert_eq!(categories.display_name(&food), "Groceries");
+        assert!(categories.rename_category(&rent, "groceries".to_string()).is_err());
+        assert!(categories.rename_category(&rent, "Bills:Rent".to_string()).is_err());
+        assert!(categories.rename_category(&food, "GROCERIES".to_string()).is_ok());
+
+        // The old name is free again, but its ID is still taken
+        let new_food = categories.create_category("Food".to_string()).unwrap();
+        assert_eq!(new_food, CategoryId::from("food-2".to_string()));
+        assert_eq!(categories.display_name(&food), "GROCERIES");
+    }
+
+    #[test]
+    fn subcategories() {
+        let mut categories = TransactionCategories::new_empty();
+        let groceries = categories.create_category("Food:Groceries".to_string()).unwrap();
+        let food = categories.find_by_name("food").unwrap().get_id().clone();
+        let restaurants = categories.create_category("food : Restaurants".to_string()).unwrap();
+        let other_food = categories.create_category("Food:Other".to_string()).unwrap();
+        let other_bills = categories.create_category("Bills:Other".to_string()).unwrap();
+
+        assert_eq!(categories.get_category(&restaurants).unwrap().get_parent(), Some(&food));
+        assert_eq!(categories.display_name(&groceries), "Food:Groceries");
+        assert_eq!(categories.ancestors(&groceries), vec![&food]);
+        assert!(!categories.is_own_ancestor(&groceries));
+        assert!(categories.create_category("Food:groceries".to_string()).is_err());
+        assert!(categories.create_category("Food:".to_string()).is_err());
+
+        assert_eq!(categories.find_by_name("FOOD:other").unwrap().get_id(), &other_food);
+        assert_eq!(categories.find_by_name("Bills:Other").unwrap().get_id(), &other_bills);
+        assert_eq!(categories.find_by_name("groceries").unwrap().get_id(), &groceries);
+        assert!(categories.find_by_name("other").is_none());
+
+        let tree: Vec<(usize, String)> = categories.tree().into_iter().map(|(d, c)| (d, c.name().clone())).collect();
+        assert_eq!(tree, vec![
+            (0, "Bills".to_string()),
+            (1, "Other".to_string()),
+            (0, "Food".to_string()),
+            (1, "Groceries".to_string()),
+            (1, "Other".to_string()),
+            (1, "Restaurants".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn parent_cycles() {
+        let mut categories = TransactionCategories::new_empty();
+        let groceries = categories.create_category("Food:Groceries".to_string()).unwrap();
+        let food = categories.find_by_name("food").unwrap().get_id().clone();
+        let snacks = categories.create_category("Food:Groceries:Snacks".to_string()).unwrap();
+        categories.set_parent(&food, groceries.clone());
+
+        assert_eq!(categories.ancestors(&groceries), vec![&food]);
+        assert_eq!(categories.ancestors(&snacks), vec![&groceries, &food]);
+        assert_eq!(categories.display_name(&snacks), "Food:Groceries:Snacks");
+        assert!(categories.is_own_ancestor(&food));
+        assert!(categories.is_own_ancestor(&groceries));
+        assert!(!categories.is_own_ancestor(&snacks));
+
+        categories.set_parent(&food, food.clone());
+        assert!(categories.ancestors(&food).is_empty());
+        assert!(categories.is_own_ancestor(&food));
+    }
+}
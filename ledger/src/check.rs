@@ -0,0 +1,63 @@
+//! Consistency checks for references between the parts of a ledger that aren't enforced when it's loaded, like
+//! transactions pointing at categories or payees that no longer exist.
+
+use std::fmt::Display;
+
+use crate::{category::CategoryId, payee::PayeeId, schedule::ScheduleId, transaction::TransactionId};
+
+/// Something in the ledger that refers to something that isn't there, or refers to things in a way it shouldn't.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Problem {
+    /// A transaction, or one of its splits, is in a category that doesn't exist.
+    MissingCategory { transaction: TransactionId, category: CategoryId },
+    /// A category's parent doesn't exist.
+    MissingParent { category: CategoryId, parent: CategoryId },
+    /// Following a category's parents leads back around to it. Only one category in each loop is reported.
+    ParentCycle { category: CategoryId },
+    /// A transaction's payee doesn't exist.
+    MissingPayee { transaction: TransactionId, payee: PayeeId },
+    /// A schedule's frequency can't come due, like the 7th Monday of each month.
+    InvalidSchedule { schedule: ScheduleId, reason: String },
+}
+
+impl Problem {
+    /// What repairing the problem does.
+    pub fn repair_description(&self) -> String {
+        match self {
+            Problem::MissingCategory { transaction, category } => {
+                format!("Moved transaction {} from '{}' to uncategorized", transaction, category)
+            },
+            Problem::MissingParent { category, .. } | Problem::ParentCycle { category } => {
+                format!("Made category '{}' top-level", category)
+            },
+            Problem::MissingPayee { transaction, .. } => {
+                format!("Matched transaction {} to a payee again from its description", transaction)
+            },
+            Problem::InvalidSchedule { schedule, .. } => {
+                format!("Deleted schedule {}, keeping the transactions it posted", schedule)
+            },
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::MissingCategory { transaction, category } => {
+                write!(f, "Transaction {} is in category '{}', which doesn't exist", transaction, category)
+            },
+            Problem::MissingParent { category, parent } => {
+                write!(f, "Category '{}' is under '{}', which doesn't exist", category, parent)
+            },
+            Problem::ParentCycle { category } => {
+                write!(f, "Category '{}' is under itself, through its parents", category)
+            },
+            Problem::MissingPayee { transaction, payee } => {
+                write!(f, "Transaction {} has payee {}, which doesn't exist", transaction, payee)
+            },
+            Problem::InvalidSchedule { schedule, reason } => {
+                write!(f, "Schedule {} can't come due: {}", schedule, reason)
+            },
+        }
+    }
+}
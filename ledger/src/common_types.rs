@@ -1,47 +1,481 @@
-#[derive(PartialEq, PartialOrd, Debug, Clone, Copy)]
+use std::{fmt::Display, str::FromStr};
+
+/// An ISO 4217 currency, identified by its three-letter code.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub struct Currency {
+    code: [u8; 3],
+    // Number of digits after the decimal point in the currency's minor unit, e.g. 2 for USD cents.
+    exponent: u8,
+}
+
+// Every active ISO 4217 currency that has a minor unit, in alphabetical order, with the number of digits in its
+// minor unit.
+static ISO_4217: [(&str, u8); 166] = [
+    ("AED", 2), ("AFN", 2), ("ALL", 2), ("AMD", 2), ("ANG", 2), ("AOA", 2), ("ARS", 2), ("AUD", 2), ("AWG", 2),
+    ("AZN", 2), ("BAM", 2), ("BBD", 2), ("BDT", 2), ("BGN", 2), ("BHD", 3), ("BIF", 0), ("BMD", 2), ("BND", 2),
+    ("BOB", 2), ("BOV", 2), ("BRL", 2), ("BSD", 2), ("BTN", 2), ("BWP", 2), ("BYN", 2), ("BZD", 2), ("CAD", 2),
+    ("CDF", 2), ("CHE", 2), ("CHF", 2), ("CHW", 2), ("CLF", 4), ("CLP", 0), ("CNY", 2), ("COP", 2), ("COU", 2),
+    ("CRC", 2), ("CUP", 2), ("CVE", 2), ("CZK", 2), ("DJF", 0), ("DKK", 2), ("DOP", 2), ("DZD", 2), ("EGP", 2),
+    ("ERN", 2), ("ETB", 2), ("EUR", 2), ("FJD", 2), ("FKP", 2), ("GBP", 2), ("GEL", 2), ("GHS", 2), ("GIP", 2),
+    ("GMD", 2), ("GNF", 0), ("GTQ", 2), ("GYD", 2), ("HKD", 2), ("HNL", 2), ("HTG", 2), ("HUF", 2), ("IDR", 2),
+    ("ILS", 2), ("INR", 2), ("IQD", 3), ("IRR", 2), ("ISK", 0), ("JMD", 2), ("JOD", 3), ("JPY", 0), ("KES", 2),
+    ("KGS", 2), ("KHR", 2), ("KMF", 0), ("KPW", 2), ("KRW", 0), ("KWD", 3), ("KYD", 2), ("KZT", 2), ("LAK", 2),
+    ("LBP", 2), ("LKR", 2), ("LRD", 2), ("LSL", 2), ("LYD", 3), ("MAD", 2), ("MDL", 2), ("MGA", 2), ("MKD", 2),
+    ("MMK", 2), ("MNT", 2), ("MOP", 2), ("MRU", 2), ("MUR", 2), ("MVR", 2), ("MWK", 2), ("MXN", 2), ("MXV", 2),
+    ("MYR", 2), ("MZN", 2), ("NAD", 2), ("NGN", 2), ("NIO", 2), ("NOK", 2), ("NPR", 2), ("NZD", 2), ("OMR", 3),
+    ("PAB", 2), ("PEN", 2), ("PGK", 2), ("PHP", 2), ("PKR", 2), ("PLN", 2), ("PYG", 0), ("QAR", 2), ("RON", 2),
+    ("RSD", 2), ("RUB", 2), ("RWF", 0), ("SAR", 2), ("SBD", 2), ("SCR", 2), ("SDG", 2), ("SEK", 2), ("SGD", 2),
+    ("SHP", 2), ("SLE", 2), ("SOS", 2), ("SRD", 2), ("SSP", 2), ("STN", 2), ("SVC", 2), ("SYP", 2), ("SZL", 2),
+    ("THB", 2), ("TJS", 2), ("TMT", 2), ("TND", 3), ("TOP", 2), ("TRY", 2), ("TTD", 2), ("TWD", 2), ("TZS", 2),
+    ("UAH", 2), ("UGX", 0), ("USD", 2), ("USN", 2), ("UYI", 0), ("UYU", 2), ("UYW", 4), ("UZS", 2), ("VED", 2),
+    ("VES", 2), ("VND", 0), ("VUV", 0), ("WST", 2), ("XAF", 0), ("XCD", 2), ("XCG", 2), ("XOF", 0), ("XPF", 0),
+    ("YER", 2), ("ZAR", 2), ("ZMW", 2), ("ZWG", 2),
+];
+
+static SYMBOLS: [(&str, &str); 5] = [
+    ("USD", "$"), ("EUR", "€"), ("GBP", "£"), ("JPY", "¥"), ("INR", "₹"),
+];
+
+impl Currency {
+    pub const USD: Currency = Currency { code: *b"USD", exponent: 2 };
+    pub const EUR: Currency = Currency { code: *b"EUR", exponent: 2 };
+    pub const GBP: Currency = Currency { code: *b"GBP", exponent: 2 };
+    pub const JPY: Currency = Currency { code: *b"JPY", exponent: 0 };
+
+    /// Look up a currency by its ISO 4217 code. The code is case-insensitive.
+    pub fn from_code(code: &str) -> Result<Currency, MoneyError> {
+        let upper = code.trim().to_ascii_uppercase();
+        let index = ISO_4217.binary_search_by(|(c, _)| (*c).cmp(upper.as_str()))
+            .map_err(|_| MoneyError::InvalidCurrency(code.to_string()))?;
+        let (code, exponent) = ISO_4217[index];
+        let code: [u8; 3] = code.as_bytes().try_into().expect("Currency codes are three letters");
+
+        Ok(Currency { code, exponent })
+    }
+
+    pub fn code(&self) -> &str {
+        // Only ever constructed from ASCII letters
+        std::str::from_utf8(&self.code).expect("Currency code is always ASCII")
+    }
+
+    pub fn exponent(&self) -> u8 {
+        self.exponent
+    }
+
+    /// The conventional symbol for this currency, if there is one we know about.
+    pub fn symbol(&self) -> Option<&'static str> {
+        SYMBOLS.iter().find(|(c, _)| *c == self.code()).map(|(_, s)| *s)
+    }
+
+    /// The currency a symbol stands for. `preferred` wins if it uses that symbol, so e.g. a symbol shared by
+    /// several currencies resolves to the one the caller is working in.
+    pub fn from_symbol(symbol: &str, preferred: Currency) -> Option<Currency> {
+        if preferred.symbol() == Some(symbol) {
+            return Some(preferred);
+        }
+        SYMBOLS.iter()
+            .find(|(_, s)| *s == symbol)
+            .and_then(|(c, _)| Currency::from_code(c).ok())
+    }
+
+    fn minor_units_per_major(&self) -> i64 {
+        10_i64.pow(self.exponent as u32)
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl serde::Serialize for Currency {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer
+    {
+        self.code().serialize(serializer)
+    }
+}
+
+impl<'a> serde::Deserialize<'a> for Currency {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'a>
+    {
+        let code = String::deserialize(deserializer)?;
+        Currency::from_code(&code).map_err(serde::de::Error::custom)
+    }
+}
+
+/// How numbers are written, i.e. which characters separate groups of digits and the decimal part.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum NumberFormat {
+    /// 1,234.56
+    #[default]
+    Standard,
+    /// 1.234,56
+    European,
+}
+
+impl NumberFormat {
+    fn decimal_separator(&self) -> char {
+        match self {
+            NumberFormat::Standard => '.',
+            NumberFormat::European => ',',
+        }
+    }
+
+    fn group_separator(&self) -> char {
+        match self {
+            NumberFormat::Standard => ',',
+            NumberFormat::European => '.',
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum MoneyError {
+    InvalidCurrency(String),
+    CurrencyMismatch(Currency, Currency),
+    InvalidAmount(String),
+    TooManyDecimals(String, Currency),
+    Overflow,
+    DivideByZero,
+    InvalidWeights,
+}
+
+impl Display for MoneyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoneyError::InvalidCurrency(code) => write!(f, "'{}' is not a valid ISO 4217 currency code", code),
+            MoneyError::CurrencyMismatch(a, b) => write!(f, "Cannot combine amounts in {} and {}", a, b),
+            MoneyError::InvalidAmount(s) => write!(f, "'{}' is not a valid amount", s),
+            MoneyError::TooManyDecimals(s, currency) => write!(
+                f,
+                "'{}' has too many decimal places, {} allows at most {}",
+                s, currency, currency.exponent()),
+            MoneyError::Overflow => write!(f, "Amount is too large"),
+            MoneyError::DivideByZero => write!(f, "Cannot divide an amount by zero"),
+            MoneyError::InvalidWeights => write!(f, "Cannot allocate an amount without at least one non-zero weight"),
+        }
+    }
+}
+
+impl std::error::Error for MoneyError { }
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, serde::Deserialize, serde::Serialize)]
 pub struct Money {
-    // For now assume it's USD and just store the number of cents
-    // Effective range is ~ ±$92 trillion
-    cents: i64,
+    // Number of the currency's minor units, e.g. cents for USD or yen for JPY.
+    // Effective range is ~ ±$92 trillion for a 2-digit currency
+    minor_units: i64,
+    currency: Currency,
 }
 
 impl Money {
-    pub fn new(cents: i64) -> Money {
-        Money {cents}
+    pub fn new(minor_units: i64, currency: Currency) -> Money {
+        Money {minor_units, currency}
     }
 
-    /// Create a money object from a float representing number of dollars with cents as the decimal part, e.g. 199.99
+    pub fn zero(currency: Currency) -> Money {
+        Money::new(0, currency)
+    }
+
+    /// Create a money object from a float representing the number of major units with the minor units as the
+    /// decimal part, e.g. 199.99
     /// Using this could potentially result in floating-point errors for large numbers
-    pub fn from_float(val: f64) -> Money {
-        Money {cents: (val * 100.0).round() as i64}
+    pub fn from_float(val: f64, currency: Currency) -> Money {
+        Money {minor_units: (val * currency.minor_units_per_major() as f64).round() as i64, currency}
+    }
+
+    pub fn minor_units(&self) -> i64 {
+        self.minor_units
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Convert to another currency, where `rate` is the number of units of `to` per unit of this amount's
+    /// currency. The result is rounded to the nearest minor unit of `to`.
+    pub fn convert(&self, rate: f64, to: Currency) -> Money {
+        let major_units = self.minor_units as f64 / self.currency.minor_units_per_major() as f64;
+        Money::from_float(major_units * rate, to)
+    }
+
+    /// Parse an amount written as a decimal string, e.g. `-1,234.56`, `(12.00)`, `$12` or `12 USD`. Amounts
+    /// without a currency symbol or code are taken to be in `default_currency`. The number is parsed exactly,
+    /// and is rejected if it has more decimal places than the currency's minor unit.
+    pub fn parse(s: &str, default_currency: Currency, format: NumberFormat) -> Result<Money, MoneyError> {
+        let invalid = || MoneyError::InvalidAmount(s.to_string());
+
+        let mut rest = s.trim();
+        // Accounting style negatives, e.g. (12.00)
+        let mut negative = false;
+        if let Some(inner) = rest.strip_prefix('(').and_then(|r| r.strip_suffix(')')) {
+            negative = true;
+            rest = inner.trim();
+        }
+
+        let mut sign_seen = false;
+        let mut currency = None;
+        loop {
+            if let Some(r) = rest.strip_prefix('-').or_else(|| rest.strip_prefix('+')) {
+                if sign_seen || negative {
+                    return Err(invalid());
+                }
+                negative = rest.starts_with('-');
+                sign_seen = true;
+                rest = r.trim_start();
+            } else if let Some((c, r)) = Self::strip_currency_prefix(rest, default_currency)? {
+                if currency.is_some() {
+                    return Err(invalid());
+                }
+                currency = Some(c);
+                rest = r.trim_start();
+            } else {
+                break;
+            }
+        }
+
+        if let Some((c, r)) = Self::strip_currency_suffix(rest, default_currency)? {
+            if currency.is_some_and(|existing| existing != c) {
+                return Err(MoneyError::CurrencyMismatch(currency.unwrap(), c));
+            }
+            currency = Some(c);
+            rest = r.trim_end();
+        }
+
+        let currency = currency.unwrap_or(default_currency);
+        let magnitude = Self::parse_unsigned_minor_units(rest, currency, format)
+            .map_err(|e| match e {
+                MoneyError::InvalidAmount(_) => invalid(),
+                MoneyError::TooManyDecimals(_, c) => MoneyError::TooManyDecimals(s.to_string(), c),
+                e => e,
+            })?;
+
+        Ok(Money::new(if negative {-magnitude} else {magnitude}, currency))
+    }
+
+    fn strip_currency_prefix(s: &str, default_currency: Currency) -> Result<Option<(Currency, &str)>, MoneyError> {
+        for (_, symbol) in SYMBOLS.iter() {
+            if let Some(rest) = s.strip_prefix(symbol) {
+                return Ok(Currency::from_symbol(symbol, default_currency).map(|c| (c, rest)));
+            }
+        }
+
+        let code_len = s.chars().take_while(|c| c.is_ascii_alphabetic()).count();
+        if code_len == 0 {
+            return Ok(None);
+        }
+        Currency::from_code(&s[..code_len]).map(|c| Some((c, &s[code_len..])))
+    }
+
+    fn strip_currency_suffix(s: &str, default_currency: Currency) -> Result<Option<(Currency, &str)>, MoneyError> {
+        for (_, symbol) in SYMBOLS.iter() {
+            if let Some(rest) = s.strip_suffix(symbol) {
+                return Ok(Currency::from_symbol(symbol, default_currency).map(|c| (c, rest)));
+            }
+        }
+
+        let code_len = s.chars().rev().take_while(|c| c.is_ascii_alphabetic()).count();
+        if code_len == 0 {
+            return Ok(None);
+        }
+        Currency::from_code(&s[s.len() - code_len..]).map(|c| Some((c, &s[..s.len() - code_len])))
+    }
+
+    /// Parse a plain number like `1,234.56` into a count of the currency's minor units.
+    fn parse_unsigned_minor_units(s: &str, currency: Currency, format: NumberFormat) -> Result<i64, MoneyError> {
+        let invalid = || MoneyError::InvalidAmount(s.to_string());
+
+        let (whole, fraction) = match s.split_once(format.decimal_separator()) {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (s, ""),
+        };
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(invalid());
+        }
+
+        // Group separators are optional, but if they're used they have to be in the right places.
+        let groups: Vec<&str> = whole.split(format.group_separator()).collect();
+        let groups_valid = groups.len() == 1
+            || (!groups[0].is_empty() && groups[0].len() <= 3 && groups[1..].iter().all(|g| g.len() == 3));
+        let digits: String = groups.concat();
+        if !groups_valid
+            || !digits.chars().all(|c| c.is_ascii_digit())
+            || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        if fraction.len() > currency.exponent() as usize {
+            return Err(MoneyError::TooManyDecimals(s.to_string(), currency));
+        }
+
+        let whole: i64 = if digits.is_empty() {0} else {digits.parse().map_err(|_| MoneyError::Overflow)?};
+        let padded_fraction = format!("{:0<width$}", fraction, width = currency.exponent() as usize);
+        let fraction: i64 = if padded_fraction.is_empty() {0} else {padded_fraction.parse().map_err(|_| invalid())?};
+
+        whole.checked_mul(currency.minor_units_per_major())
+            .and_then(|w| w.checked_add(fraction))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    /// Sum up amounts that are all expected to be in `currency`. Unlike adding with `+`, a mismatched currency
+    /// or overflow is reported as an error rather than a panic.
+    pub fn total<'a, I>(currency: Currency, amounts: I) -> Result<Money, MoneyError>
+    where
+        I: IntoIterator<Item = &'a Money>
+    {
+        amounts.into_iter().try_fold(Money::zero(currency), |sum, m| sum.checked_add(*m))
+    }
+
+    pub fn checked_add(self, rhs: Money) -> Result<Money, MoneyError> {
+        self.check_currency(rhs)?;
+        self.minor_units.checked_add(rhs.minor_units)
+            .map(|units| Money::new(units, self.currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    pub fn checked_sub(self, rhs: Money) -> Result<Money, MoneyError> {
+        self.check_currency(rhs)?;
+        self.minor_units.checked_sub(rhs.minor_units)
+            .map(|units| Money::new(units, self.currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    pub fn checked_neg(self) -> Result<Money, MoneyError> {
+        self.minor_units.checked_neg()
+            .map(|units| Money::new(units, self.currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    /// Panics for the most negative amount, whose opposite doesn't fit, the same way negating it does.
+    pub fn abs(self) -> Money {
+        if self.is_negative() {-self} else {self}
+    }
+
+    pub fn checked_abs(self) -> Result<Money, MoneyError> {
+        if self.is_negative() {self.checked_neg()} else {Ok(self)}
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.minor_units == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.minor_units < 0
+    }
+
+    /// Multiply by `numerator / denominator`, rounding half away from zero to the nearest minor unit. Useful
+    /// for things like percentages, e.g. `mul_ratio(15, 100)` for a 15% tip.
+    pub fn mul_ratio(self, numerator: i64, denominator: i64) -> Result<Money, MoneyError> {
+        if denominator == 0 {
+            return Err(MoneyError::DivideByZero);
+        }
+
+        let product = self.minor_units as i128 * numerator as i128;
+        let denominator = denominator as i128;
+        let quotient = product / denominator;
+        let remainder = product % denominator;
+        // Round half away from zero
+        let rounded = if remainder.abs() * 2 >= denominator.abs() {
+            quotient + if (product < 0) == (denominator < 0) {1} else {-1}
+        } else {
+            quotient
+        };
+
+        i64::try_from(rounded)
+            .map(|units| Money::new(units, self.currency))
+            .map_err(|_| MoneyError::Overflow)
+    }
+
+    /// Split the amount into parts proportional to `weights`. The parts always add up to exactly the original
+    /// amount: minor units that can't be divided evenly go to the parts that were rounded down the most, with
+    /// ties going to the earlier parts.
+    pub fn allocate(self, weights: &[u32]) -> Result<Vec<Money>, MoneyError> {
+        let total_weight: u64 = weights.iter().map(|w| *w as u64).sum();
+        if total_weight == 0 {
+            return Err(MoneyError::InvalidWeights);
+        }
+
+        let amount = self.minor_units as i128;
+        let total_weight = total_weight as i128;
+        let mut parts: Vec<i128> = Vec::with_capacity(weights.len());
+        let mut remainders: Vec<(i128, usize)> = Vec::with_capacity(weights.len());
+        for (index, weight) in weights.iter().enumerate() {
+            let scaled = amount * *weight as i128;
+            parts.push(scaled / total_weight);
+            remainders.push(((scaled % total_weight).abs(), index));
+        }
+
+        // Truncation always rounds towards zero, so the leftover has the same sign as the amount and is
+        // smaller than the number of parts.
+        let leftover = amount - parts.iter().sum::<i128>();
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for (_, index) in remainders.iter().take(leftover.unsigned_abs() as usize) {
+            parts[*index] += leftover.signum();
+        }
+
+        // Every part is no bigger in magnitude than the original amount, so these conversions can't fail.
+        Ok(parts.into_iter().map(|p| Money::new(p as i64, self.currency)).collect())
+    }
+
+    fn check_currency(&self, other: Money) -> Result<(), MoneyError> {
+        if self.currency != other.currency {
+            return Err(MoneyError::CurrencyMismatch(self.currency, other.currency));
+        }
+        Ok(())
+    }
+}
+
+/// Amounts are ordered by currency first, then by value. This gives a total order that sorts like amounts
+/// together, but comparing amounts in different currencies doesn't say anything about what they're worth.
+impl Ord for Money {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.currency.cmp(&other.currency).then(self.minor_units.cmp(&other.minor_units))
+    }
+}
+
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 impl std::fmt::Display for Money {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}${:.2}", 
-            if self.cents < 0 {"-"} else {""},
-            (self.cents as f64).abs() / 100.0)
+        let sign = if self.minor_units < 0 {"-"} else {""};
+        let per_major = self.currency.minor_units_per_major().unsigned_abs();
+        let abs = self.minor_units.unsigned_abs();
+        let number = match self.currency.exponent() {
+            0 => format!("{}", abs),
+            e => format!("{}.{:0width$}", abs / per_major, abs % per_major, width = e as usize),
+        };
+
+        match self.currency.symbol() {
+            Some(symbol) => write!(f, "{}{}{}", sign, symbol, number),
+            None => write!(f, "{}{} {}", sign, number, self.currency),
+        }
     }
 }
 
-impl<'a> std::iter::Sum<&'a Money> for Money {
-    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
-        let mut sum = Money::from_float(0.0);
-        for m in iter {
-            sum += *m;
-        }
-        sum
+impl FromStr for Money {
+    type Err = MoneyError;
+
+    /// Parses with the standard number format, treating amounts that don't name a currency as USD.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Money::parse(s, Currency::USD, NumberFormat::Standard)
     }
 }
 
+// The operators panic on mismatched currencies or overflow, in release builds too. Use the checked_* methods
+// when either is a possibility.
+
 impl std::ops::Add for Money {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Money::new(self.cents + rhs.cents)
+        self.checked_add(rhs).unwrap_or_else(|e| panic!("Failed to add {} to {}: {}", rhs, self, e))
     }
 }
 
@@ -51,21 +485,25 @@ impl std::ops::AddAssign for Money {
     }
 }
 
-impl serde::Serialize for Money {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer 
-    {
-        self.cents.serialize(serializer)    
+impl std::ops::Sub for Money {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs).unwrap_or_else(|e| panic!("Failed to subtract {} from {}: {}", rhs, self, e))
     }
 }
 
-impl<'a> serde::Deserialize<'a> for Money {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'a> 
-    {
-            Ok(Money{cents: i64::deserialize(deserializer)?}) 
+impl std::ops::SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl std::ops::Neg for Money {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self.checked_neg().unwrap_or_else(|e| panic!("Failed to negate {}: {}", self, e))
     }
 }
 
@@ -75,76 +513,344 @@ mod tests {
 
     #[test]
     fn new() {
-        assert_eq!(Money::new(100), Money{cents: 100});
+        assert_eq!(Money::new(100, Currency::USD), Money{minor_units: 100, currency: Currency::USD});
     }
 
     #[test]
     fn from_float() {
-        assert_eq!(Money::from_float(123.45), Money{cents: 12345});
+        assert_eq!(Money::from_float(123.45, Currency::USD), Money{minor_units: 12345, currency: Currency::USD});
     }
 
     #[test]
     fn from_float_negative() {
-        assert_eq!(Money::from_float(-123.45), Money{cents: -12345});
+        assert_eq!(Money::from_float(-123.45, Currency::USD), Money{minor_units: -12345, currency: Currency::USD});
     }
 
     #[test]
     fn from_float_zero() {
-        assert_eq!(Money::from_float(0.0), Money{cents: 0});
+        assert_eq!(Money::from_float(0.0, Currency::USD), Money::zero(Currency::USD));
     }
 
     #[test]
     fn from_float_fractional_cents() {
         // round up
-        assert_eq!(Money::from_float(666.666), Money{cents: 66667});
+        assert_eq!(Money::from_float(666.666, Currency::USD), Money::new(66667, Currency::USD));
         // round down
-        assert_eq!(Money::from_float(11.111), Money{cents: 1111});
+        assert_eq!(Money::from_float(11.111, Currency::USD), Money::new(1111, Currency::USD));
+    }
+
+    #[test]
+    fn from_float_uses_currency_exponent() {
+        assert_eq!(Money::from_float(123.0, Currency::JPY), Money::new(123, Currency::JPY));
+        let kwd = Currency::from_code("KWD").unwrap();
+        assert_eq!(Money::from_float(1.234, kwd), Money::new(1234, kwd));
+    }
+
+    #[test]
+    fn currency_from_code() {
+        assert_eq!(Currency::from_code("usd"), Ok(Currency::USD));
+        assert_eq!(Currency::from_code("JPY").unwrap().exponent(), 0);
+        assert_eq!(Currency::from_code("KWD").unwrap().exponent(), 3);
+        assert_eq!(Currency::from_code("CHF").unwrap().exponent(), 2);
+    }
+
+    #[test]
+    fn currency_from_invalid_code() {
+        assert!(Currency::from_code("US").is_err());
+        assert!(Currency::from_code("USDX").is_err());
+        assert!(Currency::from_code("U$D").is_err());
+        assert!(Currency::from_code("").is_err());
+        assert!(Currency::from_code("XYZ").is_err());
+        assert!(Currency::from_code("ABC").is_err());
     }
 
     #[test]
     fn fmt_basic() {
-        assert_eq!(format!("{}", Money::from_float(1.23)), "$1.23")
+        assert_eq!(format!("{}", Money::from_float(1.23, Currency::USD)), "$1.23")
     }
 
     #[test]
     fn fmt_whole_dollar_amount() {
-        assert_eq!(format!("{}", Money::from_float(10.0)), "$10.00")
+        assert_eq!(format!("{}", Money::from_float(10.0, Currency::USD)), "$10.00")
     }
 
     #[test]
     fn fmt_less_than_1() {
-        assert_eq!(format!("{}", Money::from_float(0.01)), "$0.01")
+        assert_eq!(format!("{}", Money::from_float(0.01, Currency::USD)), "$0.01")
     }
 
     #[test]
     fn fmt_negative() {
-        assert_eq!(format!("{}", Money::from_float(-1.23)), "-$1.23")
+        assert_eq!(format!("{}", Money::from_float(-1.23, Currency::USD)), "-$1.23")
     }
 
     #[test]
     fn fmt_zero() {
-        assert_eq!(format!("{}", Money::from_float(0.0)), "$0.00")
+        assert_eq!(format!("{}", Money::from_float(0.0, Currency::USD)), "$0.00")
+    }
+
+    #[test]
+    fn fmt_other_currencies() {
+        assert_eq!(format!("{}", Money::new(1050, Currency::EUR)), "€10.50");
+        assert_eq!(format!("{}", Money::new(-99, Currency::GBP)), "-£0.99");
+        assert_eq!(format!("{}", Money::new(1500, Currency::JPY)), "¥1500");
+        assert_eq!(format!("{}", Money::new(1005, Currency::from_code("KWD").unwrap())), "1.005 KWD");
+        assert_eq!(format!("{}", Money::new(-1005, Currency::from_code("CHF").unwrap())), "-10.05 CHF");
+    }
+
+    fn parse_usd(s: &str) -> Result<Money, MoneyError> {
+        Money::parse(s, Currency::USD, NumberFormat::Standard)
+    }
+
+    #[test]
+    fn parse_basic() {
+        assert_eq!(parse_usd("12.34"), Ok(Money::new(1234, Currency::USD)));
+        assert_eq!(parse_usd("12"), Ok(Money::new(1200, Currency::USD)));
+        assert_eq!(parse_usd("12.5"), Ok(Money::new(1250, Currency::USD)));
+        assert_eq!(parse_usd(".05"), Ok(Money::new(5, Currency::USD)));
+        assert_eq!(parse_usd("  0 "), Ok(Money::zero(Currency::USD)));
+    }
+
+    #[test]
+    fn parse_is_exact() {
+        // 0.29 * 100 is 28.999999999999996 as a float
+        assert_eq!(parse_usd("0.29"), Ok(Money::new(29, Currency::USD)));
+        assert_eq!(parse_usd("92233720368547758.07"), Ok(Money::new(i64::MAX, Currency::USD)));
+        assert_eq!(parse_usd("92233720368547758.08"), Err(MoneyError::Overflow));
+    }
+
+    #[test]
+    fn parse_negative() {
+        assert_eq!(parse_usd("-1,234.56"), Ok(Money::new(-123456, Currency::USD)));
+        assert_eq!(parse_usd("(12.00)"), Ok(Money::new(-1200, Currency::USD)));
+        assert_eq!(parse_usd("-$12"), Ok(Money::new(-1200, Currency::USD)));
+        assert_eq!(parse_usd("$-12"), Ok(Money::new(-1200, Currency::USD)));
+        assert_eq!(parse_usd("($12.00)"), Ok(Money::new(-1200, Currency::USD)));
+        assert!(parse_usd("(-12.00)").is_err());
+        assert!(parse_usd("--12").is_err());
+    }
+
+    #[test]
+    fn parse_with_currency() {
+        assert_eq!(parse_usd("$12"), Ok(Money::new(1200, Currency::USD)));
+        assert_eq!(parse_usd("12 USD"), Ok(Money::new(1200, Currency::USD)));
+        assert_eq!(parse_usd("EUR 12.50"), Ok(Money::new(1250, Currency::EUR)));
+        assert_eq!(parse_usd("12.50€"), Ok(Money::new(1250, Currency::EUR)));
+        assert_eq!(parse_usd("¥1500"), Ok(Money::new(1500, Currency::JPY)));
+        assert_eq!(parse_usd("1500 jpy"), Ok(Money::new(1500, Currency::JPY)));
+        assert_eq!(parse_usd("$12 EUR"), Err(MoneyError::CurrencyMismatch(Currency::USD, Currency::EUR)));
+        assert!(parse_usd("12 DOLLARS").is_err());
+        assert!(parse_usd("12 XYZ").is_err());
+    }
+
+    #[test]
+    fn parse_european() {
+        let parse = |s| Money::parse(s, Currency::EUR, NumberFormat::European);
+        assert_eq!(parse("1.234,56"), Ok(Money::new(123456, Currency::EUR)));
+        assert_eq!(parse("-12,5 €"), Ok(Money::new(-1250, Currency::EUR)));
+        assert_eq!(parse("12"), Ok(Money::new(1200, Currency::EUR)));
+        assert!(parse("1,234.56").is_err());
+    }
+
+    #[test]
+    fn parse_too_many_decimals() {
+        assert_eq!(parse_usd("1.234"), Err(MoneyError::TooManyDecimals("1.234".to_string(), Currency::USD)));
+        assert!(matches!(parse_usd("¥12.5"), Err(MoneyError::TooManyDecimals(_, Currency::JPY))));
+        let kwd = Currency::from_code("KWD").unwrap();
+        assert_eq!(Money::parse("1.234", kwd, NumberFormat::Standard), Ok(Money::new(1234, kwd)));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for s in ["", "abc", "12.34.56", "1,23.45", ",123", "12-", "1 2", "$", "."] {
+            assert!(parse_usd(s).is_err(), "'{}' should not parse", s);
+        }
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("1,000".parse::<Money>(), Ok(Money::new(100000, Currency::USD)));
     }
 
     #[test]
     fn add() {
-        assert_eq!(Money::from_float(10.00) + Money::from_float(0.50), Money::from_float(10.50))
+        assert_eq!(
+            Money::from_float(10.00, Currency::USD) + Money::from_float(0.50, Currency::USD),
+            Money::from_float(10.50, Currency::USD))
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_mismatched_currencies() {
+        let _ = Money::new(100, Currency::USD) + Money::new(100, Currency::EUR);
     }
 
     #[test]
     fn add_assign() {
-        let mut m = Money::from_float(10.00);
-        m += Money::from_float(0.50);
-        assert_eq!(m, Money::from_float(10.50))
+        let mut m = Money::from_float(10.00, Currency::USD);
+        m += Money::from_float(0.50, Currency::USD);
+        assert_eq!(m, Money::from_float(10.50, Currency::USD))
+    }
+
+    #[test]
+    fn convert() {
+        assert_eq!(Money::new(1000, Currency::USD).convert(0.9, Currency::EUR), Money::new(900, Currency::EUR));
+        assert_eq!(Money::new(1000, Currency::USD).convert(150.0, Currency::JPY), Money::new(1500, Currency::JPY));
+        assert_eq!(Money::new(1500, Currency::JPY).convert(1.0 / 150.0, Currency::USD), Money::new(1000, Currency::USD));
     }
 
     #[test]
-    fn sum() {
+    #[should_panic]
+    fn add_overflow() {
+        let _ = Money::new(i64::MAX, Currency::USD) + Money::new(1, Currency::USD);
+    }
+
+    #[test]
+    fn checked_add() {
+        let usd = |units| Money::new(units, Currency::USD);
+        assert_eq!(usd(100).checked_add(usd(-30)), Ok(usd(70)));
+        assert_eq!(usd(i64::MAX).checked_add(usd(1)), Err(MoneyError::Overflow));
+        assert_eq!(
+            usd(1).checked_add(Money::new(1, Currency::EUR)),
+            Err(MoneyError::CurrencyMismatch(Currency::USD, Currency::EUR)));
+    }
+
+    #[test]
+    fn checked_sub() {
+        let usd = |units| Money::new(units, Currency::USD);
+        assert_eq!(usd(100).checked_sub(usd(130)), Ok(usd(-30)));
+        assert_eq!(usd(i64::MIN).checked_sub(usd(1)), Err(MoneyError::Overflow));
+        assert!(usd(1).checked_sub(Money::new(1, Currency::EUR)).is_err());
+    }
+
+    #[test]
+    fn sub_and_neg() {
+        let mut m = Money::new(1000, Currency::USD) - Money::new(250, Currency::USD);
+        assert_eq!(m, Money::new(750, Currency::USD));
+        m -= Money::new(1000, Currency::USD);
+        assert_eq!(-m, Money::new(250, Currency::USD));
+        assert_eq!(Money::new(i64::MIN, Currency::USD).checked_neg(), Err(MoneyError::Overflow));
+    }
+
+    #[test]
+    fn abs() {
+        assert_eq!(Money::new(-5, Currency::USD).abs(), Money::new(5, Currency::USD));
+        assert_eq!(Money::new(5, Currency::USD).abs(), Money::new(5, Currency::USD));
+    }
+
+    #[test]
+    #[should_panic]
+    fn abs_overflow() {
+        let _ = Money::new(i64::MIN, Currency::USD).abs();
+    }
+
+    #[test]
+    fn checked_abs() {
+        assert_eq!(Money::new(-5, Currency::USD).checked_abs(), Ok(Money::new(5, Currency::USD)));
+        assert_eq!(Money::new(5, Currency::USD).checked_abs(), Ok(Money::new(5, Currency::USD)));
+        assert_eq!(Money::new(i64::MIN, Currency::USD).checked_abs(), Err(MoneyError::Overflow));
+    }
+
+    #[test]
+    fn ordering() {
+        let mut amounts = vec![
+            Money::new(300, Currency::USD),
+            Money::new(-100, Currency::USD),
+            Money::new(5, Currency::EUR),
+            Money::new(0, Currency::USD)];
+        amounts.sort();
+        assert_eq!(amounts, vec![
+            Money::new(5, Currency::EUR),
+            Money::new(-100, Currency::USD),
+            Money::new(0, Currency::USD),
+            Money::new(300, Currency::USD)]);
+        assert!(Money::new(1, Currency::USD) > Money::new(-1, Currency::USD));
+    }
+
+    #[test]
+    fn mul_ratio() {
+        let usd = |units| Money::new(units, Currency::USD);
+        assert_eq!(usd(1000).mul_ratio(15, 100), Ok(usd(150)));
+        assert_eq!(usd(1001).mul_ratio(1, 2), Ok(usd(501)));
+        assert_eq!(usd(-1001).mul_ratio(1, 2), Ok(usd(-501)));
+        assert_eq!(usd(1001).mul_ratio(-1, 2), Ok(usd(-501)));
+        assert_eq!(usd(100).mul_ratio(1, 3), Ok(usd(33)));
+        assert_eq!(usd(100).mul_ratio(1, 0), Err(MoneyError::DivideByZero));
+        assert_eq!(usd(i64::MAX).mul_ratio(2, 1), Err(MoneyError::Overflow));
+    }
+
+    #[test]
+    fn allocate_even() {
+        let parts = Money::new(100, Currency::USD).allocate(&[1, 1, 1]).unwrap();
+        assert_eq!(parts, vec![
+            Money::new(34, Currency::USD),
+            Money::new(33, Currency::USD),
+            Money::new(33, Currency::USD)]);
+    }
+
+    #[test]
+    fn allocate_weighted() {
+        let parts = Money::new(1000, Currency::USD).allocate(&[70, 20, 10]).unwrap();
+        assert_eq!(parts, vec![
+            Money::new(700, Currency::USD),
+            Money::new(200, Currency::USD),
+            Money::new(100, Currency::USD)]);
+
+        // 5 * 3/7 = 2.14, 5 * 4/7 = 2.86, so the leftover unit goes to the second part
+        let parts = Money::new(5, Currency::USD).allocate(&[3, 4]).unwrap();
+        assert_eq!(parts, vec![Money::new(2, Currency::USD), Money::new(3, Currency::USD)]);
+    }
+
+    #[test]
+    fn allocate_negative_and_zero_weights() {
+        let parts = Money::new(-100, Currency::USD).allocate(&[1, 0, 2]).unwrap();
+        assert_eq!(parts, vec![
+            Money::new(-33, Currency::USD),
+            Money::new(0, Currency::USD),
+            Money::new(-67, Currency::USD)]);
+    }
+
+    #[test]
+    fn allocate_always_adds_up() {
+        for units in [0, 1, 7, 99, 100, 12345, -12345, i64::MAX, i64::MIN] {
+            let amount = Money::new(units, Currency::USD);
+            let parts = amount.allocate(&[3, 1, 4, 1, 5, 9]).unwrap();
+            let sum: i128 = parts.iter().map(|p| p.minor_units() as i128).sum();
+            assert_eq!(sum, units as i128);
+        }
+    }
+
+    #[test]
+    fn allocate_invalid_weights() {
+        assert_eq!(Money::new(100, Currency::USD).allocate(&[]), Err(MoneyError::InvalidWeights));
+        assert_eq!(Money::new(100, Currency::USD).allocate(&[0, 0]), Err(MoneyError::InvalidWeights));
+    }
+
+    #[test]
+    fn total() {
         let a = vec![
-            Money::from_float(1.00),
-            Money::from_float(0.50),
-            Money::from_float(100.00)];
-        let s: Money = a.iter().sum();
-        assert_eq!(s, Money::from_float(101.50))
+            Money::from_float(1.00, Currency::USD),
+            Money::from_float(0.50, Currency::USD),
+            Money::from_float(100.00, Currency::USD)];
+        assert_eq!(Money::total(Currency::USD, &a), Ok(Money::from_float(101.50, Currency::USD)))
+    }
+
+    #[test]
+    fn total_empty() {
+        assert_eq!(Money::total(Currency::EUR, &[]), Ok(Money::zero(Currency::EUR)))
+    }
+
+    #[test]
+    fn total_overflow() {
+        let a = vec![Money::new(i64::MAX, Currency::USD), Money::new(1, Currency::USD)];
+        assert_eq!(Money::total(Currency::USD, &a), Err(MoneyError::Overflow))
+    }
+
+    #[test]
+    fn total_mismatched_currencies() {
+        let a = vec![Money::new(100, Currency::USD), Money::new(100, Currency::EUR)];
+        assert_eq!(
+            Money::total(Currency::USD, &a),
+            Err(MoneyError::CurrencyMismatch(Currency::USD, Currency::EUR)))
     }
 }
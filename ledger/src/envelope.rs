@@ -0,0 +1,207 @@
+//! Envelope (zero-based) budgeting. Each category is an envelope. Money that comes in uncategorized is ready to
+//! assign, and the goal is to assign all of it to envelopes. Spending in a category comes out of its envelope, and
+//! whatever's left in an envelope stays there from month to month.
+
+use chrono::NaiveDate;
+
+use crate::{budget::month_start, category::CategoryId, common_types::{Currency, Money}};
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+struct Assignment {
+    category: CategoryId,
+    // The start of the month
+    month: NaiveDate,
+    amount: Money,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct Envelopes {
+    enabled: bool,
+    currency: Currency,
+    // The net amount assigned to each envelope in each month. Moving money between envelopes lowers one and raises
+    // the other.
+    assignments: Vec<Assignment>,
+}
+
+impl Default for Envelopes {
+    fn default() -> Self {
+        Envelopes::new_empty()
+    }
+}
+
+impl Envelopes {
+    pub fn new_empty() -> Envelopes {
+        Envelopes {enabled: false, currency: Currency::USD, assignments: Vec::new()}
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Turn envelope budgeting on in `currency`, or off if it's None. Assignments are kept while it's off. Fails if
+    /// there are assignments in another currency.
+    pub(crate) fn set_mode(&mut self, currency: Option<Currency>) -> Result<(), String> {
+        match currency {
+            Some(currency) => {
+                if currency != self.currency && !self.assignments.is_empty() {
+                    return Err(format!("Money has already been assigned in {}", self.currency));
+                }
+                self.enabled = true;
+                self.currency = currency;
+            },
+            None => self.enabled = false,
+        }
+        Ok(())
+    }
+
+    /// What was assigned to an envelope in the month `date` is in.
+    pub fn get_assigned(&self, category: &CategoryId, date: NaiveDate) -> Money {
+        let month = month_start(date);
+        self.total(self.assignments.iter().filter(|a| a.category == *category && a.month == month))
+    }
+
+    /// What was assigned to an envelope, or to every envelope if `category` is None, in every month up to and
+    /// including the one `date` is in.
+    pub fn get_assigned_through(&self, category: Option<&CategoryId>, date: NaiveDate) -> Money {
+        let month = month_start(date);
+        self.total(self.assignments.iter().filter(|a| category.is_none_or(|c| a.category == *c) && a.month <= month))
+    }
+
+    fn total<'a>(&self, assignments: impl Iterator<Item = &'a Assignment>) -> Money {
+        assignments.fold(Money::zero(self.currency), |total, a| total + a.amount)
+    }
+
+    /// Add `amount` to what's assigned to an envelope in the month `date` is in. A negative amount takes money out.
+    pub(crate) fn assign(&mut self, category: &CategoryId, date: NaiveDate, amount: Money) -> Result<(), String> {
+        self.check_amount(amount)?;
+        self.add(category, month_start(date), amount)
+    }
+
+    /// Take `amount` out of one envelope and add it to another in the month `date` is in. Both sides are checked
+    /// before either changes, so if one would overflow, neither envelope is touched.
+    pub(crate) fn move_between(&mut self, from: &CategoryId, to: &CategoryId, date: NaiveDate, amount: Money) -> Result<(), String> {
+        self.check_amount(amount)?;
+        let month = month_start(date);
+        let taken = amount.checked_neg().map_err(|e| e.to_string())?;
+        self.get_assigned(from, month).checked_add(taken).map_err(|e| e.to_string())?;
+        self.get_assigned(to, month).checked_add(amount).map_err(|e| e.to_string())?;
+        self.add(from, month, taken)?;
+        self.add(to, month, amount)
+    }
+
+    fn check_amount(&self, amount: Money) -> Result<(), String> {
+        if !self.enabled {
+            return Err("Envelope budgeting isn't turned on".to_string());
+        }
+        if amount.currency() != self.currency {
+            return Err(format!("Envelopes are in {}, not {}", self.currency, amount.currency()));
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, category: &CategoryId, month: NaiveDate, amount: Money) -> Result<(), String> {
+        match self.assignments.iter_mut().find(|a| a.category == *category && a.month == month) {
+            Some(assignment) => assignment.amount = assignment.amount.checked_add(amount).map_err(|e| e.to_string())?,
+            None => self.assignments.push(Assignment {category: category.clone(), month, amount}),
+        }
+        Ok(())
+    }
+
+    /// Move what's assigned to one envelope to another, or back to ready to assign if `to` is None.
+    pub(crate) fn replace_category(&mut self, from: &CategoryId, to: Option<&CategoryId>) {
+        let (moved, kept): (Vec<Assignment>, Vec<Assignment>) = self.assignments.drain(..).partition(|a| a.category == *from);
+        self.assignments = kept;
+        if let Some(to) = to {
+            for assignment in moved {
+                // Adding amounts in the same currency that were both assigned already can't overflow in practice
+                let _ = self.add(to, assignment.month, assignment.amount);
+            }
+        }
+    }
+}
+
+/// An envelope's state in one month.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnvelopeLine {
+    pub category: CategoryId,
+    // Assigned in this month
+    pub assigned: Money,
+    // Net amount of this month's transactions in the category, which is negative when more was spent than came back
+    pub activity: Money,
+    // Everything assigned up to the end of this month, plus the net amount of every transaction in the category
+    // until then
+    pub available: Money,
+}
+
+/// The envelopes for one month.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnvelopeReport {
+    pub month: NaiveDate,
+    // Money that's come in uncategorized up to the end of the month, plus the accounts' opening balances, less
+    // everything assigned until then. Zero-based budgeting aims to keep this at zero.
+    pub ready_to_assign: Money,
+    pub envelopes: Vec<EnvelopeLine>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn assignments() {
+        let food = CategoryId::from("food".to_string());
+        let rent = CategoryId::from("rent".to_string());
+        let usd = |amount| Money::new(amount, Currency::USD);
+        let mut envelopes = Envelopes::new_empty();
+        assert!(envelopes.assign(&food, date(2024, 3, 1), usd(100)).is_err());
+
+        envelopes.set_mode(Some(Currency::USD)).unwrap();
+        envelopes.assign(&food, date(2024, 2, 10), usd(30000)).unwrap();
+        envelopes.assign(&food, date(2024, 3, 1), usd(40000)).unwrap();
+        envelopes.assign(&food, date(2024, 3, 31), usd(-5000)).unwrap();
+        envelopes.assign(&rent, date(2024, 4, 1), usd(100000)).unwrap();
+        assert!(envelopes.assign(&food, date(2024, 3, 1), Money::new(100, Currency::EUR)).is_err());
+
+        assert_eq!(envelopes.get_assigned(&food, date(2024, 3, 15)), usd(35000));
+        assert_eq!(envelopes.get_assigned_through(Some(&food), date(2024, 3, 15)), usd(65000));
+        assert_eq!(envelopes.get_assigned_through(None, date(2024, 4, 1)), usd(165000));
+
+        envelopes.replace_category(&rent, Some(&food));
+        assert_eq!(envelopes.get_assigned(&food, date(2024, 4, 1)), usd(100000));
+        assert_eq!(envelopes.get_assigned(&rent, date(2024, 4, 1)), usd(0));
+        envelopes.replace_category(&food, None);
+        assert_eq!(envelopes.get_assigned_through(None, date(2024, 4, 1)), usd(0));
+
+        assert!(envelopes.set_mode(Some(Currency::EUR)).is_ok());
+        envelopes.set_mode(None).unwrap();
+        assert!(!envelopes.is_enabled());
+    }
+
+    #[test]
+    fn failed_move_changes_nothing() {
+        let food = CategoryId::from("food".to_string());
+        let rent = CategoryId::from("rent".to_string());
+        let usd = |amount| Money::new(amount, Currency::USD);
+        let mut envelopes = Envelopes::new_empty();
+        envelopes.set_mode(Some(Currency::USD)).unwrap();
+        envelopes.assign(&food, date(2024, 3, 1), usd(100)).unwrap();
+        envelopes.assign(&rent, date(2024, 3, 1), usd(i64::MAX)).unwrap();
+
+        // Taking $1 out of food works, but adding it to rent would overflow
+        assert!(envelopes.move_between(&food, &rent, date(2024, 3, 1), usd(100)).is_err());
+        assert_eq!(envelopes.get_assigned(&food, date(2024, 3, 1)), usd(100));
+        assert_eq!(envelopes.get_assigned(&rent, date(2024, 3, 1)), usd(i64::MAX));
+
+        envelopes.move_between(&rent, &food, date(2024, 3, 1), usd(100)).unwrap();
+        assert_eq!(envelopes.get_assigned(&food, date(2024, 3, 1)), usd(200));
+        assert_eq!(envelopes.get_assigned(&rent, date(2024, 3, 1)), usd(i64::MAX - 100));
+    }
+}
@@ -0,0 +1,242 @@
+//! Dated exchange rates between currencies, used to convert amounts into a single reporting currency.
+
+use std::io::BufRead;
+
+use chrono::NaiveDate;
+
+use crate::common_types::{Currency, Money};
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ExchangeRate {
+    from: Currency,
+    to: Currency,
+    date: NaiveDate,
+    // Units of `to` per one unit of `from`. Rates are inherently approximate, so a float is good enough here.
+    rate: f64,
+}
+
+impl ExchangeRate {
+    pub fn get_from(&self) -> Currency {
+        self.from
+    }
+
+    pub fn get_to(&self) -> Currency {
+        self.to
+    }
+
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    pub fn get_rate(&self) -> f64 {
+        self.rate
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct ExchangeRates {
+    // Kept sorted by date so lookups can take the latest rate that applies.
+    rates: Vec<ExchangeRate>,
+}
+
+impl ExchangeRates {
+    pub fn new_empty() -> ExchangeRates {
+        ExchangeRates { rates: Vec::new() }
+    }
+
+    /// Record the rate from one currency to another on the given date, replacing any rate already recorded for
+    /// that pair and date.
+    pub fn set_rate(&mut self, from: Currency, to: Currency, date: NaiveDate, rate: f64) -> Result<(), String> {
+        if from == to {
+            return Err(format!("Cannot set an exchange rate from {} to itself", from));
+        }
+        if !rate.is_finite() || rate <= 0.0 {
+            return Err(format!("Exchange rate must be a positive number, got {}", rate));
+        }
+
+        self.rates.retain(|r| !(r.from == from && r.to == to && r.date == date));
+        let index = self.rates.partition_point(|r| r.date <= date);
+        self.rates.insert(index, ExchangeRate { from, to, date, rate });
+
+        Ok(())
+    }
+
+    pub fn rates(&self) -> impl Iterator<Item = &ExchangeRate> {
+        self.rates.iter()
+    }
+
+    /// The most recent rate from `from` to `to` recorded on or before `date`. A rate recorded in the opposite
+    /// direction is inverted if it's more recent.
+    pub fn get_rate(&self, from: Currency, to: Currency, date: NaiveDate) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+
+        self.rates.iter()
+            .rev()
+            .filter(|r| r.date <= date)
+            .find_map(|r| {
+                if r.from == from && r.to == to {
+                    Some(r.rate)
+                } else if r.from == to && r.to == from {
+                    Some(1.0 / r.rate)
+                } else {
+                    None
+                }
+            })
+    }
+
+    pub fn convert(&self, amount: Money, to: Currency, date: NaiveDate) -> Result<Money, String> {
+        let from = amount.currency();
+        let rate = self.get_rate(from, to, date).ok_or_else(
+            || format!("No exchange rate from {} to {} on or before {}", from, to, date))?;
+        Ok(amount.convert(rate, to))
+    }
+
+    /// Import rates from CSV data with the columns `date,from,to,rate`, e.g. `2024-01-31,EUR,USD,1.0837`. Blank
+    /// lines, lines starting with '#' and a header row before the first rate are skipped. Returns the number of rates imported.
+    /// Nothing is imported if any line fails to parse.
+    pub fn import_csv<R: BufRead>(&mut self, reader: R) -> Result<usize, String> {
+        let mut parsed = Vec::new();
+        let mut seen_row = false;
+        for (index, line) in reader.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.map_err(|e| format!("Failed to read line {}: {}", line_number, e))?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+            let first_row = !seen_row;
+            seen_row = true;
+            if first_row && fields[0].eq_ignore_ascii_case("date") {
+                continue;
+            }
+            parsed.push(Self::parse_csv_fields(&fields).map_err(|e| format!("Line {}: {}", line_number, e))?);
+        }
+
+        let count = parsed.len();
+        for (from, to, date, rate) in parsed {
+            self.set_rate(from, to, date, rate)?;
+        }
+        Ok(count)
+    }
+
+    fn parse_csv_fields(fields: &[&str]) -> Result<(Currency, Currency, NaiveDate, f64), String> {
+        let [date, from, to, rate] = fields else {
+            return Err(format!("Expected 4 fields (date,from,to,rate), found {}", fields.len()));
+        };
+
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+        let from = Currency::from_code(from).map_err(|e| e.to_string())?;
+        let to = Currency::from_code(to).map_err(|e| e.to_string())?;
+        let rate: f64 = rate.parse().map_err(|_| format!("Invalid rate '{}'", rate))?;
+        if from == to || !rate.is_finite() || rate <= 0.0 {
+            return Err(format!("Invalid rate {} from {} to {}", rate, from, to));
+        }
+
+        Ok((from, to, date, rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").expect("Failed to parse")
+    }
+
+    #[test]
+    fn same_currency_rate() {
+        let rates = ExchangeRates::new_empty();
+        assert_eq!(rates.get_rate(Currency::EUR, Currency::EUR, date("2000-01-01")), Some(1.0));
+    }
+
+    #[test]
+    fn missing_rate() {
+        let rates = ExchangeRates::new_empty();
+        assert_eq!(rates.get_rate(Currency::EUR, Currency::USD, date("2000-01-01")), None);
+    }
+
+    #[test]
+    fn uses_latest_rate_on_or_before_date() {
+        let mut rates = ExchangeRates::new_empty();
+        rates.set_rate(Currency::EUR, Currency::USD, date("2024-01-01"), 1.10).unwrap();
+        rates.set_rate(Currency::EUR, Currency::USD, date("2024-03-01"), 1.08).unwrap();
+        rates.set_rate(Currency::EUR, Currency::USD, date("2024-02-01"), 1.09).unwrap();
+
+        assert_eq!(rates.get_rate(Currency::EUR, Currency::USD, date("2023-12-31")), None);
+        assert_eq!(rates.get_rate(Currency::EUR, Currency::USD, date("2024-01-15")), Some(1.10));
+        assert_eq!(rates.get_rate(Currency::EUR, Currency::USD, date("2024-02-01")), Some(1.09));
+        assert_eq!(rates.get_rate(Currency::EUR, Currency::USD, date("2025-01-01")), Some(1.08));
+    }
+
+    #[test]
+    fn set_rate_replaces_same_day() {
+        let mut rates = ExchangeRates::new_empty();
+        rates.set_rate(Currency::EUR, Currency::USD, date("2024-01-01"), 1.10).unwrap();
+        rates.set_rate(Currency::EUR, Currency::USD, date("2024-01-01"), 1.20).unwrap();
+        assert_eq!(rates.rates().count(), 1);
+        assert_eq!(rates.get_rate(Currency::EUR, Currency::USD, date("2024-01-01")), Some(1.20));
+    }
+
+    #[test]
+    fn set_invalid_rate() {
+        let mut rates = ExchangeRates::new_empty();
+        assert!(rates.set_rate(Currency::EUR, Currency::USD, date("2024-01-01"), 0.0).is_err());
+        assert!(rates.set_rate(Currency::EUR, Currency::USD, date("2024-01-01"), -1.0).is_err());
+        assert!(rates.set_rate(Currency::EUR, Currency::USD, date("2024-01-01"), f64::NAN).is_err());
+        assert!(rates.set_rate(Currency::EUR, Currency::EUR, date("2024-01-01"), 1.0).is_err());
+    }
+
+    #[test]
+    fn inverse_rate() {
+        let mut rates = ExchangeRates::new_empty();
+        rates.set_rate(Currency::USD, Currency::JPY, date("2024-01-01"), 150.0).unwrap();
+        let converted = rates.convert(Money::new(300, Currency::JPY), Currency::USD, date("2024-01-02")).unwrap();
+        assert_eq!(converted, Money::new(200, Currency::USD));
+    }
+
+    #[test]
+    fn convert() {
+        let mut rates = ExchangeRates::new_empty();
+        rates.set_rate(Currency::GBP, Currency::USD, date("2024-01-01"), 1.25).unwrap();
+        let converted = rates.convert(Money::new(1000, Currency::GBP), Currency::USD, date("2024-01-01")).unwrap();
+        assert_eq!(converted, Money::new(1250, Currency::USD));
+        assert!(rates.convert(Money::new(1000, Currency::EUR), Currency::USD, date("2024-01-01")).is_err());
+    }
+
+    #[test]
+    fn import_csv() {
+        let data = "date,from,to,rate\n\
+                    2024-01-01,EUR,USD,1.10\n\
+                    \n\
+                    # comment\n\
+                    2024-01-02, gbp, usd, 1.25\n";
+        let mut rates = ExchangeRates::new_empty();
+        assert_eq!(rates.import_csv(data.as_bytes()), Ok(2));
+        assert_eq!(rates.get_rate(Currency::GBP, Currency::USD, date("2024-01-02")), Some(1.25));
+    }
+
+    #[test]
+    fn import_csv_header_after_comments() {
+        let data = "# Rates from the bank\n\nDate,From,To,Rate\n2024-01-01,EUR,USD,1.10\n";
+        let mut rates = ExchangeRates::new_empty();
+        assert_eq!(rates.import_csv(data.as_bytes()), Ok(1));
+
+        let data = "2024-01-01,EUR,USD,1.10\ndate,from,to,rate\n";
+        assert!(rates.import_csv(data.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn import_csv_invalid_line() {
+        let data = "2024-01-01,EUR,USD,1.10\n2024-01-02,EUR,USD\n";
+        let mut rates = ExchangeRates::new_empty();
+        let err = rates.import_csv(data.as_bytes()).unwrap_err();
+        assert!(err.starts_with("Line 2"), "{}", err);
+        assert_eq!(rates.rates().count(), 0);
+    }
+}
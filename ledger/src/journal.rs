@@ -0,0 +1,79 @@
+//! Journal entries for double-entry bookkeeping. An entry moves money between two or more accounts, and the amounts
+//! it posts to them always add up to zero. Each posting is stored as a transaction in its account, linked back to the
+//! entry, so account balances come straight from the postings.
+
+use std::fmt::Display;
+
+use crate::{account::AccountId, category::CategoryId, common_types::{Currency, Money}};
+
+/// Uniquely identifies a journal entry within a ledger.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub struct EntryId(u64);
+
+impl From<u64> for EntryId {
+    fn from(value: u64) -> Self {
+        EntryId(value)
+    }
+}
+
+impl Display for EntryId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// One line of a journal entry: an amount to post to an account, in the account's currency.
+#[derive(Debug, Clone)]
+pub struct Posting {
+    pub account: AccountId,
+    pub amount: Money,
+    pub category: Option<CategoryId>,
+}
+
+/// Fails unless there are at least two postings and, for each currency, the amounts add up to zero.
+pub(crate) fn check_balanced(postings: &[Posting]) -> Result<(), String> {
+    if postings.len() < 2 {
+        return Err("A journal entry needs at least two postings".to_string());
+    }
+
+    let mut currencies: Vec<Currency> = postings.iter().map(|p| p.amount.currency()).collect();
+    currencies.sort();
+    currencies.dedup();
+    for currency in currencies {
+        let amounts = postings.iter().map(|p| &p.amount).filter(|a| a.currency() == currency);
+        let total = Money::total(currency, amounts).map_err(|e| e.to_string())?;
+        if !total.is_zero() {
+            return Err(format!("The postings in {} add up to {} instead of zero", currency, total));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posting(account: u64, amount: i64, currency: Currency) -> Posting {
+        Posting {account: AccountId::from(account), amount: Money::new(amount, currency), category: None}
+    }
+
+    #[test]
+    fn balanced() {
+        assert!(check_balanced(&[posting(1, 100, Currency::USD), posting(2, -100, Currency::USD)]).is_ok());
+        assert!(check_balanced(&[
+            posting(1, 100, Currency::USD),
+            posting(2, -60, Currency::USD),
+            posting(3, -40, Currency::USD),
+            posting(4, 90, Currency::EUR),
+            posting(5, -90, Currency::EUR),
+        ]).is_ok());
+    }
+
+    #[test]
+    fn unbalanced() {
+        assert!(check_balanced(&[]).is_err());
+        assert!(check_balanced(&[posting(1, 0, Currency::USD)]).is_err());
+        assert!(check_balanced(&[posting(1, 100, Currency::USD), posting(2, -99, Currency::USD)]).is_err());
+        assert!(check_balanced(&[posting(1, 100, Currency::USD), posting(2, -100, Currency::EUR)]).is_err());
+    }
+}
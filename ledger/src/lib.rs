@@ -2,38 +2,590 @@
 //! It is the top-level object the application interacts with in order to query and manipulate user data. 
 
 pub mod account;
+pub mod budget;
 pub mod category;
+pub mod check;
 pub mod common_types;
+pub mod envelope;
+pub mod exchange;
+pub mod journal;
+pub mod payee;
+pub mod report;
+pub mod schedule;
 pub mod transaction;
 
-use account::Account;
-use category::TransactionCategories;
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+
+use account::{Account, AccountId, AccountType};
+use budget::{month_start, BudgetLine, Budgets};
+use category::{CategoryId, TransactionCategories};
+use check::Problem;
+use chrono::{DateTime, Days, Months, NaiveDate, TimeZone};
+use common_types::{Currency, Money};
+use envelope::{EnvelopeLine, EnvelopeReport, Envelopes};
+use exchange::ExchangeRates;
+use journal::{EntryId, Posting};
+use payee::{PayeeId, Payees};
+use report::{IncomeExpenseReport, TrialBalance};
+use schedule::{PostedSchedules, Schedule, ScheduleId, ScheduleUpdate};
+use transaction::{normalize_tag, Transaction, TransactionFilter, TransactionId, TransactionStatus, TransactionUpdate};
 
 // TODO: Move this to it's own file, if it's not annoying from a module hierarchy standpoint. 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct Ledger {
     accounts: Vec<Account>,
     categories: TransactionCategories,
+    #[serde(default)]
+    exchange_rates: ExchangeRates,
+    // The next IDs to hand out. These only ever go up, so IDs are never reused even after deletion. Files written
+    // before the account and transaction ones were stored load them as 0, and `fill_in_next_ids` works them out.
+    #[serde(default)]
+    next_account_id: u64,
+    #[serde(default)]
+    next_transaction_id: u64,
+    #[serde(default = "first_id")]
+    next_entry_id: u64,
+    // In double-entry mode, money can only move through balanced journal entries and transfers
+    #[serde(default)]
+    double_entry: bool,
+    #[serde(default)]
+    payees: Payees,
+    // Like the account and transaction ones, filled in by `fill_in_next_ids` if it wasn't in the file
+    #[serde(default)]
+    next_payee_id: u64,
+    #[serde(default)]
+    schedules: Vec<Schedule>,
+    #[serde(default)]
+    budgets: Budgets,
+    #[serde(default)]
+    envelopes: Envelopes,
+    #[serde(default = "first_id")]
+    next_schedule_id: u64,
+}
+
+fn first_id() -> u64 {
+    1
 }
 
 impl Ledger {
     pub fn new_empty() -> Ledger {
-        Ledger {accounts: Vec::new(), categories: TransactionCategories::new_empty() }
+        Ledger {
+            accounts: Vec::new(),
+            categories: TransactionCategories::new_empty(),
+            exchange_rates: ExchangeRates::new_empty(),
+            next_account_id: 1,
+            next_transaction_id: 1,
+            next_entry_id: 1,
+            double_entry: false,
+            payees: Payees::new_empty(),
+            next_payee_id: 1,
+            schedules: Vec::new(),
+            budgets: Budgets::new_empty(),
+            envelopes: Envelopes::new_empty(),
+            next_schedule_id: 1,
+        }
+    }
+
+    pub fn is_double_entry(&self) -> bool {
+        self.double_entry
+    }
+
+    /// Turn double-entry mode on or off. Existing transactions are kept either way; `get_trial_balance` shows
+    /// whether they balance.
+    pub fn set_double_entry(&mut self, double_entry: bool) {
+        self.double_entry = double_entry;
     }
 
-    pub fn add_new_account(&mut self, name: String) {
-        let new_account = Account::new_empty(name);
+    pub fn add_new_account(&mut self, name: String, currency: Currency) -> Result<AccountId, String> {
+        self.add_new_account_of_type(name, currency, AccountType::Asset)
+    }
+
+    /// Fails if the name is blank, has control characters in it, or is already used by another account. Names are
+    /// compared ignoring case, the same way `get_account_by_name` looks them up.
+    pub fn add_new_account_of_type(&mut self, name: String, currency: Currency, account_type: AccountType) -> Result<AccountId, String> {
+        self.check_account_name(&name, None)?;
+
+        self.fill_in_next_ids();
+        let id = AccountId::from(self.next_account_id);
+        self.next_account_id += 1;
+
+        let new_account = Account::new_empty(id, name, currency, account_type);
         self.accounts.push(new_account);
+        Ok(id)
+    }
+
+    /// Add an account that starts with a balance on `date`, as for `set_opening_balance`. Nothing is added if the
+    /// balance isn't valid for the account.
+    pub fn add_new_account_with_opening_balance(
+        &mut self,
+        name: String,
+        currency: Currency,
+        account_type: AccountType,
+        balance: Money,
+        date: NaiveDate,
+    ) -> Result<AccountId, String> {
+        if balance.currency() != currency {
+            return Err(format!("Account '{}' holds {}, but the opening balance is in {}", name, currency, balance.currency()));
+        }
+        account_type.from_stated_balance(balance)?;
+
+        let id = self.add_new_account_of_type(name, currency, account_type)?;
+        // The balance was checked above, so this can't fail
+        self.set_opening_balance(id, balance, date)?;
+        Ok(id)
+    }
+
+    /// Give an account a new name, which has to follow the same rules as for a new account.
+    pub fn rename_account(&mut self, id: AccountId, name: String) -> Result<(), String> {
+        self.check_account_name(&name, Some(id))?;
+        let account = self.get_account_mut(id).ok_or_else(|| format!("No account with ID {}", id))?;
+        account.set_name(name);
+        Ok(())
+    }
+
+    // Start the account, transaction and payee IDs after the largest ones in use, if they weren't in the file
+    fn fill_in_next_ids(&mut self) {
+        if self.next_account_id == 0 {
+            self.next_account_id = self.accounts.iter().map(|a| u64::from(a.get_id())).max().unwrap_or(0) + 1;
+        }
+        if self.next_transaction_id == 0 {
+            self.next_transaction_id = self.accounts.iter()
+                .flat_map(|a| a.get_transactions())
+                .map(|t| u64::from(t.get_id()))
+                .max()
+                .unwrap_or(0) + 1;
+        }
+        if self.next_payee_id == 0 {
+            self.next_payee_id = self.payees.get_payees().iter().map(|p| u64::from(p.get_id())).max().unwrap_or(0) + 1;
+        }
+    }
+
+    fn check_account_name(&self, name: &str, renaming: Option<AccountId>) -> Result<(), String> {
+        if name.trim().is_empty() {
+            return Err("Account names can't be blank".to_string());
+        }
+        if name.chars().any(char::is_control) {
+            return Err(format!("Account name {:?} has control characters in it", name));
+        }
+        if self.get_account_by_name(name).is_some_and(|a| Some(a.get_id()) != renaming) {
+            return Err(format!("An account named '{}' already exists", name));
+        }
+        Ok(())
+    }
+
+    /// Stop an account from taking new transactions, keeping the ones it has. Fails while schedules that haven't
+    /// ended still post to it.
+    pub fn close_account(&mut self, id: AccountId) -> Result<(), String> {
+        let schedules = self.schedules.iter().filter(|s| s.get_account() == id && s.get_next_due().is_some()).count();
+        let account = self.get_account_mut(id).ok_or_else(|| format!("No account with ID {}", id))?;
+        if account.is_closed() {
+            return Err(format!("Account '{}' is already closed", account.get_name()));
+        }
+        if schedules > 0 {
+            return Err(format!(
+                "Account '{}' still has {} schedules. Delete them or let them end first.", account.get_name(), schedules));
+        }
+        account.set_closed(true);
+        Ok(())
+    }
+
+    pub fn reopen_account(&mut self, id: AccountId) -> Result<(), String> {
+        let account = self.get_account_mut(id).ok_or_else(|| format!("No account with ID {}", id))?;
+        if !account.is_closed() {
+            return Err(format!("Account '{}' isn't closed", account.get_name()));
+        }
+        account.set_closed(false);
+        Ok(())
+    }
+
+    /// Move every transaction in one account to another, returning how many were moved. The accounts have to hold
+    /// the same currency, the target has to be open, and none of the transactions can be reconciled. Fails rather than
+    /// leave a transfer going from an account to itself.
+    pub fn move_transactions(&mut self, from: AccountId, to: AccountId) -> Result<usize, String> {
+        if from == to {
+            return Err("Can't move transactions to the account they're already in".to_string());
+        }
+        let source = self.get_account(from).ok_or_else(|| format!("No account with ID {}", from))?;
+        let target = self.get_account(to).ok_or_else(|| format!("No account with ID {}", to))?;
+        target.check_open()?;
+        if source.get_currency() != target.get_currency() {
+            return Err(format!(
+                "Account '{}' holds {}, but '{}' holds {}",
+                source.get_name(), source.get_currency(), target.get_name(), target.get_currency()));
+        }
+        let self_transfer = source.get_transactions().iter()
+            .filter_map(|t| t.get_transfer())
+            .find(|other| target.get_transaction(*other).is_some());
+        if let Some(other) = self_transfer {
+            return Err(format!("Transaction {} is a transfer with '{}', so it can't be moved there", other, target.get_name()));
+        }
+        if let Some(reconciled) = source.get_transactions().iter().find(|t| t.get_status() == TransactionStatus::Reconciled) {
+            return Err(format!("Transaction {} has been reconciled, so it can't be moved", reconciled.get_id()));
+        }
+
+        let transactions = self.get_account_mut(from).expect("Checked above").take_transactions();
+        let count = transactions.len();
+        let target = self.get_account_mut(to).expect("Checked above");
+        for transaction in transactions {
+            // The target was checked above, so this can't fail
+            target.add_transaction(transaction)?;
+        }
+        Ok(count)
+    }
+
+    /// Delete an account. If it still has transactions or schedules, this fails unless `force` is set, in which case
+    /// they're deleted along with the other halves of any transfers and journal entries they belong to. Accounts with
+    /// reconciled transactions can't be deleted.
+    pub fn delete_account(&mut self, id: AccountId, force: bool) -> Result<Account, String> {
+        let account = self.get_account(id).ok_or_else(|| format!("No account with ID {}", id))?;
+        let transaction_ids: Vec<TransactionId> = account.get_transactions().iter().map(|t| t.get_id()).collect();
+        if !transaction_ids.is_empty() && !force {
+            return Err(format!(
+                "Account '{}' still has {} transactions. Move them to another account or force the delete.",
+                account.get_name(), transaction_ids.len()));
+        }
+        let schedules = self.schedules.iter().filter(|s| s.get_account() == id).count();
+        if schedules > 0 && !force {
+            return Err(format!(
+                "Account '{}' still has {} schedules. Delete them or force the delete.", account.get_name(), schedules));
+        }
+        for transaction_id in &transaction_ids {
+            self.check_unlocked(*transaction_id)?;
+        }
+
+        for transaction_id in transaction_ids {
+            // Deleting one transaction can take linked ones with it, so some may already be gone
+            if self.get_transaction(transaction_id).is_some() {
+                self.delete_transaction(transaction_id)?;
+            }
+        }
+        self.schedules.retain(|s| s.get_account() != id);
+        let index = self.accounts.iter().position(|a| a.get_id() == id).expect("Checked above");
+        Ok(self.accounts.remove(index))
+    }
+
+    /// The other accounts holding halves of transfers, or postings of journal entries, that an account's transactions
+    /// belong to. Force deleting the account deletes those transactions from them too.
+    pub fn get_linked_accounts(&self, id: AccountId) -> Vec<&Account> {
+        let Some(account) = self.get_account(id) else { return Vec::new() };
+        let mut linked: Vec<TransactionId> = Vec::new();
+        for transaction in account.get_transactions() {
+            linked.extend(transaction.get_transfer());
+            if let Some(entry) = transaction.get_entry() {
+                linked.extend(self.get_journal_entry(entry).iter().map(|t| t.get_id()));
+            }
+        }
+        self.accounts.iter()
+            .filter(|a| a.get_id() != id && linked.iter().any(|t| a.get_transaction(*t).is_some()))
+            .collect()
+    }
+
+    /// Set the balance an account started with on `date`. For liabilities like credit cards and loans, `balance` is
+    /// the amount owed, so it lowers the total.
+    pub fn set_opening_balance(&mut self, account: AccountId, balance: Money, date: NaiveDate) -> Result<(), String> {
+        let account = self.get_account_mut(account).ok_or_else(|| format!("No account with ID {}", account))?;
+        let balance = account.get_type().from_stated_balance(balance)?;
+        account.set_opening_balance(balance, date)
+    }
+
+    /// Reconcile an account against a bank statement. If the account's cleared balance on the statement date matches
+    /// the statement, every cleared transaction up to that date becomes reconciled, and the number of them is
+    /// returned. For liabilities, `statement_balance` is the amount owed.
+    pub fn reconcile(&mut self, account: AccountId, statement_date: NaiveDate, statement_balance: Money) -> Result<usize, String> {
+        let account = self.get_account_mut(account).ok_or_else(|| format!("No account with ID {}", account))?;
+        if statement_balance.currency() != account.get_currency() {
+            return Err(format!(
+                "Account '{}' holds {}, but the statement balance is in {}",
+                account.get_name(), account.get_currency(), statement_balance.currency()));
+        }
+        let balance = account.get_type().from_stated_balance(statement_balance)?;
+        let cleared = account.cleared_balance_at(statement_date);
+        if cleared != balance {
+            return Err(format!(
+                "The cleared balance of '{}' is {}, but the statement balance is {}", account.get_name(), cleared, balance));
+        }
+
+        let cleared_ids: Vec<TransactionId> = account.get_transactions().iter()
+            .filter(|t| t.get_status() == TransactionStatus::Cleared && t.get_date() <= statement_date)
+            .map(|t| t.get_id())
+            .collect();
+        for id in &cleared_ids {
+            account.set_transaction_status(*id, TransactionStatus::Reconciled)?;
+        }
+        account.set_last_reconciled(statement_date, balance);
+        Ok(cleared_ids.len())
     }
 
     pub fn get_accounts(&self) -> &Vec<Account> {
         &self.accounts
     }
 
+    pub fn get_account(&self, id: AccountId) -> Option<&Account> {
+        self.accounts.iter().find(|a| a.get_id() == id)
+    }
+
+    pub fn get_account_mut(&mut self, id: AccountId) -> Option<&mut Account> {
+        self.accounts.iter_mut().find(|a| a.get_id() == id)
+    }
+
+    pub fn get_account_by_name(&self, name: &str) -> Option<&Account> {
+        self.accounts.iter().find(|a| a.get_name().eq_ignore_ascii_case(name))
+    }
+
     pub fn get_account_by_name_mut(&mut self, name: &str) -> Option<&mut Account> {
         self.accounts.iter_mut().find(|a| a.get_name().eq_ignore_ascii_case(name))
     }
 
+    /// Add a transaction to an account, assigning it a new ID. In double-entry mode this isn't allowed, since a
+    /// transaction on its own would unbalance the books.
+    pub fn add_transaction(&mut self, account_id: AccountId, transaction: Transaction) -> Result<TransactionId, String> {
+        if self.double_entry {
+            return Err("In double-entry mode, add a journal entry instead of a single transaction".to_string());
+        }
+        self.insert_transaction(account_id, transaction)
+    }
+
+    fn insert_transaction(&mut self, account_id: AccountId, mut transaction: Transaction) -> Result<TransactionId, String> {
+        self.check_new_transactions(&[(account_id, &transaction)])?;
+
+        self.fill_in_next_ids();
+        let account = self.accounts.iter_mut().find(|a| a.get_id() == account_id)
+            .ok_or_else(|| format!("No account with ID {}", account_id))?;
+
+        let id = TransactionId::from(self.next_transaction_id);
+        transaction.set_id(id);
+        transaction.set_payee(self.payees.match_description(transaction.get_description()));
+        account.add_transaction(transaction)?;
+        self.next_transaction_id += 1;
+        Ok(id)
+    }
+
+    // Fails if any of the transactions couldn't be inserted, so that transactions which belong together are added
+    // all at once or not at all. Besides what each account checks, no account's total can overflow.
+    fn check_new_transactions(&self, transactions: &[(AccountId, &Transaction)]) -> Result<(), String> {
+        let mut totals: HashMap<AccountId, Money> = HashMap::new();
+        for (account_id, transaction) in transactions {
+            // Covers the transaction's own category, or each split's if it's split
+            for (category, _) in transaction.get_category_amounts() {
+                if let Some(category) = category.filter(|c| self.categories.get_category(c).is_none()) {
+                    return Err(format!("No category named '{}'", category));
+                }
+            }
+
+            let account = self.get_account(*account_id).ok_or_else(|| format!("No account with ID {}", account_id))?;
+            account.check_new_transaction(transaction)?;
+            let total = totals.entry(*account_id).or_insert_with(|| account.get_transaction_total());
+            *total = total.checked_add(*transaction.get_amount())
+                .map_err(|e| format!("Account '{}' can't hold any more: {}", account.get_name(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Move a positive `amount` between two accounts holding its currency. This adds a withdrawal from `from` and a
+    /// deposit into `to`, linked so that edits and deletes apply to both. Returns the IDs of the withdrawal and the
+    /// deposit, in that order.
+    pub fn transfer<Tz: TimeZone>(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        amount: Money,
+        time: DateTime<Tz>,
+        description: String,
+    ) -> Result<(TransactionId, TransactionId), String> {
+        if from == to {
+            return Err("Can't transfer from an account to itself".to_string());
+        }
+        if amount.is_negative() || amount.is_zero() {
+            return Err(format!("Transfer amount must be positive, not {}", amount));
+        }
+        for id in [from, to] {
+            let account = self.get_account(id).ok_or_else(|| format!("No account with ID {}", id))?;
+            account.check_open()?;
+            if account.get_currency() != amount.currency() {
+                return Err(format!(
+                    "Account '{}' holds {}, but the transfer is in {}", account.get_name(), account.get_currency(), amount.currency()));
+            }
+        }
+
+        self.fill_in_next_ids();
+        let withdrawal_id = TransactionId::from(self.next_transaction_id);
+        let deposit_id = TransactionId::from(self.next_transaction_id + 1);
+        let mut withdrawal = Transaction::new(-amount, time.clone(), description.clone(), None);
+        withdrawal.set_transfer(deposit_id);
+        let mut deposit = Transaction::new(amount, time, description, None);
+        deposit.set_transfer(withdrawal_id);
+
+        // Once both are known to fit, neither insert can fail and leave half a transfer behind
+        self.check_new_transactions(&[(from, &withdrawal), (to, &deposit)])?;
+        self.insert_transaction(from, withdrawal)?;
+        self.insert_transaction(to, deposit)?;
+        Ok((withdrawal_id, deposit_id))
+    }
+
+    /// Record a balanced journal entry, adding a transaction to each posting's account. For each currency, the
+    /// posted amounts have to add up to zero.
+    pub fn add_journal_entry<Tz: TimeZone>(
+        &mut self,
+        time: DateTime<Tz>,
+        description: String,
+        postings: Vec<Posting>,
+    ) -> Result<EntryId, String> {
+        journal::check_balanced(&postings)?;
+        for posting in &postings {
+            let account = self.get_account(posting.account).ok_or_else(|| format!("No account with ID {}", posting.account))?;
+            account.check_open()?;
+            if account.get_currency() != posting.amount.currency() {
+                return Err(format!(
+                    "Account '{}' holds {}, but its posting is in {}", account.get_name(), account.get_currency(), posting.amount.currency()));
+            }
+            if let Some(category) = &posting.category {
+                if self.categories.get_category(category).is_none() {
+                    return Err(format!("No category named '{}'", category));
+                }
+            }
+        }
+
+        let entry_id = EntryId::from(self.next_entry_id);
+        let transactions: Vec<(AccountId, Transaction)> = postings.into_iter()
+            .map(|posting| {
+                let mut transaction = Transaction::new(posting.amount, time.clone(), description.clone(), posting.category);
+                transaction.set_entry(entry_id);
+                (posting.account, transaction)
+            })
+            .collect();
+
+        // Once every posting is known to fit, none of the inserts can fail and leave part of the entry behind
+        let checked: Vec<(AccountId, &Transaction)> = transactions.iter().map(|(a, t)| (*a, t)).collect();
+        self.check_new_transactions(&checked)?;
+        self.next_entry_id += 1;
+        for (account, transaction) in transactions {
+            self.insert_transaction(account, transaction)?;
+        }
+        Ok(entry_id)
+    }
+
+    /// The postings of a journal entry, in the order they were made. Empty if there's no such entry.
+    pub fn get_journal_entry(&self, id: EntryId) -> Vec<&Transaction> {
+        let mut postings: Vec<&Transaction> = self.accounts.iter()
+            .flat_map(|a| a.get_transactions())
+            .filter(|t| t.get_entry() == Some(id))
+            .collect();
+        postings.sort_by_key(|t| t.get_id());
+        postings
+    }
+
+    pub fn get_transaction(&self, id: TransactionId) -> Option<&Transaction> {
+        self.accounts.iter().find_map(|a| a.get_transaction(id))
+    }
+
+    /// Change some or all of the fields of an existing transaction. For half of a transfer, the other half gets the
+    /// same date and description, and the opposite amount. For a posting of a journal entry, the other postings get
+    /// the same date and description, but the amount can't change since that would unbalance the entry. Categories
+    /// aren't shared. Reconciled transactions, and ones linked to them, can't be changed.
+    pub fn update_transaction(&mut self, id: TransactionId, update: TransactionUpdate) -> Result<(), String> {
+        let split_categories = update.splits.iter().flatten().filter_map(|s| s.get_category());
+        for category in update.category.iter().flatten().chain(split_categories) {
+            if self.categories.get_category(category).is_none() {
+                return Err(format!("No category named '{}'", category));
+            }
+        }
+
+        self.check_unlocked(id)?;
+        let transaction = self.get_transaction(id).ok_or_else(|| format!("No transaction with ID {}", id))?;
+        let shared = TransactionUpdate {
+            time: update.time,
+            date: update.date,
+            description: update.description.clone(),
+            ..Default::default()
+        };
+        let mut mirrored = Vec::new();
+        if let Some(other) = transaction.get_transfer() {
+            let amount = update.amount
+                .map(|a| a.checked_neg().map_err(|e| e.to_string()))
+                .transpose()?;
+            mirrored.push((other, TransactionUpdate {amount, ..shared.clone()}));
+        }
+        if let Some(entry) = transaction.get_entry() {
+            if update.amount.is_some_and(|a| a != *transaction.get_amount()) || update.splits.is_some() {
+                return Err(format!("Transaction {} is part of journal entry {}, so its amount can't change on its own", id, entry));
+            }
+            for other in self.get_journal_entry(entry).into_iter().filter(|t| t.get_id() != id) {
+                mirrored.push((other.get_id(), shared.clone()));
+            }
+        }
+
+        let description_changed = update.description.is_some();
+        let account = self.accounts.iter_mut().find(|a| a.get_transaction(id).is_some())
+            .ok_or_else(|| format!("No transaction with ID {}", id))?;
+        account.update_transaction(id, update)?;
+
+        // Linked transactions only get changes that already worked on this one, so these can't fail
+        let mut changed = vec![id];
+        for (other, update) in mirrored {
+            if let Some(account) = self.accounts.iter_mut().find(|a| a.get_transaction(other).is_some()) {
+                account.update_transaction(other, update)?;
+                changed.push(other);
+            }
+        }
+        if description_changed {
+            let transactions = self.accounts.iter_mut().flat_map(|a| a.transactions_mut()).filter(|t| changed.contains(&t.get_id()));
+            for transaction in transactions {
+                transaction.set_payee(self.payees.match_description(transaction.get_description()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove a transaction from whichever account holds it, returning the removed transaction. Deleting half of a
+    /// transfer deletes the other half too, and deleting a posting deletes its whole journal entry. Reconciled
+    /// transactions, and ones linked to them, can't be deleted.
+    pub fn delete_transaction(&mut self, id: TransactionId) -> Result<Transaction, String> {
+        self.check_unlocked(id)?;
+        let removed = self.accounts.iter_mut()
+            .find_map(|a| a.remove_transaction(id))
+            .ok_or_else(|| format!("No transaction with ID {}", id))?;
+
+        let mut linked: Vec<TransactionId> = removed.get_transfer().into_iter().collect();
+        if let Some(entry) = removed.get_entry() {
+            linked.extend(self.get_journal_entry(entry).iter().map(|t| t.get_id()));
+        }
+        for other in linked {
+            self.accounts.iter_mut().find_map(|a| a.remove_transaction(other));
+        }
+        Ok(removed)
+    }
+
+    /// Mark a transaction as pending or cleared. Setting the status of a reconciled transaction unlocks it, so it can
+    /// be edited again. Transactions only become reconciled by reconciling their account.
+    pub fn set_transaction_status(&mut self, id: TransactionId, status: TransactionStatus) -> Result<(), String> {
+        if status == TransactionStatus::Reconciled {
+            return Err("Transactions can only be reconciled by reconciling their account against a statement".to_string());
+        }
+        let account = self.accounts.iter_mut().find(|a| a.get_transaction(id).is_some())
+            .ok_or_else(|| format!("No transaction with ID {}", id))?;
+        account.set_transaction_status(id, status)
+    }
+
+    // Fails if the transaction, or a transaction linked to it by a transfer or journal entry, has been reconciled
+    fn check_unlocked(&self, id: TransactionId) -> Result<(), String> {
+        let transaction = self.get_transaction(id).ok_or_else(|| format!("No transaction with ID {}", id))?;
+        let mut linked = vec![transaction];
+        linked.extend(transaction.get_transfer().and_then(|other| self.get_transaction(other)));
+        if let Some(entry) = transaction.get_entry() {
+            linked.extend(self.get_journal_entry(entry));
+        }
+        match linked.iter().find(|t| t.get_status() == TransactionStatus::Reconciled) {
+            Some(t) if t.get_id() == id => Err(format!("Transaction {} has been reconciled, so it can't be changed", id)),
+            Some(t) => Err(format!("Transaction {} is linked to transaction {}, which has been reconciled", id, t.get_id())),
+            None => Ok(()),
+        }
+    }
+
+    /// The account that holds the transaction with the given ID.
+    pub fn get_account_for_transaction(&self, id: TransactionId) -> Option<&Account> {
+        self.accounts.iter().find(|a| a.get_transaction(id).is_some())
+    }
+
     pub fn get_transaction_categories(&self) -> &TransactionCategories {
         &self.categories
     }
@@ -41,10 +593,551 @@ impl Ledger {
     pub fn get_transaction_categories_mut(&mut self) -> &mut TransactionCategories {
         &mut self.categories
     }
+
+    /// Move every transaction and split in category `from` into `into`, then remove `from`, returning how many
+    /// transactions moved. Recategorizing doesn't change any amounts, so reconciled transactions move too.
+    pub fn merge_categories(&mut self, from: &CategoryId, into: &CategoryId) -> Result<usize, String> {
+        if from == into {
+            return Err("Can't merge a category into itself".to_string());
+        }
+        if self.categories.get_category(into).is_none() {
+            return Err(format!("No category with ID '{}'", into));
+        }
+        self.check_removable_category(from)?;
+        Ok(self.remove_category(from, Some(into)))
+    }
+
+    /// Delete a category, returning how many transactions were in it. If any transactions, splits or schedules are
+    /// still in it, this fails unless `uncategorize` is set, in which case they're left uncategorized.
+    pub fn delete_category(&mut self, id: &CategoryId, uncategorize: bool) -> Result<usize, String> {
+        self.check_removable_category(id)?;
+        let used = self.accounts.iter().flat_map(|a| a.get_transactions()).filter(|t| t.uses_category(id)).count();
+        if used > 0 && !uncategorize {
+            return Err(format!(
+                "Category {} still has {} transactions. Merge it into another category or leave them uncategorized.",
+                self.categories.display_name(id), used));
+        }
+        let scheduled = self.schedules.iter().filter(|s| s.get_category() == Some(id)).count();
+        if scheduled > 0 && !uncategorize {
+            return Err(format!(
+                "Category {} still has {} schedules. Edit them or leave them uncategorized.",
+                self.categories.display_name(id), scheduled));
+        }
+        Ok(self.remove_category(id, None))
+    }
+
+    // Categories with subcategories can't be removed, since that would leave the subcategories without a parent
+    fn check_removable_category(&self, id: &CategoryId) -> Result<(), String> {
+        if self.categories.get_category(id).is_none() {
+            return Err(format!("No category with ID '{}'", id));
+        }
+        if !self.categories.children(Some(id)).is_empty() {
+            return Err(format!(
+                "Category {} has subcategories. Merge or delete them first.", self.categories.display_name(id)));
+        }
+        Ok(())
+    }
+
+    // The category's budget goes with it, even when it's merged into another one, since that has its own budget.
+    // Money assigned to its envelope moves along with its transactions, though.
+    fn remove_category(&mut self, id: &CategoryId, replacement: Option<&CategoryId>) -> usize {
+        let moved = self.accounts.iter_mut().map(|a| a.replace_category(id, replacement)).sum();
+        self.budgets.remove_budget(id);
+        self.envelopes.replace_category(id, replacement);
+        for schedule in &mut self.schedules {
+            schedule.replace_category(id, replacement);
+        }
+        self.categories.remove_category(id);
+        moved
+    }
+
+    /// Every tag in use, with how many transactions have it, in alphabetical order.
+    pub fn get_tags(&self) -> BTreeMap<&String, usize> {
+        let mut tags = BTreeMap::new();
+        for tag in self.accounts.iter().flat_map(|a| a.get_transactions()).flat_map(|t| t.get_tags()) {
+            *tags.entry(tag).or_insert(0) += 1;
+        }
+        tags
+    }
+
+    /// Rename a tag on every transaction that has it, returning how many did. Transactions that already have the new
+    /// tag just lose the old one. Like categories, tags don't change any amounts, so this includes reconciled
+    /// transactions.
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> Result<usize, String> {
+        let (from, to) = (normalize_tag(from)?, normalize_tag(to)?);
+        Ok(self.accounts.iter_mut().map(|a| a.replace_tag(&from, Some(&to))).sum())
+    }
+
+    /// Remove a tag from every transaction that has it, returning how many did.
+    pub fn remove_tag(&mut self, tag: &str) -> Result<usize, String> {
+        let tag = normalize_tag(tag)?;
+        Ok(self.accounts.iter_mut().map(|a| a.replace_tag(&tag, None)).sum())
+    }
+
+    pub fn get_payees(&self) -> &Payees {
+        &self.payees
+    }
+
+    /// Add a payee with the given alias patterns, returning its ID. Existing transactions without a payee are
+    /// matched against it.
+    pub fn add_payee(&mut self, name: String, aliases: Vec<String>) -> Result<PayeeId, String> {
+        self.fill_in_next_ids();
+        let id = PayeeId::from(self.next_payee_id);
+        self.payees.add_payee(id, name, aliases)?;
+        self.next_payee_id += 1;
+        self.match_payees();
+        Ok(id)
+    }
+
+    /// Add an alias pattern to a payee, returning how many transactions without a payee it matched.
+    pub fn add_payee_alias(&mut self, payee: PayeeId, pattern: String) -> Result<usize, String> {
+        self.payees.add_alias(payee, pattern)?;
+        Ok(self.match_payees())
+    }
+
+    pub fn rename_payee(&mut self, payee: PayeeId, name: String) -> Result<(), String> {
+        self.payees.rename_payee(payee, name)
+    }
+
+    /// Move every transaction from one payee to another, and remove the first. Its name and aliases become aliases of
+    /// the other one, so descriptions it matched keep matching. Returns how many transactions moved.
+    pub fn merge_payees(&mut self, from: PayeeId, into: PayeeId) -> Result<usize, String> {
+        self.payees.merge_payees(from, into)?;
+        let transactions = self.accounts.iter_mut()
+            .flat_map(|a| a.transactions_mut())
+            .filter(|t| t.get_payee() == Some(from));
+        let mut moved = 0;
+        for transaction in transactions {
+            transaction.set_payee(Some(into));
+            moved += 1;
+        }
+        Ok(moved)
+    }
+
+    // Match every transaction without a payee against the payees, returning how many got one
+    fn match_payees(&mut self) -> usize {
+        let mut matched = 0;
+        for transaction in self.accounts.iter_mut().flat_map(|a| a.transactions_mut()).filter(|t| t.get_payee().is_none()) {
+            let payee = self.payees.match_description(transaction.get_description());
+            if payee.is_some() {
+                transaction.set_payee(payee);
+                matched += 1;
+            }
+        }
+        matched
+    }
+
+    pub fn get_schedules(&self) -> &Vec<Schedule> {
+        &self.schedules
+    }
+
+    pub fn get_schedule(&self, id: ScheduleId) -> Option<&Schedule> {
+        self.schedules.iter().find(|s| s.get_id() == id)
+    }
+
+    /// Add a schedule, returning its ID. Fails unless its account exists, is open and holds the schedule's currency,
+    /// and its category exists.
+    pub fn add_schedule(&mut self, mut schedule: Schedule) -> Result<ScheduleId, String> {
+        self.check_schedule(&schedule)?;
+        let id = ScheduleId::from(self.next_schedule_id);
+        schedule.set_id(id);
+        self.schedules.push(schedule);
+        self.next_schedule_id += 1;
+        Ok(id)
+    }
+
+    /// Change a schedule's template or end. Transactions it's already posted are left as they are.
+    pub fn update_schedule(&mut self, id: ScheduleId, update: ScheduleUpdate) -> Result<(), String> {
+        let mut schedule = self.get_schedule(id).ok_or_else(|| format!("No schedule with ID {}", id))?.clone();
+        schedule.update(update)?;
+        self.check_schedule(&schedule)?;
+        *self.get_schedule_mut(id)? = schedule;
+        Ok(())
+    }
+
+    fn check_schedule(&self, schedule: &Schedule) -> Result<(), String> {
+        schedule.get_frequency().check()?;
+        let account = self.get_account(schedule.get_account())
+            .ok_or_else(|| format!("No account with ID {}", schedule.get_account()))?;
+        account.check_open()?;
+        if schedule.get_amount().currency() != account.get_currency() {
+            return Err(format!(
+                "Account '{}' holds {}, but the schedule is in {}",
+                account.get_name(), account.get_currency(), schedule.get_amount().currency()));
+        }
+        if let Some(category) = schedule.get_category().filter(|c| self.categories.get_category(c).is_none()) {
+            return Err(format!("No category with ID '{}'", category));
+        }
+        Ok(())
+    }
+
+    /// Skip a schedule's next occurrence without posting it, returning its date.
+    pub fn skip_schedule(&mut self, id: ScheduleId) -> Result<NaiveDate, String> {
+        let schedule = self.get_schedule_mut(id)?;
+        let date = schedule.get_next_due().ok_or_else(|| format!("Schedule {} has ended", id))?;
+        schedule.advance();
+        Ok(date)
+    }
+
+    pub fn delete_schedule(&mut self, id: ScheduleId) -> Result<Schedule, String> {
+        let index = self.schedules.iter().position(|s| s.get_id() == id).ok_or_else(|| format!("No schedule with ID {}", id))?;
+        Ok(self.schedules.remove(index))
+    }
+
+    fn get_schedule_mut(&mut self, id: ScheduleId) -> Result<&mut Schedule, String> {
+        self.schedules.iter_mut().find(|s| s.get_id() == id).ok_or_else(|| format!("No schedule with ID {}", id))
+    }
+
+    /// Every occurrence of every schedule that's due by `today` and hasn't been posted or skipped, oldest first.
+    pub fn get_due_schedules(&self, today: NaiveDate) -> Vec<(ScheduleId, NaiveDate)> {
+        let mut due: Vec<(ScheduleId, NaiveDate)> = self.schedules.iter()
+            .flat_map(|s| s.get_due(today).into_iter().map(|date| (s.get_id(), date)))
+            .collect();
+        due.sort_by_key(|(id, date)| (*date, *id));
+        due
+    }
+
+    /// Post a transaction for every occurrence `get_due_schedules` returns. An occurrence that can't be posted, like
+    /// in double-entry mode, is skipped along with the rest of its schedule's. They stay due, so they can be posted
+    /// once the problem is fixed.
+    pub fn post_due_schedules(&mut self, today: NaiveDate) -> PostedSchedules {
+        let mut result = PostedSchedules::default();
+        let mut failed: HashMap<ScheduleId, String> = HashMap::new();
+        for (id, date) in self.get_due_schedules(today) {
+            if let Some(error) = failed.get(&id) {
+                result.skipped.push((id, date, error.clone()));
+                continue;
+            }
+            let schedule = self.get_schedule(id).expect("Due schedules exist");
+            let account = schedule.get_account();
+            let transaction = schedule.to_transaction(date);
+            match self.add_transaction(account, transaction) {
+                Ok(transaction_id) => {
+                    self.get_schedule_mut(id).expect("Due schedules exist").advance();
+                    result.posted.push(transaction_id);
+                },
+                Err(e) => {
+                    failed.insert(id, e.clone());
+                    result.skipped.push((id, date, e));
+                },
+            }
+        }
+        result
+    }
+
+    pub fn get_budgets(&self) -> &Budgets {
+        &self.budgets
+    }
+
+    /// Budget `amount` a month for a category, from the month `date` is in until it's set again for a later month.
+    pub fn set_budget(&mut self, category: &CategoryId, date: NaiveDate, amount: Money) -> Result<(), String> {
+        if self.categories.get_category(category).is_none() {
+            return Err(format!("No category with ID '{}'", category));
+        }
+        self.budgets.set_budget(category, date, amount)
+    }
+
+    /// Choose whether what's left of a category's budget at the end of each month carries into the next. This
+    /// applies to every month since the budget started, not just the ones after it's changed.
+    pub fn set_budget_rollover(&mut self, category: &CategoryId, rollover: bool) -> Result<(), String> {
+        self.budgets.set_rollover(category, rollover)
+    }
+
+    pub fn remove_budget(&mut self, category: &CategoryId) -> Result<(), String> {
+        if !self.budgets.remove_budget(category) {
+            return Err(format!("Category {} doesn't have a budget", self.categories.display_name(category)));
+        }
+        Ok(())
+    }
+
+    /// How each budgeted category did in the month `date` is in, in category tree order. Categories whose budgets
+    /// start after that month are left out. Only transactions in a budget's currency count against it.
+    pub fn get_budget_report(&self, date: NaiveDate) -> Result<Vec<BudgetLine>, String> {
+        let month = month_start(date);
+        let mut reports = HashMap::new();
+        let mut lines = Vec::new();
+        for (_, category) in self.categories.tree() {
+            let Some(budget) = self.budgets.get_budget(category.get_id()) else {
+                continue;
+            };
+            let Some(budgeted) = budget.amount_for(month) else {
+                continue;
+            };
+            let currency = budgeted.currency();
+
+            let mut carried = Money::zero(currency);
+            if budget.get_rollover() {
+                let mut earlier = budget.get_start();
+                while earlier < month {
+                    let available = budget.amount_for(earlier).expect("The budget has started").checked_add(carried)
+                        .map_err(|e| e.to_string())?;
+                    let spent = self.spent_in(category.get_id(), currency, earlier, &mut reports)?;
+                    carried = available.checked_sub(spent).map_err(|e| e.to_string())?;
+                    earlier = earlier + Months::new(1);
+                }
+            }
+
+            let spent = self.spent_in(category.get_id(), currency, month, &mut reports)?;
+            lines.push(BudgetLine {category: category.get_id().clone(), budgeted, carried, spent});
+        }
+        Ok(lines)
+    }
+
+    // Net spending in a category and its subcategories in the month starting on `month`. Rollover needs every month
+    // since a budget started, for every budgeted category, so the reports are kept in `reports` for reuse.
+    fn spent_in(
+        &self,
+        category: &CategoryId,
+        currency: Currency,
+        month: NaiveDate,
+        reports: &mut HashMap<(Currency, NaiveDate), IncomeExpenseReport>,
+    ) -> Result<Money, String> {
+        let report = match reports.entry((currency, month)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let filter = TransactionFilter {
+                    from: Some(month),
+                    to: Some(month + Months::new(1) - Days::new(1)),
+                    currency: Some(currency),
+                    ..Default::default()
+                };
+                entry.insert(self.get_income_expense_report(currency, &filter)?)
+            },
+        };
+        let net = report.get_category_totals().iter().find(|(c, _)| c == category).map_or(Money::zero(currency), |(_, total)| *total);
+        net.checked_neg().map_err(|e| e.to_string())
+    }
+
+    pub fn get_envelopes(&self) -> &Envelopes {
+        &self.envelopes
+    }
+
+    /// Turn envelope budgeting on in `currency`, or off if it's None. Money assigned to envelopes is kept while it's
+    /// off. Fails if money has already been assigned in another currency.
+    pub fn set_envelope_mode(&mut self, currency: Option<Currency>) -> Result<(), String> {
+        self.envelopes.set_mode(currency)
+    }
+
+    /// Assign `amount` to a category's envelope in the month `date` is in, out of the money that's ready to assign. A
+    /// negative amount returns money to ready to assign.
+    pub fn assign_to_envelope(&mut self, category: &CategoryId, date: NaiveDate, amount: Money) -> Result<(), String> {
+        if self.categories.get_category(category).is_none() {
+            return Err(format!("No category with ID '{}'", category));
+        }
+        self.envelopes.assign(category, date, amount)
+    }
+
+    /// Move a positive `amount` from one envelope to another in the month `date` is in.
+    pub fn move_between_envelopes(&mut self, from: &CategoryId, to: &CategoryId, date: NaiveDate, amount: Money) -> Result<(), String> {
+        if from == to {
+            return Err("Can't move money to the envelope it's already in".to_string());
+        }
+        if amount.is_negative() || amount.is_zero() {
+            return Err("The amount to move has to be positive".to_string());
+        }
+        for category in [from, to] {
+            if self.categories.get_category(category).is_none() {
+                return Err(format!("No category with ID '{}'", category));
+            }
+        }
+        self.envelopes.move_between(from, to, date, amount)
+    }
+
+    /// The envelopes for the month `date` is in, in category tree order. Envelopes that haven't had any money assigned
+    /// or transactions by the end of the month are left out. Only transactions in the envelope currency count. Ready
+    /// to assign is the uncategorized income and the opening balances of balance sheet accounts in that currency, less
+    /// what's been assigned.
+    pub fn get_envelope_report(&self, date: NaiveDate) -> Result<EnvelopeReport, String> {
+        if !self.envelopes.is_enabled() {
+            return Err("Envelope budgeting isn't turned on".to_string());
+        }
+        let currency = self.envelopes.get_currency();
+        let month = month_start(date);
+        let end = month + Months::new(1) - Days::new(1);
+        let in_currency = TransactionFilter {to: Some(end), currency: Some(currency), ..Default::default()};
+        let to_date = self.get_income_expense_report(currency, &in_currency)?;
+        let this_month = self.get_income_expense_report(currency, &TransactionFilter {from: Some(month), ..in_currency})?;
+        let net = |report: &IncomeExpenseReport, category: Option<&CategoryId>| {
+            report.get_categories().iter().find(|(c, _)| c.as_ref() == category).map(|(_, total)| *total)
+        };
+
+        let mut envelopes = Vec::new();
+        for (_, category) in self.categories.tree() {
+            let id = category.get_id();
+            let assigned = self.envelopes.get_assigned_through(Some(id), month);
+            let net_to_date = net(&to_date, Some(id));
+            if assigned.is_zero() && net_to_date.is_none() {
+                continue;
+            }
+            envelopes.push(EnvelopeLine {
+                category: id.clone(),
+                assigned: self.envelopes.get_assigned(id, month),
+                activity: net(&this_month, Some(id)).unwrap_or(Money::zero(currency)),
+                available: assigned.checked_add(net_to_date.unwrap_or(Money::zero(currency))).map_err(|e| e.to_string())?,
+            });
+        }
+
+        // Money the accounts started with is ready to assign like income. Liabilities start negative, so what's owed on
+        // them has to be covered first.
+        let mut ready_to_assign = net(&to_date, None).unwrap_or(Money::zero(currency));
+        let opening_balances = self.accounts.iter()
+            .filter(|a| a.get_type().is_balance_sheet() && a.get_currency() == currency)
+            .filter_map(|a| a.get_opening_balance())
+            .filter(|(_, opened)| *opened <= end);
+        for (balance, _) in opening_balances {
+            ready_to_assign = ready_to_assign.checked_add(balance).map_err(|e| e.to_string())?;
+        }
+        let ready_to_assign = ready_to_assign.checked_sub(self.envelopes.get_assigned_through(None, month)).map_err(|e| e.to_string())?;
+        Ok(EnvelopeReport {month, ready_to_assign, envelopes})
+    }
+
+    /// Look for references to things that don't exist, like transactions in deleted categories, and schedules that
+    /// can't come due. These can't be made through the ledger's own methods, but can turn up in files written by
+    /// older or buggy versions.
+    pub fn check(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        for transaction in self.accounts.iter().flat_map(|a| a.get_transactions()) {
+            let mut missing: Vec<&CategoryId> = transaction.get_categories()
+                .filter(|c| self.categories.get_category(c).is_none())
+                .collect();
+            missing.sort_by_key(|c| c.to_string());
+            missing.dedup();
+            problems.extend(missing.into_iter().map(|category| Problem::MissingCategory {
+                transaction: transaction.get_id(),
+                category: category.clone(),
+            }));
+        }
+        for transaction in self.accounts.iter().flat_map(|a| a.get_transactions()) {
+            if let Some(payee) = transaction.get_payee().filter(|p| self.payees.get_payee(*p).is_none()) {
+                problems.push(Problem::MissingPayee {transaction: transaction.get_id(), payee});
+            }
+        }
+        for category in self.categories.categories() {
+            let id = category.get_id();
+            if let Some(parent) = category.get_parent().filter(|p| self.categories.get_category(p).is_none()) {
+                problems.push(Problem::MissingParent {category: id.clone(), parent: parent.clone()});
+            }
+            // Report each loop once, under whichever of its categories has the first ID
+            if self.categories.is_own_ancestor(id)
+                && self.categories.ancestors(id).iter().all(|a| id.to_string() <= a.to_string()) {
+                problems.push(Problem::ParentCycle {category: id.clone()});
+            }
+        }
+        for schedule in &self.schedules {
+            if let Err(reason) = schedule.get_frequency().check() {
+                problems.push(Problem::InvalidSchedule {schedule: schedule.get_id(), reason});
+            }
+        }
+        problems
+    }
+
+    /// Fix everything `check` finds, returning what was fixed. Transactions in missing categories are left
+    /// uncategorized, categories whose parent is missing or that are under themselves become top-level,
+    /// transactions with a missing payee are matched to one again, and schedules that can't come due are deleted.
+    pub fn repair(&mut self) -> Vec<Problem> {
+        let problems = self.check();
+        for problem in &problems {
+            match problem {
+                Problem::MissingCategory { transaction, category } => {
+                    if let Some(account) = self.accounts.iter_mut().find(|a| a.get_transaction(*transaction).is_some()) {
+                        account.replace_category(category, None);
+                    }
+                },
+                Problem::MissingParent { category, .. } | Problem::ParentCycle { category } => {
+                    self.categories.clear_parent(category)
+                },
+                Problem::MissingPayee { transaction, .. } => {
+                    let transaction = self.accounts.iter_mut()
+                        .flat_map(|a| a.transactions_mut())
+                        .find(|t| t.get_id() == *transaction);
+                    if let Some(transaction) = transaction {
+                        transaction.set_payee(self.payees.match_description(transaction.get_description()));
+                    }
+                },
+                Problem::InvalidSchedule { schedule, .. } => {
+                    self.schedules.retain(|s| s.get_id() != *schedule);
+                },
+            }
+        }
+        problems
+    }
+
+    pub fn get_exchange_rates(&self) -> &ExchangeRates {
+        &self.exchange_rates
+    }
+
+    pub fn get_exchange_rates_mut(&mut self) -> &mut ExchangeRates {
+        &mut self.exchange_rates
+    }
+
+    /// An account's balance at the end of `date`.
+    pub fn balance_at(&self, account: AccountId, date: NaiveDate) -> Result<Money, String> {
+        self.get_account(account)
+            .map(|a| a.balance_at(date))
+            .ok_or_else(|| format!("No account with ID {}", account))
+    }
+
+    /// The account's balance at the end of `date`, converted into `currency` using the exchange rate in effect then.
+    pub fn get_account_total_in(&self, account: &Account, currency: Currency, date: NaiveDate) -> Result<Money, String> {
+        self.exchange_rates.convert(account.balance_at(date), currency, date)
+    }
+
+    /// Income and expenses across every account for the transactions matching `filter`, converted into `currency`
+    /// using the exchange rate in effect on each transaction's date. Split transactions count under each split's
+    /// category, and transfers are left out. Journal entries count by their postings to income and expense
+    /// accounts, where money credited to an income account is income and money debited to an expense account is
+    /// an expense.
+    pub fn get_income_expense_report(&self, currency: Currency, filter: &TransactionFilter) -> Result<IncomeExpenseReport, String> {
+        let mut report = IncomeExpenseReport::new_empty(currency);
+        for account in &self.accounts {
+            if account.get_type() == AccountType::Equity {
+                continue;
+            }
+            let negate = !account.get_type().is_balance_sheet();
+            let transactions = account.get_transactions().iter()
+                .filter(|t| t.get_transfer().is_none() && filter.matches(t))
+                .filter(|t| !(account.get_type().is_balance_sheet() && t.get_entry().is_some()));
+            for transaction in transactions {
+                let mut total = Money::zero(currency);
+                for (category, amount) in transaction.get_category_amounts() {
+                    let amount = if negate { amount.checked_neg().map_err(|e| e.to_string())? } else { amount };
+                    let amount = self.exchange_rates.convert(amount, currency, transaction.get_date())?;
+                    report.add(amount, category)?;
+                    total = total.checked_add(amount).map_err(|e| e.to_string())?;
+                }
+                report.add_payee(total, transaction.get_payee())?;
+            }
+        }
+        report.roll_up(&self.categories)?;
+        Ok(report)
+    }
+
+    /// The sum of the asset and liability account balances at the end of `date`, converted into `currency` using the
+    /// exchange rates in effect then.
+    pub fn get_net_worth(&self, currency: Currency, date: NaiveDate) -> Result<Money, String> {
+        let mut net_worth = Money::zero(currency);
+        for account in self.accounts.iter().filter(|a| a.get_type().is_balance_sheet()) {
+            let total = self.get_account_total_in(account, currency, date)?;
+            net_worth = net_worth.checked_add(total).map_err(|e| e.to_string())?;
+        }
+        Ok(net_worth)
+    }
+
+    /// The balance of every account holding `currency`. When the books balance, the debits and credits are equal.
+    /// Opening balances are left out, since they were carried over from before the books started.
+    pub fn get_trial_balance(&self, currency: Currency) -> Result<TrialBalance, String> {
+        let mut trial_balance = TrialBalance::new_empty(currency);
+        for account in self.accounts.iter().filter(|a| a.get_currency() == currency) {
+            trial_balance.add(account.get_id(), account.get_transaction_total())?;
+        }
+        Ok(trial_balance)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use schedule::{Frequency, ScheduleEnd};
+
     use super::*;
 
     #[test]
@@ -56,16 +1149,107 @@ mod tests {
     #[test]
     fn new_account() {
         let mut ledger = Ledger::new_empty();
-        ledger.add_new_account("My Account".to_string());
+        ledger.add_new_account("My Account".to_string(), Currency::USD).unwrap();
         assert_eq!(ledger.accounts.len(), 1);
         assert_eq!(ledger.accounts[0].get_name(), "My Account");
+        assert_eq!(ledger.accounts[0].get_currency(), Currency::USD);
+    }
+
+    #[test]
+    fn new_account_invalid_names() {
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        assert!(ledger.add_new_account("checking".to_string(), Currency::EUR).is_err());
+        assert!(ledger.add_new_account("".to_string(), Currency::USD).is_err());
+        assert!(ledger.add_new_account(" \t ".to_string(), Currency::USD).is_err());
+        assert!(ledger.add_new_account("Line\nbreak".to_string(), Currency::USD).is_err());
+        assert_eq!(ledger.accounts.len(), 1);
+
+        // Rejected names don't use up an ID
+        let id = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        assert_eq!(id, AccountId::from(2));
+    }
+
+    #[test]
+    fn rename_account() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+
+        ledger.rename_account(checking, "CHECKING".to_string()).unwrap();
+        assert_eq!(ledger.get_account(checking).unwrap().get_name(), "CHECKING");
+        assert!(ledger.rename_account(checking, "savings".to_string()).is_err());
+        assert!(ledger.rename_account(checking, " ".to_string()).is_err());
+        assert!(ledger.rename_account(AccountId::from(100), "Other".to_string()).is_err());
+    }
+
+    #[test]
+    fn close_account() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let savings = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        let id = add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+
+        ledger.close_account(checking).unwrap();
+        assert!(ledger.close_account(checking).is_err());
+        assert!(ledger.get_account(checking).unwrap().is_closed());
+        assert!(ledger.get_transaction(id).is_some());
+        let transaction = Transaction::new(Money::new(100, Currency::USD), time, "Widgets Inc.".to_string(), None);
+        assert!(ledger.add_transaction(checking, transaction).is_err());
+        assert!(ledger.transfer(savings, checking, Money::new(100, Currency::USD), time, "".to_string()).is_err());
+        assert!(ledger.get_account(savings).unwrap().get_transactions().is_empty());
+
+        ledger.reopen_account(checking).unwrap();
+        assert!(ledger.reopen_account(checking).is_err());
+        add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+    }
+
+    #[test]
+    fn delete_account() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let savings = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        let empty = ledger.add_new_account("Empty".to_string(), Currency::USD).unwrap();
+        add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let (_, deposit) = ledger.transfer(checking, savings, Money::new(50, Currency::USD), time, "".to_string()).unwrap();
+
+        ledger.delete_account(empty, false).unwrap();
+        assert!(ledger.get_account(empty).is_none());
+        assert!(ledger.delete_account(checking, false).is_err());
+        let linked: Vec<AccountId> = ledger.get_linked_accounts(checking).iter().map(|a| a.get_id()).collect();
+        assert_eq!(linked, vec![savings]);
+        ledger.delete_account(checking, true).unwrap();
+        assert!(ledger.get_account(checking).is_none());
+        assert!(ledger.get_transaction(deposit).is_none());
+    }
+
+    #[test]
+    fn move_transactions() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let savings = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        let joint = ledger.add_new_account("Joint".to_string(), Currency::USD).unwrap();
+        let girokonto = ledger.add_new_account("Girokonto".to_string(), Currency::EUR).unwrap();
+        let id = add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        ledger.transfer(checking, savings, Money::new(50, Currency::USD), time, "".to_string()).unwrap();
+
+        assert!(ledger.move_transactions(checking, girokonto).is_err());
+        assert!(ledger.move_transactions(checking, savings).is_err());
+        assert!(ledger.move_transactions(checking, checking).is_err());
+        assert_eq!(ledger.move_transactions(checking, joint), Ok(2));
+        assert_eq!(ledger.get_account_for_transaction(id).unwrap().get_id(), joint);
+        assert_eq!(ledger.get_account(joint).unwrap().get_total(), Money::new(50, Currency::USD));
+        assert!(ledger.get_account(checking).unwrap().get_transactions().is_empty());
     }
 
     #[test]
     fn get_account_by_name_success() {
         let mut ledger = Ledger::new_empty();
         let name = "My Account".to_string();
-        ledger.add_new_account(name.clone());
+        ledger.add_new_account(name.clone(), Currency::USD).unwrap();
         assert_eq!(ledger.get_account_by_name_mut(&name).expect("FAILURE").get_name(), &name);
     }
 
@@ -73,13 +1257,805 @@ mod tests {
     fn get_account_by_name_not_found() {
         let mut ledger = Ledger::new_empty();
         let name = "My Account".to_string();
-        ledger.add_new_account(name.clone());
-        assert!(ledger.get_account_by_name_mut(&"INVALID ACCOUNT".to_string()).is_none());
+        ledger.add_new_account(name.clone(), Currency::USD).unwrap();
+        assert!(ledger.get_account_by_name_mut("INVALID ACCOUNT").is_none());
+    }
+
+    fn add_test_transaction(ledger: &mut Ledger, account_name: &str, amount: Money) -> TransactionId {
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let transaction = Transaction::new(amount, time, "Widgets Inc.".to_string(), None);
+        let account_id = ledger.get_account_by_name(account_name).unwrap().get_id();
+        ledger.add_transaction(account_id, transaction).unwrap()
+    }
+
+    #[test]
+    fn account_ids_are_unique() {
+        let mut ledger = Ledger::new_empty();
+        let first = ledger.add_new_account("First".to_string(), Currency::USD).unwrap();
+        let second = ledger.add_new_account("Second".to_string(), Currency::USD).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(ledger.get_account(first).unwrap().get_name(), "First");
+        assert_eq!(ledger.get_account_mut(second).unwrap().get_name(), "Second");
+        assert!(ledger.get_account(AccountId::from(100)).is_none());
+    }
+
+    #[test]
+    fn loads_without_id_counters() {
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+        add_test_transaction(&mut ledger, "Checking", Money::new(200, Currency::USD));
+        ledger.add_payee("Amazon".to_string(), Vec::new()).unwrap();
+
+        // Leave out the fields older files don't have
+        let mut value = ciborium::Value::serialized(&ledger).unwrap();
+        let ciborium::Value::Map(fields) = &mut value else { panic!("Expected a map, got {:?}", value) };
+        fields.retain(|(k, _)| !matches!(k.as_text(), Some("next_account_id" | "next_transaction_id" | "next_payee_id" | "exchange_rates")));
+        let mut loaded: Ledger = value.deserialized().unwrap();
+
+        assert_eq!(loaded.add_new_account("Savings".to_string(), Currency::USD), Ok(AccountId::from(2)));
+        assert_eq!(add_test_transaction(&mut loaded, "Savings", Money::new(300, Currency::USD)), TransactionId::from(3));
+        let transfer = loaded.transfer(
+            AccountId::from(1), AccountId::from(2), Money::new(50, Currency::USD), chrono::Utc::now(), "".to_string());
+        assert_eq!(transfer, Ok((TransactionId::from(4), TransactionId::from(5))));
+        assert_eq!(loaded.add_payee("Costco".to_string(), Vec::new()), Ok(PayeeId::from(2)));
+    }
+
+    #[test]
+    fn add_transaction_assigns_ids() {
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        let first = add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+        let second = add_test_transaction(&mut ledger, "Savings", Money::new(200, Currency::USD));
+        assert_ne!(first, second);
+
+        assert_eq!(ledger.get_transaction(second).unwrap().get_id(), second);
+        assert_eq!(ledger.get_transaction(second).unwrap().get_amount(), &Money::new(200, Currency::USD));
+        assert_eq!(ledger.get_account_for_transaction(first).unwrap().get_name(), "Checking");
+        assert!(ledger.get_transaction(TransactionId::from(100)).is_none());
+    }
+
+    #[test]
+    fn update_transaction() {
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        ledger.get_transaction_categories_mut().create_category("food".to_string()).unwrap();
+        let id = add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+
+        let update = TransactionUpdate {
+            amount: Some(Money::new(-2500, Currency::USD)),
+            description: Some("Groceries".to_string()),
+            category: Some(Some(category::CategoryId::from("food".to_string()))),
+            ..Default::default()
+        };
+        ledger.update_transaction(id, update).unwrap();
+        assert_eq!(ledger.get_transaction(id).unwrap().get_amount(), &Money::new(-2500, Currency::USD));
+        assert_eq!(ledger.get_account_by_name("Checking").unwrap().get_total(), Money::new(-2500, Currency::USD));
+    }
+
+    #[test]
+    fn update_transaction_invalid() {
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let id = add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+
+        let bad_category = TransactionUpdate {
+            category: Some(Some(category::CategoryId::from("nope".to_string()))),
+            ..Default::default()
+        };
+        assert!(ledger.update_transaction(id, bad_category).is_err());
+        assert!(ledger.update_transaction(TransactionId::from(100), TransactionUpdate::default()).is_err());
+    }
+
+    #[test]
+    fn delete_transaction() {
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let first = add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+        let second = add_test_transaction(&mut ledger, "Checking", Money::new(200, Currency::USD));
+
+        assert_eq!(ledger.delete_transaction(first).unwrap().get_id(), first);
+        assert!(ledger.get_transaction(first).is_none());
+        assert!(ledger.delete_transaction(first).is_err());
+        assert_eq!(ledger.get_account_by_name("Checking").unwrap().get_total(), Money::new(200, Currency::USD));
+        assert!(ledger.get_transaction(second).is_some());
+    }
+
+    #[test]
+    fn add_transaction_failure_does_not_use_id() {
+        let mut ledger = Ledger::new_empty();
+        let account = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let transaction = Transaction::new(Money::new(100, Currency::EUR), time, "Widgets Inc.".to_string(), None);
+        assert!(ledger.add_transaction(account, transaction).is_err());
+        assert!(ledger.add_transaction(AccountId::from(100), Transaction::new(
+            Money::new(100, Currency::USD), time, "Widgets Inc.".to_string(), None)).is_err());
+
+        let id = add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+        assert_eq!(id, TransactionId::from(1));
+    }
+
+    #[test]
+    fn net_worth_across_currencies() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        ledger.add_new_account("Girokonto".to_string(), Currency::EUR).unwrap();
+        add_test_transaction(&mut ledger, "Checking", Money::new(10000, Currency::USD));
+        add_test_transaction(&mut ledger, "Girokonto", Money::new(5000, Currency::EUR));
+        ledger.get_exchange_rates_mut().set_rate(Currency::EUR, Currency::USD, date, 1.1).unwrap();
+
+        assert_eq!(ledger.get_net_worth(Currency::USD, date), Ok(Money::new(15500, Currency::USD)));
+        assert!(ledger.get_net_worth(Currency::GBP, date).is_err());
+
+        // Transactions after the date don't count
+        let checking = ledger.get_account_by_name("Checking").unwrap().get_id();
+        let later = Transaction::new_on_date(Money::new(2500, Currency::USD), date + Days::new(1), "".to_string(), None);
+        ledger.add_transaction(checking, later).unwrap();
+        assert_eq!(ledger.get_net_worth(Currency::USD, date), Ok(Money::new(15500, Currency::USD)));
+        assert_eq!(ledger.get_net_worth(Currency::USD, date + Days::new(1)), Ok(Money::new(18000, Currency::USD)));
+    }
+
+    #[test]
+    fn net_worth_overflow() {
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        add_test_transaction(&mut ledger, "Checking", Money::new(i64::MAX, Currency::USD));
+        add_test_transaction(&mut ledger, "Savings", Money::new(i64::MAX, Currency::USD));
+        assert!(ledger.get_net_worth(Currency::USD, NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()).is_err());
+    }
+
+    #[test]
+    fn transfer() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let savings = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+
+        let (withdrawal, deposit) = ledger.transfer(
+            checking, savings, Money::new(50000, Currency::USD), time, "Saving up".to_string()).unwrap();
+        assert_eq!(ledger.get_account(checking).unwrap().get_total(), Money::new(-50000, Currency::USD));
+        assert_eq!(ledger.get_account(savings).unwrap().get_total(), Money::new(50000, Currency::USD));
+        assert_eq!(ledger.get_transaction(withdrawal).unwrap().get_transfer(), Some(deposit));
+        assert_eq!(ledger.get_transaction(deposit).unwrap().get_transfer(), Some(withdrawal));
+
+        let update = TransactionUpdate {
+            amount: Some(Money::new(-20000, Currency::USD)),
+            description: Some("Saving a bit".to_string()),
+            ..Default::default()
+        };
+        ledger.update_transaction(withdrawal, update).unwrap();
+        assert_eq!(ledger.get_transaction(deposit).unwrap().get_amount(), &Money::new(20000, Currency::USD));
+        assert_eq!(ledger.get_transaction(deposit).unwrap().get_description(), "Saving a bit");
+
+        ledger.delete_transaction(deposit).unwrap();
+        assert!(ledger.get_transaction(withdrawal).is_none());
+        assert!(ledger.get_account(checking).unwrap().get_transactions().is_empty());
+    }
+
+    #[test]
+    fn transfer_invalid() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let girokonto = ledger.add_new_account("Girokonto".to_string(), Currency::EUR).unwrap();
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let amount = Money::new(100, Currency::USD);
+
+        assert!(ledger.transfer(checking, checking, amount, time, "".to_string()).is_err());
+        assert!(ledger.transfer(checking, girokonto, amount, time, "".to_string()).is_err());
+        assert!(ledger.transfer(checking, AccountId::from(100), amount, time, "".to_string()).is_err());
+        assert!(ledger.transfer(girokonto, checking, Money::new(-100, Currency::USD), time, "".to_string()).is_err());
+        assert!(ledger.get_account(checking).unwrap().get_transactions().is_empty());
+    }
+
+    #[test]
+    fn transfer_is_all_or_nothing() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let savings = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        add_test_transaction(&mut ledger, "Savings", Money::new(i64::MAX, Currency::USD));
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+
+        // The deposit doesn't fit, so the withdrawal isn't made either
+        assert!(ledger.transfer(checking, savings, Money::new(100, Currency::USD), time, "".to_string()).is_err());
+        assert!(ledger.get_account(checking).unwrap().get_transactions().is_empty());
+        assert_eq!(ledger.get_account(savings).unwrap().get_transactions().len(), 1);
+    }
+
+    #[test]
+    fn income_expense_report_skips_transfers() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let savings = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        add_test_transaction(&mut ledger, "Checking", Money::new(100000, Currency::USD));
+        add_test_transaction(&mut ledger, "Checking", Money::new(-2500, Currency::USD));
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        ledger.transfer(checking, savings, Money::new(50000, Currency::USD), time, "Saving up".to_string()).unwrap();
+
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let food = ledger.get_transaction_categories_mut().create_category("food".to_string()).unwrap();
+        let costco = Transaction::new(Money::new(-10000, Currency::USD), time, "Costco".to_string(), None)
+            .with_splits(vec![
+                transaction::Split::new(Money::new(-6000, Currency::USD), Some(food.clone()), String::new()),
+                transaction::Split::new(Money::new(-4000, Currency::USD), None, "Batteries".to_string()),
+            ])
+            .unwrap();
+        ledger.add_transaction(checking, costco).unwrap();
+
+        let report = ledger.get_income_expense_report(Currency::USD, &TransactionFilter::default()).unwrap();
+        assert_eq!(report.get_income(), Money::new(100000, Currency::USD));
+        assert_eq!(report.get_expenses(), Money::new(12500, Currency::USD));
+        assert_eq!(report.get_categories(), &vec![
+            (None, Money::new(100000 - 2500 - 4000, Currency::USD)),
+            (Some(food), Money::new(-6000, Currency::USD)),
+        ]);
+        assert!(ledger.get_income_expense_report(Currency::EUR, &TransactionFilter::default()).is_err());
+    }
+
+    #[test]
+    fn add_transaction_checks_categories() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let food = ledger.get_transaction_categories_mut().create_category("food".to_string()).unwrap();
+        let missing = category::CategoryId::from("missing".to_string());
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+
+        let uncategorized = Transaction::new(Money::new(-100, Currency::USD), time, "".to_string(), Some(missing.clone()));
+        assert!(ledger.add_transaction(checking, uncategorized).is_err());
+
+        let split = Transaction::new(Money::new(-100, Currency::USD), time, "".to_string(), None)
+            .with_splits(vec![
+                transaction::Split::new(Money::new(-60, Currency::USD), Some(food), String::new()),
+                transaction::Split::new(Money::new(-40, Currency::USD), Some(missing), String::new()),
+            ])
+            .unwrap();
+        assert!(ledger.add_transaction(checking, split).is_err());
+        assert!(ledger.get_account(checking).unwrap().get_transactions().is_empty());
+    }
+
+    fn posting(account: AccountId, amount: i64) -> Posting {
+        Posting {account, amount: Money::new(amount, Currency::USD), category: None}
+    }
+
+    #[test]
+    fn journal_entries() {
+        let mut ledger = Ledger::new_empty();
+        ledger.set_double_entry(true);
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let salary = ledger.add_new_account_of_type("Salary".to_string(), Currency::USD, AccountType::Income).unwrap();
+        let taxes = ledger.add_new_account_of_type("Taxes".to_string(), Currency::USD, AccountType::Expense).unwrap();
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+
+        let entry = ledger.add_journal_entry(time, "Paycheck".to_string(), vec![
+            posting(checking, 80000),
+            posting(taxes, 20000),
+            posting(salary, -100000),
+        ]).unwrap();
+        assert_eq!(ledger.get_journal_entry(entry).len(), 3);
+        assert_eq!(ledger.get_account(checking).unwrap().get_total(), Money::new(80000, Currency::USD));
+        assert_eq!(ledger.get_account(salary).unwrap().get_total(), Money::new(-100000, Currency::USD));
+
+        let trial_balance = ledger.get_trial_balance(Currency::USD).unwrap();
+        assert!(trial_balance.is_balanced());
+        assert_eq!(trial_balance.get_debits(), Money::new(100000, Currency::USD));
+
+        let report = ledger.get_income_expense_report(Currency::USD, &TransactionFilter::default()).unwrap();
+        assert_eq!(report.get_income(), Money::new(100000, Currency::USD));
+        assert_eq!(report.get_expenses(), Money::new(20000, Currency::USD));
+        assert_eq!(ledger.get_net_worth(Currency::USD, time.date_naive()), Ok(Money::new(80000, Currency::USD)));
+
+        // Single transactions would unbalance the books
+        let transaction = Transaction::new(Money::new(100, Currency::USD), time, "Widgets Inc.".to_string(), None);
+        assert!(ledger.add_transaction(checking, transaction).is_err());
+        assert!(ledger.add_journal_entry(time, "Oops".to_string(), vec![posting(checking, 100), posting(salary, -99)]).is_err());
+        assert!(ledger.add_journal_entry(time, "Oops".to_string(), vec![posting(checking, 100)]).is_err());
+    }
+
+    #[test]
+    fn journal_entry_is_all_or_nothing() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let salary = ledger.add_new_account_of_type("Salary".to_string(), Currency::USD, AccountType::Income).unwrap();
+        let savings = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        add_test_transaction(&mut ledger, "Salary", Money::new(i64::MIN, Currency::USD));
+        ledger.close_account(savings).unwrap();
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+
+        // A later posting that doesn't fit, or goes to a closed account, stops the earlier ones too
+        assert!(ledger.add_journal_entry(time, "Paycheck".to_string(), vec![posting(checking, 100), posting(salary, -100)]).is_err());
+        assert!(ledger.add_journal_entry(time, "Paycheck".to_string(), vec![posting(checking, 100), posting(savings, -100)]).is_err());
+        assert!(ledger.get_account(checking).unwrap().get_transactions().is_empty());
+        assert_eq!(ledger.get_account(salary).unwrap().get_transactions().len(), 1);
+    }
+
+    #[test]
+    fn edit_and_delete_journal_entries() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let salary = ledger.add_new_account_of_type("Salary".to_string(), Currency::USD, AccountType::Income).unwrap();
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let entry = ledger.add_journal_entry(time, "Paycheck".to_string(), vec![
+            posting(checking, 100000),
+            posting(salary, -100000),
+        ]).unwrap();
+        let postings: Vec<TransactionId> = ledger.get_journal_entry(entry).iter().map(|t| t.get_id()).collect();
+
+        let amount = TransactionUpdate {amount: Some(Money::new(50000, Currency::USD)), ..Default::default()};
+        assert!(ledger.update_transaction(postings[0], amount).is_err());
+        let description = TransactionUpdate {description: Some("Bonus".to_string()), ..Default::default()};
+        ledger.update_transaction(postings[0], description).unwrap();
+        assert_eq!(ledger.get_transaction(postings[1]).unwrap().get_description(), "Bonus");
+
+        ledger.delete_transaction(postings[1]).unwrap();
+        assert!(ledger.get_journal_entry(entry).is_empty());
+        assert!(ledger.get_trial_balance(Currency::USD).unwrap().is_balanced());
+    }
+
+    #[test]
+    fn balance_at() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+
+        let before = NaiveDate::from_ymd_opt(1999, 12, 31).unwrap();
+        let after = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        assert_eq!(ledger.balance_at(checking, before), Ok(Money::zero(Currency::USD)));
+        assert_eq!(ledger.balance_at(checking, after), Ok(Money::new(100, Currency::USD)));
+        assert!(ledger.balance_at(AccountId::from(100), after).is_err());
+    }
+
+    #[test]
+    fn reconcile() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let savings = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        let paycheck = add_test_transaction(&mut ledger, "Checking", Money::new(10000, Currency::USD));
+        let groceries = add_test_transaction(&mut ledger, "Checking", Money::new(-2500, Currency::USD));
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let (withdrawal, _) = ledger.transfer(savings, checking, Money::new(500, Currency::USD), time, "".to_string()).unwrap();
+        let statement_date = NaiveDate::from_ymd_opt(2000, 1, 31).unwrap();
+
+        ledger.set_transaction_status(paycheck, TransactionStatus::Cleared).unwrap();
+        assert!(ledger.reconcile(checking, statement_date, Money::new(7500, Currency::USD)).is_err());
+        assert!(ledger.set_transaction_status(groceries, TransactionStatus::Reconciled).is_err());
+        ledger.set_transaction_status(groceries, TransactionStatus::Cleared).unwrap();
+        assert!(ledger.reconcile(checking, statement_date, Money::new(7500, Currency::EUR)).is_err());
+        assert_eq!(ledger.reconcile(checking, statement_date, Money::new(7500, Currency::USD)), Ok(2));
+
+        let account = ledger.get_account(checking).unwrap();
+        assert_eq!(account.get_last_reconciled(), Some((statement_date, Money::new(7500, Currency::USD))));
+        assert_eq!(ledger.get_transaction(paycheck).unwrap().get_status(), TransactionStatus::Reconciled);
+        assert_eq!(ledger.get_transaction(withdrawal).unwrap().get_status(), TransactionStatus::Pending);
+
+        // Reconciled transactions are locked until they're explicitly unlocked
+        let update = TransactionUpdate {description: Some("Typo".to_string()), ..Default::default()};
+        assert!(ledger.update_transaction(paycheck, update.clone()).is_err());
+        assert!(ledger.delete_transaction(groceries).is_err());
+        assert!(ledger.delete_account(checking, true).is_err());
+        assert!(ledger.move_transactions(checking, savings).is_err());
+        ledger.set_transaction_status(paycheck, TransactionStatus::Pending).unwrap();
+        assert!(ledger.update_transaction(paycheck, update).is_ok());
+    }
+
+    #[test]
+    fn reconciled_transfers_lock_both_halves() {
+        let mut ledger = Ledger::new_empty();
+        let card = ledger.add_new_account_of_type("Visa".to_string(), Currency::USD, AccountType::CreditCard).unwrap();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let (payment, deposit) = ledger.transfer(checking, card, Money::new(5000, Currency::USD), time, "".to_string()).unwrap();
+        ledger.set_transaction_status(deposit, TransactionStatus::Cleared).unwrap();
+
+        // The card statement shows what's owed, which is negative here since the card was overpaid
+        let statement_date = NaiveDate::from_ymd_opt(2000, 1, 31).unwrap();
+        assert_eq!(ledger.reconcile(card, statement_date, Money::new(-5000, Currency::USD)), Ok(1));
+        assert!(ledger.delete_transaction(payment).is_err());
+    }
+
+    fn add_categorized_transaction(ledger: &mut Ledger, account: AccountId, category: &CategoryId) -> TransactionId {
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let transaction = Transaction::new(Money::new(-100, Currency::USD), time, "Widgets Inc.".to_string(), Some(category.clone()));
+        ledger.add_transaction(account, transaction).unwrap()
+    }
+
+    #[test]
+    fn merge_categories() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let groceries = ledger.get_transaction_categories_mut().create_category("Food:Groceries".to_string()).unwrap();
+        let supermarket = ledger.get_transaction_categories_mut().create_category("Supermarket".to_string()).unwrap();
+        let food = ledger.get_transaction_categories().find_by_name("Food").unwrap().get_id().clone();
+        let id = add_categorized_transaction(&mut ledger, checking, &supermarket);
+        add_categorized_transaction(&mut ledger, checking, &supermarket);
+
+        assert!(ledger.merge_categories(&supermarket, &supermarket).is_err());
+        assert!(ledger.merge_categories(&food, &supermarket).is_err());
+        assert_eq!(ledger.merge_categories(&supermarket, &groceries), Ok(2));
+        assert_eq!(ledger.get_transaction(id).unwrap().get_category(), Some(&groceries));
+        assert!(ledger.get_transaction_categories().get_category(&supermarket).is_none());
+        assert!(ledger.check().is_empty());
+    }
+
+    #[test]
+    fn delete_category() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let food = ledger.get_transaction_categories_mut().create_category("Food".to_string()).unwrap();
+        let rent = ledger.get_transaction_categories_mut().create_category("Rent".to_string()).unwrap();
+        let id = add_categorized_transaction(&mut ledger, checking, &food);
+
+        assert_eq!(ledger.delete_category(&rent, false), Ok(0));
+        assert!(ledger.delete_category(&food, false).is_err());
+        assert_eq!(ledger.delete_category(&food, true), Ok(1));
+        assert_eq!(ledger.get_transaction(id).unwrap().get_category(), None);
+        assert!(ledger.delete_category(&food, true).is_err());
+    }
+
+    #[test]
+    fn check_and_repair() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let groceries = ledger.get_transaction_categories_mut().create_category("Food:Groceries".to_string()).unwrap();
+        let food = ledger.get_transaction_categories().find_by_name("Food").unwrap().get_id().clone();
+        let id = add_categorized_transaction(&mut ledger, checking, &food);
+        assert!(ledger.check().is_empty());
+
+        // Go around the ledger's own checks, like an old or hand-edited file might
+        ledger.categories.remove_category(&food);
+        let problems = ledger.check();
+        assert_eq!(problems.len(), 2);
+        assert!(problems.contains(&Problem::MissingCategory {transaction: id, category: food.clone()}));
+        assert!(problems.contains(&Problem::MissingParent {category: groceries.clone(), parent: food.clone()}));
+
+        assert_eq!(ledger.repair().len(), 2);
+        assert!(ledger.check().is_empty());
+        assert_eq!(ledger.get_transaction(id).unwrap().get_category(), None);
+        assert_eq!(ledger.get_transaction_categories().get_category(&groceries).unwrap().get_parent(), None);
+    }
+
+    #[test]
+    fn check_reports_each_missing_category_once() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let food = ledger.get_transaction_categories_mut().create_category("Food".to_string()).unwrap();
+        let drinks = ledger.get_transaction_categories_mut().create_category("Drinks".to_string()).unwrap();
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let split = |amount, category: &CategoryId| transaction::Split::new(Money::new(amount, Currency::USD), Some(category.clone()), String::new());
+        let transaction = Transaction::new(Money::new(-300, Currency::USD), time, "".to_string(), None)
+            .with_splits(vec![split(-100, &food), split(-100, &drinks), split(-100, &food)])
+            .unwrap();
+        let id = ledger.add_transaction(checking, transaction).unwrap();
+
+        ledger.categories.remove_category(&food);
+        ledger.categories.remove_category(&drinks);
+        let problems = ledger.check();
+        assert_eq!(problems.len(), 2);
+        assert!(problems.contains(&Problem::MissingCategory {transaction: id, category: food}));
+        assert!(problems.contains(&Problem::MissingCategory {transaction: id, category: drinks}));
+    }
+
+    #[test]
+    fn check_and_repair_parent_cycle() {
+        let mut ledger = Ledger::new_empty();
+        let groceries = ledger.get_transaction_categories_mut().create_category("Food:Groceries".to_string()).unwrap();
+        let food = ledger.get_transaction_categories().find_by_name("Food").unwrap().get_id().clone();
+        ledger.categories.set_parent(&food, groceries.clone());
+
+        assert_eq!(ledger.check(), vec![Problem::ParentCycle {category: food.clone()}]);
+        assert_eq!(ledger.repair().len(), 1);
+        assert!(ledger.check().is_empty());
+        assert_eq!(ledger.get_transaction_categories().get_category(&food).unwrap().get_parent(), None);
+        assert_eq!(ledger.get_transaction_categories().get_category(&groceries).unwrap().get_parent(), Some(&food));
+    }
+
+    #[test]
+    fn check_and_repair_invalid_schedule() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let frequency = Frequency::NthWeekday { n: 0, weekday: chrono::Weekday::Mon };
+        let schedule = Schedule::new(checking, Money::new(-100, Currency::USD), "".to_string(), None, frequency, date);
+        assert!(ledger.add_schedule(schedule.clone()).is_err());
+
+        // Like a damaged file might have
+        let mut schedule = schedule;
+        schedule.set_id(ScheduleId::from(1));
+        ledger.schedules.push(schedule);
+        assert!(ledger.get_due_schedules(date).is_empty());
+        assert!(matches!(ledger.check()[..], [Problem::InvalidSchedule { .. }]));
+        assert_eq!(ledger.repair().len(), 1);
+        assert!(ledger.check().is_empty());
+        assert!(ledger.get_schedules().is_empty());
+    }
+
+    #[test]
+    fn rename_and_remove_tags() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let mut ids = Vec::new();
+        for tags in [vec!["vacation"], vec!["vacation", "trip"], vec!["gift"]] {
+            let transaction = Transaction::new(Money::new(-100, Currency::USD), time, "".to_string(), None).with_tags(&tags).unwrap();
+            ids.push(ledger.add_transaction(checking, transaction).unwrap());
+        }
+
+        assert_eq!(ledger.rename_tag("#Vacation", "trip"), Ok(2));
+        assert_eq!(ledger.get_transaction(ids[1]).unwrap().get_tags().len(), 1);
+        assert!(ledger.rename_tag("trip", "road trip").is_err());
+        assert_eq!(ledger.remove_tag("gift"), Ok(1));
+        assert_eq!(ledger.get_tags().into_iter().collect::<Vec<_>>(), vec![(&"trip".to_string(), 2)]);
+    }
+
+    #[test]
+    fn payees() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let mut ids = Vec::new();
+        for (amount, description) in [(-1000, "AMZN Mktp US*2K3"), (-500, "Amazon.com"), (-200, "amazon"), (-300, "Corner store")] {
+            let transaction = Transaction::new(Money::new(amount, Currency::USD), time, description.to_string(), None);
+            ids.push(ledger.add_transaction(checking, transaction).unwrap());
+        }
+
+        let amazon = ledger.add_payee("Amazon".to_string(), vec!["amzn mktp*".to_string()]).unwrap();
+        let amazon_com = ledger.add_payee("Amazon.com".to_string(), Vec::new()).unwrap();
+        assert_eq!(ledger.get_transaction(ids[0]).unwrap().get_payee(), Some(amazon));
+        assert_eq!(ledger.get_transaction(ids[1]).unwrap().get_payee(), Some(amazon_com));
+        assert_eq!(ledger.get_transaction(ids[2]).unwrap().get_payee(), Some(amazon));
+        assert_eq!(ledger.get_transaction(ids[3]).unwrap().get_payee(), None);
+
+        let store = ledger.add_payee("Corner Store".to_string(), Vec::new()).unwrap();
+        assert_eq!(ledger.get_transaction(ids[3]).unwrap().get_payee(), Some(store));
+        assert_eq!(ledger.merge_payees(amazon_com, amazon), Ok(1));
+        let transaction = Transaction::new(Money::new(-100, Currency::USD), time, "AMAZON.COM".to_string(), None);
+        let id = ledger.add_transaction(checking, transaction).unwrap();
+        assert_eq!(ledger.get_transaction(id).unwrap().get_payee(), Some(amazon));
+
+        let update = TransactionUpdate {description: Some("Market stall".to_string()), ..Default::default()};
+        ledger.update_transaction(id, update).unwrap();
+        assert_eq!(ledger.get_transaction(id).unwrap().get_payee(), None);
+        assert_eq!(ledger.add_payee_alias(store, "market*".to_string()), Ok(1));
+
+        let report = ledger.get_income_expense_report(Currency::USD, &TransactionFilter::default()).unwrap();
+        assert_eq!(report.get_payees(), &vec![
+            (Some(amazon), Money::new(-1700, Currency::USD)),
+            (Some(store), Money::new(-400, Currency::USD)),
+        ]);
+    }
+
+    #[test]
+    fn repair_missing_payee() {
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let time = "2000-1-1T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().expect("Failed to parse");
+        let transaction = Transaction::new(Money::new(-100, Currency::USD), time, "Amazon".to_string(), None);
+        let id = ledger.add_transaction(checking, transaction).unwrap();
+        ledger.accounts[0].transactions_mut().for_each(|t| t.set_payee(Some(PayeeId::from(7))));
+
+        assert_eq!(ledger.check(), vec![Problem::MissingPayee {transaction: id, payee: PayeeId::from(7)}]);
+        let amazon = ledger.add_payee("Amazon".to_string(), Vec::new()).unwrap();
+        assert_eq!(ledger.repair().len(), 1);
+        assert_eq!(ledger.get_transaction(id).unwrap().get_payee(), Some(amazon));
+        assert!(ledger.check().is_empty());
+    }
+
+    #[test]
+    fn post_due_schedules() {
+        let date = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let rent = ledger.get_transaction_categories_mut().create_category("Rent".to_string()).unwrap();
+        let schedule = Schedule::new(
+            checking, Money::new(-50000, Currency::USD), "Rent".to_string(), Some(rent.clone()), Frequency::Weekly, date(1));
+        let id = ledger.add_schedule(schedule).unwrap();
+        assert!(ledger.add_schedule(Schedule::new(
+            checking, Money::new(100, Currency::EUR), "".to_string(), None, Frequency::Daily, date(1))).is_err());
+
+        assert_eq!(ledger.skip_schedule(id), Ok(date(1)));
+        assert_eq!(ledger.get_due_schedules(date(16)), vec![(id, date(8)), (id, date(15))]);
+        let update = ScheduleUpdate {amount: Some(Money::new(-60000, Currency::USD)), ..Default::default()};
+        ledger.update_schedule(id, update).unwrap();
+        let posted = ledger.post_due_schedules(date(16)).posted;
+        assert_eq!(posted.len(), 2);
+        let transaction = ledger.get_transaction(posted[1]).unwrap();
+        assert_eq!(transaction.get_date(), date(15));
+        assert_eq!(transaction.get_amount(), &Money::new(-60000, Currency::USD));
+        assert_eq!(transaction.get_category(), Some(&rent));
+        assert_eq!(ledger.post_due_schedules(date(16)), PostedSchedules::default());
+        assert_eq!(ledger.get_schedule(id).unwrap().get_next_due(), Some(date(22)));
+
+        assert!(ledger.delete_category(&rent, false).is_err());
+        ledger.delete_category(&rent, true).unwrap();
+        assert_eq!(ledger.get_schedule(id).unwrap().get_category(), None);
+        assert!(ledger.delete_account(checking, false).is_err());
+        ledger.delete_account(checking, true).unwrap();
+        assert!(ledger.get_schedules().is_empty());
+    }
+
+    #[test]
+    fn schedules_block_closing_and_skip_failures() {
+        let date = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let savings = ledger.add_new_account("Savings".to_string(), Currency::USD).unwrap();
+        let rent = ledger.add_schedule(Schedule::new(
+            checking, Money::new(-50000, Currency::USD), "Rent".to_string(), None, Frequency::Weekly, date(1))).unwrap();
+        let interest = Schedule::new(savings, Money::new(100, Currency::USD), "Interest".to_string(), None, Frequency::Weekly, date(1));
+        let interest = ledger.add_schedule(interest.with_end(ScheduleEnd::After(1))).unwrap();
+
+        assert!(ledger.close_account(checking).is_err());
+        assert!(!ledger.get_account(checking).unwrap().is_closed());
+
+        // Double-entry mode refuses single transactions, so every occurrence is skipped and stays due
+        ledger.set_double_entry(true);
+        let result = ledger.post_due_schedules(date(8));
+        assert!(result.posted.is_empty());
+        let skipped: Vec<_> = result.skipped.iter().map(|(id, date, _)| (*id, *date)).collect();
+        assert_eq!(skipped, vec![(rent, date(1)), (interest, date(1)), (rent, date(8))]);
+        assert_eq!(ledger.get_due_schedules(date(8)).len(), 3);
+
+        ledger.set_double_entry(false);
+        assert_eq!(ledger.post_due_schedules(date(8)).posted.len(), 3);
+        // The interest schedule has ended, so its account can be closed
+        ledger.close_account(savings).unwrap();
+    }
+
+    #[test]
+    fn budgets() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        let usd = |amount| Money::new(amount, Currency::USD);
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let food = ledger.get_transaction_categories_mut().create_category("Food".to_string()).unwrap();
+        let groceries = ledger.get_transaction_categories_mut().create_category("Food:Groceries".to_string()).unwrap();
+        for (month, amount, category) in [(1, -30000, &groceries), (1, -5000, &food), (2, -45000, &groceries), (3, 1000, &groceries)] {
+            let transaction = Transaction::new_on_date(usd(amount), date(month, 10), "".to_string(), Some(category.clone()));
+            ledger.add_transaction(checking, transaction).unwrap();
+        }
+        // Spending in other currencies doesn't count, so it doesn't need an exchange rate
+        let euros = ledger.add_new_account("Euros".to_string(), Currency::EUR).unwrap();
+        let transaction = Transaction::new_on_date(Money::new(-9900, Currency::EUR), date(2, 10), "".to_string(), Some(food.clone()));
+        ledger.add_transaction(euros, transaction).unwrap();
+
+        ledger.set_budget(&food, date(1, 1), usd(40000)).unwrap();
+        ledger.set_budget(&groceries, date(2, 1), usd(40000)).unwrap();
+        assert!(ledger.set_budget(&CategoryId::from("rent".to_string()), date(1, 1), usd(100)).is_err());
+        let report = ledger.get_budget_report(date(2, 20)).unwrap();
+        assert_eq!(report, vec![
+            BudgetLine {category: food.clone(), budgeted: usd(40000), carried: usd(0), spent: usd(45000)},
+            BudgetLine {category: groceries.clone(), budgeted: usd(40000), carried: usd(0), spent: usd(45000)},
+        ]);
+        assert!(report[0].is_overspent());
+
+        // January left $50 for food, then February went $50 over
+        ledger.set_budget_rollover(&food, true).unwrap();
+        ledger.set_budget(&food, date(3, 1), usd(20000)).unwrap();
+        let report = ledger.get_budget_report(date(3, 1)).unwrap();
+        assert_eq!(report[0], BudgetLine {category: food.clone(), budgeted: usd(20000), carried: usd(0), spent: usd(-1000)});
+        assert_eq!(report[0].get_remaining(), usd(21000));
+        assert!(ledger.get_budget_report(date(1, 1)).unwrap().iter().all(|line| line.category == food));
+
+        // A category added later can be budgeted like any other
+        let rent = ledger.get_transaction_categories_mut().create_category("Rent".to_string()).unwrap();
+        ledger.set_budget(&rent, date(3, 1), usd(100000)).unwrap();
+        assert_eq!(ledger.get_budget_report(date(3, 1)).unwrap().len(), 3);
+        ledger.merge_categories(&rent, &food).unwrap();
+        assert!(ledger.get_budgets().get_budget(&rent).is_none());
+        assert!(ledger.remove_budget(&rent).is_err());
+    }
+
+    #[test]
+    fn envelopes() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        let usd = |amount| Money::new(amount, Currency::USD);
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        let food = ledger.get_transaction_categories_mut().create_category("Food".to_string()).unwrap();
+        let rent = ledger.get_transaction_categories_mut().create_category("Rent".to_string()).unwrap();
+        ledger.get_transaction_categories_mut().create_category("Fun".to_string()).unwrap();
+        for (month, amount, category) in [(1, 300000, None), (1, -20000, Some(&food)), (2, -30000, Some(&food)), (2, 2000, Some(&food))] {
+            let transaction = Transaction::new_on_date(usd(amount), date(month, 5), "".to_string(), category.cloned());
+            ledger.add_transaction(checking, transaction).unwrap();
+        }
+        let euros = ledger.add_new_account("Euros".to_string(), Currency::EUR).unwrap();
+        let transaction = Transaction::new_on_date(Money::new(-9900, Currency::EUR), date(2, 10), "".to_string(), Some(food.clone()));
+        ledger.add_transaction(euros, transaction).unwrap();
+
+        assert!(ledger.assign_to_envelope(&food, date(1, 1), usd(40000)).is_err());
+        assert!(ledger.get_envelope_report(date(1, 1)).is_err());
+        ledger.set_envelope_mode(Some(Currency::USD)).unwrap();
+        ledger.assign_to_envelope(&food, date(1, 1), usd(40000)).unwrap();
+        ledger.assign_to_envelope(&rent, date(1, 1), usd(150000)).unwrap();
+        ledger.move_between_envelopes(&rent, &food, date(2, 1), usd(10000)).unwrap();
+        assert!(ledger.move_between_envelopes(&rent, &food, date(2, 1), usd(-100)).is_err());
+        assert!(ledger.move_between_envelopes(&rent, &rent, date(2, 1), usd(100)).is_err());
+
+        let report = ledger.get_envelope_report(date(2, 28)).unwrap();
+        assert_eq!(report.ready_to_assign, usd(110000));
+        assert_eq!(report.envelopes, vec![
+            EnvelopeLine {category: food.clone(), assigned: usd(10000), activity: usd(-28000), available: usd(2000)},
+            EnvelopeLine {category: rent.clone(), assigned: usd(-10000), activity: usd(0), available: usd(140000)},
+        ]);
+
+        // Assigning everything that's left brings ready to assign to zero
+        ledger.assign_to_envelope(&rent, date(2, 1), usd(110000)).unwrap();
+        assert!(ledger.get_envelope_report(date(2, 1)).unwrap().ready_to_assign.is_zero());
+        ledger.merge_categories(&rent, &food).unwrap();
+        let report = ledger.get_envelope_report(date(2, 1)).unwrap();
+        assert_eq!(report.envelopes.len(), 1);
+        assert_eq!(report.envelopes[0].available, usd(252000));
+    }
+
+    #[test]
+    fn opening_balances_are_ready_to_assign() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        let usd = |amount| Money::new(amount, Currency::USD);
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account_with_opening_balance(
+            "Checking".to_string(), Currency::USD, AccountType::Checking, usd(500000), date(1, 1)).unwrap();
+        ledger.add_new_account_with_opening_balance(
+            "Visa".to_string(), Currency::USD, AccountType::CreditCard, usd(20000), date(1, 1)).unwrap();
+        ledger.add_new_account_with_opening_balance(
+            "Savings".to_string(), Currency::USD, AccountType::Savings, usd(100000), date(2, 1)).unwrap();
+        ledger.add_new_account_with_opening_balance(
+            "Euros".to_string(), Currency::EUR, AccountType::Checking, Money::new(30000, Currency::EUR), date(1, 1)).unwrap();
+        let food = ledger.get_transaction_categories_mut().create_category("Food".to_string()).unwrap();
+        ledger.set_envelope_mode(Some(Currency::USD)).unwrap();
+        ledger.assign_to_envelope(&food, date(1, 1), usd(40000)).unwrap();
+
+        // What's owed on the card comes out, and savings don't count until they were opened
+        assert_eq!(ledger.get_envelope_report(date(1, 1)).unwrap().ready_to_assign, usd(440000));
+        assert_eq!(ledger.get_envelope_report(date(2, 1)).unwrap().ready_to_assign, usd(540000));
+    }
+
+    #[test]
+    fn liabilities_lower_net_worth() {
+        let date = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        let mut ledger = Ledger::new_empty();
+        let checking = ledger.add_new_account_of_type("Checking".to_string(), Currency::USD, AccountType::Checking).unwrap();
+        let card = ledger.add_new_account_of_type("Visa".to_string(), Currency::USD, AccountType::CreditCard).unwrap();
+        ledger.set_opening_balance(checking, Money::new(100000, Currency::USD), date).unwrap();
+        ledger.set_opening_balance(card, Money::new(25000, Currency::USD), date).unwrap();
+        add_test_transaction(&mut ledger, "Visa", Money::new(-5000, Currency::USD));
+
+        assert_eq!(ledger.get_account(card).unwrap().get_total(), Money::new(-30000, Currency::USD));
+        assert_eq!(ledger.get_net_worth(Currency::USD, date), Ok(Money::new(70000, Currency::USD)));
+        assert!(ledger.set_opening_balance(card, Money::new(100, Currency::EUR), date).is_err());
+        assert!(ledger.set_opening_balance(AccountId::from(100), Money::new(100, Currency::USD), date).is_err());
+    }
+
+    #[test]
+    fn new_account_with_opening_balance() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let mut ledger = Ledger::new_empty();
+        let card = ledger.add_new_account_with_opening_balance(
+            "Visa".to_string(), Currency::USD, AccountType::CreditCard, Money::new(25000, Currency::USD), date).unwrap();
+        assert_eq!(ledger.get_account(card).unwrap().get_total(), Money::new(-25000, Currency::USD));
+
+        assert!(ledger.add_new_account_with_opening_balance(
+            "Savings".to_string(), Currency::USD, AccountType::Savings, Money::new(100, Currency::EUR), date).is_err());
+        assert!(ledger.add_new_account_with_opening_balance(
+            "Loan".to_string(), Currency::USD, AccountType::Loan, Money::new(i64::MIN, Currency::USD), date).is_err());
+        assert_eq!(ledger.get_accounts().len(), 1);
+    }
+
+    #[test]
+    fn trial_balance_shows_imbalance() {
+        let mut ledger = Ledger::new_empty();
+        ledger.add_new_account("Checking".to_string(), Currency::USD).unwrap();
+        ledger.add_new_account("Girokonto".to_string(), Currency::EUR).unwrap();
+        add_test_transaction(&mut ledger, "Checking", Money::new(100, Currency::USD));
+
+        let trial_balance = ledger.get_trial_balance(Currency::USD).unwrap();
+        assert_eq!(trial_balance.get_balances().len(), 1);
+        assert_eq!(trial_balance.get_imbalance(), Money::new(100, Currency::USD));
     }
 
     #[test]
     fn get_account_by_name_empty() {
         let mut ledger = Ledger::new_empty();
-        assert!(ledger.get_account_by_name_mut(&"My Account".to_string()).is_none());
+        assert!(ledger.get_account_by_name_mut("My Account").is_none());
     }
 }
\ No newline at end of file
@@ -0,0 +1,231 @@
+//! Payees: the people and businesses money goes to or comes from. Banks describe the same merchant in many ways, like
+//! "AMZN Mktp US*2K3" and "Amazon.com", so each payee has alias patterns that transaction descriptions are matched
+//! against.
+
+use std::fmt::Display;
+
+/// Uniquely identifies a payee within a ledger.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub struct PayeeId(u64);
+
+impl From<u64> for PayeeId {
+    fn from(value: u64) -> Self {
+        PayeeId(value)
+    }
+}
+
+impl From<PayeeId> for u64 {
+    fn from(value: PayeeId) -> Self {
+        value.0
+    }
+}
+
+impl Display for PayeeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct Payee {
+    id: PayeeId,
+    name: String,
+    // Patterns for descriptions that mean this payee. See `matches_pattern`.
+    aliases: Vec<String>,
+}
+
+impl Payee {
+    pub fn get_id(&self) -> PayeeId {
+        self.id
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_aliases(&self) -> &Vec<String> {
+        &self.aliases
+    }
+
+    /// Fails if the pattern is blank, would match anything, or the payee already has it.
+    fn add_alias(&mut self, pattern: String) -> Result<(), String> {
+        let pattern = pattern.trim().to_string();
+        if pattern.chars().all(|c| c == '*') {
+            return Err("An alias has to match something more specific than any description".to_string());
+        }
+        if self.aliases.iter().any(|a| a.to_lowercase() == pattern.to_lowercase()) {
+            return Err(format!("Payee '{}' already has the alias '{}'", self.name, pattern));
+        }
+        self.aliases.push(pattern);
+        Ok(())
+    }
+
+    /// Whether a transaction description means this payee: it's the payee's name, or matches one of the aliases.
+    pub fn matches(&self, description: &str) -> bool {
+        self.name.to_lowercase() == description.trim().to_lowercase()
+            || self.aliases.iter().any(|alias| matches_pattern(alias, description))
+    }
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct Payees {
+    payees: Vec<Payee>,
+}
+
+impl Default for Payees {
+    fn default() -> Self {
+        Payees::new_empty()
+    }
+}
+
+impl Payees {
+    pub fn new_empty() -> Payees {
+        Payees {payees: Vec::new()}
+    }
+
+    /// Fails if the name is blank, has control characters, or is another payee's name ignoring case, or if any of the
+    /// aliases aren't valid.
+    pub(crate) fn add_payee(&mut self, id: PayeeId, name: String, aliases: Vec<String>) -> Result<(), String> {
+        let name = name.trim().to_string();
+        self.check_name(&name, None)?;
+        let mut payee = Payee {id, name, aliases: Vec::new()};
+        for alias in aliases {
+            payee.add_alias(alias)?;
+        }
+        self.payees.push(payee);
+        Ok(())
+    }
+
+    pub(crate) fn rename_payee(&mut self, id: PayeeId, name: String) -> Result<(), String> {
+        let name = name.trim().to_string();
+        self.check_name(&name, Some(id))?;
+        self.get_payee_mut(id)?.name = name;
+        Ok(())
+    }
+
+    fn check_name(&self, name: &str, renaming: Option<PayeeId>) -> Result<(), String> {
+        if name.is_empty() {
+            return Err("Payee names can't be blank".to_string());
+        }
+        if name.chars().any(char::is_control) {
+            return Err("Payee names can't contain control characters".to_string());
+        }
+        if let Some(existing) = self.find_by_name(name).filter(|p| Some(p.id) != renaming) {
+            return Err(format!("Payee '{}' already exists", existing.name));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn add_alias(&mut self, id: PayeeId, pattern: String) -> Result<(), String> {
+        self.get_payee_mut(id)?.add_alias(pattern)
+    }
+
+    /// Remove a payee, giving its aliases to `into`.
+    pub(crate) fn merge_payees(&mut self, from: PayeeId, into: PayeeId) -> Result<(), String> {
+        if from == into {
+            return Err("Can't merge a payee into itself".to_string());
+        }
+        self.get_payee(into).ok_or_else(|| format!("No payee with ID {}", into))?;
+        let index = self.payees.iter().position(|p| p.id == from).ok_or_else(|| format!("No payee with ID {}", from))?;
+        let removed = self.payees.remove(index);
+        let into = self.get_payee_mut(into)?;
+        for alias in removed.aliases.into_iter().chain(std::iter::once(removed.name)) {
+            if !into.aliases.iter().any(|a| a.to_lowercase() == alias.to_lowercase()) {
+                into.aliases.push(alias);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_payees(&self) -> &Vec<Payee> {
+        &self.payees
+    }
+
+    pub fn get_payee(&self, id: PayeeId) -> Option<&Payee> {
+        self.payees.iter().find(|p| p.id == id)
+    }
+
+    fn get_payee_mut(&mut self, id: PayeeId) -> Result<&mut Payee, String> {
+        self.payees.iter_mut().find(|p| p.id == id).ok_or_else(|| format!("No payee with ID {}", id))
+    }
+
+    /// Look up a payee by name, ignoring case.
+    pub fn find_by_name(&self, name: &str) -> Option<&Payee> {
+        let name = name.trim().to_lowercase();
+        self.payees.iter().find(|p| p.name.to_lowercase() == name)
+    }
+
+    /// The payee a transaction description means, if any. When more than one matches, the first one added wins.
+    pub fn match_description(&self, description: &str) -> Option<PayeeId> {
+        self.payees.iter().find(|p| p.matches(description)).map(|p| p.id)
+    }
+}
+
+/// Whether `text` matches `pattern`, ignoring case. A `*` in the pattern matches any run of characters, including
+/// none, and everything else has to match exactly. So `amzn mktp*` matches "AMZN Mktp US*2K3", and `*amazon*` matches
+/// anything with "amazon" in it.
+pub fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    let text = text.trim().to_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let middle: Vec<&str> = parts.collect();
+    let Some((last, middle)) = middle.split_last() else {
+        // No `*` at all, so the whole text has to match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        assert!(matches_pattern("amzn mktp*", "AMZN Mktp US*2K3"));
+        assert!(matches_pattern("*amazon*", "www.Amazon.com"));
+        assert!(matches_pattern("amazon", " Amazon "));
+        assert!(matches_pattern("a*b*c", "abc"));
+        assert!(matches_pattern("a*b*c", "a-xbx-c"));
+        assert!(!matches_pattern("a*b*c", "a-c"));
+        assert!(!matches_pattern("amazon", "amazon.com"));
+        assert!(!matches_pattern("*.com", "amazon.co"));
+        assert!(!matches_pattern("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn payees() {
+        let mut payees = Payees::new_empty();
+        let amazon = PayeeId(1);
+        payees.add_payee(amazon, "Amazon".to_string(), vec!["AMZN Mktp*".to_string()]).unwrap();
+        assert!(payees.add_alias(amazon, "amzn mktp*".to_string()).is_err());
+        assert!(payees.add_alias(amazon, "**".to_string()).is_err());
+        assert!(payees.add_payee(PayeeId(2), "amazon".to_string(), Vec::new()).is_err());
+        assert!(payees.add_payee(PayeeId(2), "Amazon Prime".to_string(), vec!["*".to_string()]).is_err());
+        assert!(payees.find_by_name("amazon prime").is_none());
+
+        assert_eq!(payees.match_description("AMZN Mktp US*2K3"), Some(amazon));
+        assert_eq!(payees.match_description("amazon"), Some(amazon));
+        assert_eq!(payees.match_description("Corner store"), None);
+
+        let amazon_com = PayeeId(2);
+        payees.add_payee(amazon_com, "Amazon.com".to_string(), Vec::new()).unwrap();
+        payees.merge_payees(amazon_com, amazon).unwrap();
+        assert_eq!(payees.match_description("AMAZON.COM"), Some(amazon));
+        assert!(payees.get_payee(amazon_com).is_none());
+        assert!(payees.merge_payees(amazon, amazon).is_err());
+
+        payees.rename_payee(amazon, "Amazon 📦".to_string()).unwrap();
+        assert_eq!(payees.find_by_name("amazon 📦").unwrap().get_id(), amazon);
+    }
+}
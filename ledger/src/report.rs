@@ -0,0 +1,230 @@
+//! Summaries of the money moving through the ledger's accounts.
+
+use std::collections::HashMap;
+
+use crate::{account::AccountId, category::{CategoryId, TransactionCategories}, common_types::{Currency, Money}, payee::PayeeId};
+
+/// Income and expenses over some set of transactions, in a single currency. Transfers between accounts are neither,
+/// so they're left out.
+#[derive(Debug, PartialEq)]
+pub struct IncomeExpenseReport {
+    currency: Currency,
+    income: Money,
+    // Kept positive, like the income
+    expenses: Money,
+    // Net amount per category, in the order categories were first seen
+    categories: Vec<(Option<CategoryId>, Money)>,
+    // Net amount per category including its subcategories, in category tree order
+    category_totals: Vec<(CategoryId, Money)>,
+    // Net amount per payee, in the order payees were first seen
+    payees: Vec<(Option<PayeeId>, Money)>,
+}
+
+impl IncomeExpenseReport {
+    pub(crate) fn new_empty(currency: Currency) -> IncomeExpenseReport {
+        IncomeExpenseReport {
+            currency,
+            income: Money::zero(currency),
+            expenses: Money::zero(currency),
+            categories: Vec::new(),
+            category_totals: Vec::new(),
+            payees: Vec::new(),
+        }
+    }
+
+    /// Fails if the amount isn't in the report currency, or the totals overflow.
+    pub(crate) fn add(&mut self, amount: Money, category: Option<&CategoryId>) -> Result<(), String> {
+        if amount.is_negative() {
+            self.expenses = self.expenses.checked_sub(amount).map_err(|e| e.to_string())?;
+        } else {
+            self.income = self.income.checked_add(amount).map_err(|e| e.to_string())?;
+        }
+
+        match self.categories.iter_mut().find(|(c, _)| c.as_ref() == category) {
+            Some((_, total)) => *total = total.checked_add(amount).map_err(|e| e.to_string())?,
+            None => self.categories.push((category.cloned(), amount)),
+        }
+        Ok(())
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_income(&self) -> Money {
+        self.income
+    }
+
+    pub fn get_expenses(&self) -> Money {
+        self.expenses
+    }
+
+    /// Income minus expenses.
+    pub fn get_net(&self) -> Money {
+        self.income - self.expenses
+    }
+
+    /// The net amount for each category, with `None` for uncategorized transactions.
+    pub fn get_categories(&self) -> &Vec<(Option<CategoryId>, Money)> {
+        &self.categories
+    }
+
+    /// Count a transaction's amount towards its payee. `add` counts it towards the income and expenses.
+    pub(crate) fn add_payee(&mut self, amount: Money, payee: Option<PayeeId>) -> Result<(), String> {
+        match self.payees.iter_mut().find(|(p, _)| *p == payee) {
+            Some((_, total)) => *total = total.checked_add(amount).map_err(|e| e.to_string())?,
+            None => self.payees.push((payee, amount)),
+        }
+        Ok(())
+    }
+
+    /// The net amount for each payee, with `None` for transactions that didn't match a payee.
+    pub fn get_payees(&self) -> &Vec<(Option<PayeeId>, Money)> {
+        &self.payees
+    }
+
+    /// Work out the totals for each category with its subcategories rolled into it. Call this after everything has
+    /// been added.
+    pub(crate) fn roll_up(&mut self, categories: &TransactionCategories) -> Result<(), String> {
+        let mut totals: HashMap<&CategoryId, Money> = HashMap::new();
+        for (category, amount) in self.categories.iter().filter_map(|(c, a)| c.as_ref().map(|c| (c, a))) {
+            for id in std::iter::once(category).chain(categories.ancestors(category)) {
+                let total = totals.entry(id).or_insert(Money::zero(self.currency));
+                *total = total.checked_add(*amount).map_err(|e| e.to_string())?;
+            }
+        }
+
+        // Categories that aren't in the tree, like ones that have been deleted, go at the end
+        let mut ordered: Vec<&CategoryId> = categories.tree().into_iter().map(|(_, c)| c.get_id()).collect();
+        ordered.extend(self.categories.iter().filter_map(|(c, _)| c.as_ref()).filter(|c| categories.get_category(c).is_none()));
+        self.category_totals = ordered.into_iter()
+            .filter_map(|id| totals.get(id).map(|total| (id.clone(), *total)))
+            .collect();
+        Ok(())
+    }
+
+    /// The net amount for each category that has any, including its subcategories. Parents come before their
+    /// subcategories.
+    pub fn get_category_totals(&self) -> &Vec<(CategoryId, Money)> {
+        &self.category_totals
+    }
+}
+
+/// Account balances split into debits (positive) and credits (negative), for checking that the books balance.
+#[derive(Debug, PartialEq)]
+pub struct TrialBalance {
+    currency: Currency,
+    balances: Vec<(AccountId, Money)>,
+    debits: Money,
+    // Kept positive, like the debits
+    credits: Money,
+}
+
+impl TrialBalance {
+    pub(crate) fn new_empty(currency: Currency) -> TrialBalance {
+        TrialBalance {
+            currency,
+            balances: Vec::new(),
+            debits: Money::zero(currency),
+            credits: Money::zero(currency),
+        }
+    }
+
+    /// Fails if the balance isn't in the trial balance currency, or the totals overflow.
+    pub(crate) fn add(&mut self, account: AccountId, balance: Money) -> Result<(), String> {
+        if balance.is_negative() {
+            self.credits = self.credits.checked_sub(balance).map_err(|e| e.to_string())?;
+        } else {
+            self.debits = self.debits.checked_add(balance).map_err(|e| e.to_string())?;
+        }
+        self.balances.push((account, balance));
+        Ok(())
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_balances(&self) -> &Vec<(AccountId, Money)> {
+        &self.balances
+    }
+
+    pub fn get_debits(&self) -> Money {
+        self.debits
+    }
+
+    pub fn get_credits(&self) -> Money {
+        self.credits
+    }
+
+    /// Debits minus credits, which is zero when the books balance.
+    pub fn get_imbalance(&self) -> Money {
+        self.debits - self.credits
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.get_imbalance().is_zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add() {
+        let food = CategoryId::from("food".to_string());
+        let mut report = IncomeExpenseReport::new_empty(Currency::USD);
+        report.add(Money::new(10000, Currency::USD), None).unwrap();
+        report.add(Money::new(-2500, Currency::USD), Some(&food)).unwrap();
+        report.add(Money::new(-500, Currency::USD), Some(&food)).unwrap();
+
+        assert_eq!(report.get_income(), Money::new(10000, Currency::USD));
+        assert_eq!(report.get_expenses(), Money::new(3000, Currency::USD));
+        assert_eq!(report.get_net(), Money::new(7000, Currency::USD));
+        assert_eq!(report.get_categories(), &vec![
+            (None, Money::new(10000, Currency::USD)),
+            (Some(food), Money::new(-3000, Currency::USD)),
+        ]);
+
+        assert!(report.add(Money::new(100, Currency::EUR), None).is_err());
+    }
+
+    #[test]
+    fn roll_up() {
+        let mut categories = TransactionCategories::new_empty();
+        let groceries = categories.create_category("Food:Groceries".to_string()).unwrap();
+        let restaurants = categories.create_category("Food:Restaurants".to_string()).unwrap();
+        let food = categories.find_by_name("Food").unwrap().get_id().clone();
+        let rent = categories.create_category("Rent".to_string()).unwrap();
+
+        let mut report = IncomeExpenseReport::new_empty(Currency::USD);
+        report.add(Money::new(-2500, Currency::USD), Some(&restaurants)).unwrap();
+        report.add(Money::new(-6000, Currency::USD), Some(&groceries)).unwrap();
+        report.add(Money::new(-1000, Currency::USD), Some(&food)).unwrap();
+        report.add(Money::new(10000, Currency::USD), None).unwrap();
+        report.roll_up(&categories).unwrap();
+
+        assert_eq!(report.get_category_totals(), &vec![
+            (food, Money::new(-9500, Currency::USD)),
+            (groceries, Money::new(-6000, Currency::USD)),
+            (restaurants, Money::new(-2500, Currency::USD)),
+        ]);
+        assert!(!report.get_category_totals().iter().any(|(c, _)| *c == rent));
+    }
+
+    #[test]
+    fn trial_balance() {
+        let mut trial_balance = TrialBalance::new_empty(Currency::USD);
+        trial_balance.add(AccountId::from(1), Money::new(10000, Currency::USD)).unwrap();
+        trial_balance.add(AccountId::from(2), Money::new(-7500, Currency::USD)).unwrap();
+        assert_eq!(trial_balance.get_debits(), Money::new(10000, Currency::USD));
+        assert_eq!(trial_balance.get_credits(), Money::new(7500, Currency::USD));
+        assert_eq!(trial_balance.get_imbalance(), Money::new(2500, Currency::USD));
+        assert!(!trial_balance.is_balanced());
+
+        trial_balance.add(AccountId::from(3), Money::new(-2500, Currency::USD)).unwrap();
+        assert!(trial_balance.is_balanced());
+        assert_eq!(trial_balance.get_balances().len(), 3);
+    }
+}
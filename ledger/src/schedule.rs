@@ -0,0 +1,427 @@
+//! Schedules for transactions that repeat, like rent, a salary or a subscription. Each schedule is a template for
+//! the transaction plus the dates it comes due on. The ledger posts a real transaction for each date once it's due.
+
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
+use crate::{account::AccountId, category::CategoryId, common_types::Money, transaction::{Transaction, TransactionId}};
+
+/// Uniquely identifies a schedule within a ledger.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub struct ScheduleId(u64);
+
+impl From<u64> for ScheduleId {
+    fn from(value: u64) -> Self {
+        ScheduleId(value)
+    }
+}
+
+impl Display for ScheduleId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// How often a schedule comes due. The monthly kinds can be every few months with `Schedule::with_interval`, and
+/// likewise for the others.
+#[derive(Debug, PartialEq, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    /// On the start date's day of the month, or the last day of months too short for it
+    Monthly,
+    Yearly,
+    /// Monthly, on the nth weekday of the month, like the 2nd Tuesday. `n` is 1 to 4, or -1 for the last one.
+    NthWeekday { n: i8, weekday: Weekday },
+    /// Monthly, on the last Monday to Friday of the month
+    LastBusinessDay,
+}
+
+static FREQUENCY_NAMES: [(Frequency, &str); 5] = [
+    (Frequency::Daily, "daily"),
+    (Frequency::Weekly, "weekly"),
+    (Frequency::Monthly, "monthly"),
+    (Frequency::Yearly, "yearly"),
+    (Frequency::LastBusinessDay, "last business day"),
+];
+
+static ORDINALS: [(i8, &str); 5] = [(1, "1st"), (2, "2nd"), (3, "3rd"), (4, "4th"), (-1, "last")];
+
+impl Frequency {
+    /// Fails for an nth weekday whose `n` isn't 1 to 4 or -1. Those can't be parsed, but can be built directly or
+    /// turn up in a damaged file.
+    pub fn check(&self) -> Result<(), String> {
+        match self {
+            Frequency::NthWeekday { n, .. } if !ORDINALS.iter().any(|(o, _)| o == n) => {
+                Err(format!("The week of the month has to be 1 to 4, or -1 for the last one, not {}", n))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    // The day this falls on in the month starting at `first`, for the kinds that pick a particular day
+    fn day_in_month(&self, first: NaiveDate) -> Option<NaiveDate> {
+        let last = first.checked_add_months(Months::new(1))? - Days::new(1);
+        match self {
+            Frequency::NthWeekday { n: -1, weekday } => {
+                let days_back = (last.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+                Some(last - Days::new(days_back as u64))
+            },
+            Frequency::NthWeekday { n: n @ 1..=4, weekday } => {
+                let days_forward = (weekday.num_days_from_monday() + 7 - first.weekday().num_days_from_monday()) % 7;
+                Some(first + Days::new(days_forward as u64 + 7 * (*n as u64 - 1)))
+            },
+            Frequency::LastBusinessDay => {
+                let days_back = match last.weekday() {
+                    Weekday::Sat => 1,
+                    Weekday::Sun => 2,
+                    _ => 0,
+                };
+                Some(last - Days::new(days_back))
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Display for Frequency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Frequency::NthWeekday { n, weekday } => {
+                match ORDINALS.iter().find(|(o, _)| o == n) {
+                    Some((_, ordinal)) => write!(f, "{} {}", ordinal, weekday),
+                    // Only possible if it was built directly, which `check` catches
+                    None => write!(f, "{} {}", n, weekday),
+                }
+            },
+            _ => {
+                let (_, name) = FREQUENCY_NAMES.iter().find(|(fr, _)| fr == self).expect("Every other frequency has a name");
+                write!(f, "{}", name)
+            },
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = String;
+
+    /// Accepts daily, weekly, monthly, yearly and last business day, or an ordinal and a weekday for the nth weekday
+    /// of each month, like "2nd tuesday" or "last fri".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.split_whitespace().collect::<Vec<_>>().join(" ").to_ascii_lowercase();
+        if let Some((frequency, _)) = FREQUENCY_NAMES.iter().find(|(_, name)| *name == normalized) {
+            return Ok(*frequency);
+        }
+        normalized.split_once(' ')
+            .and_then(|(ordinal, weekday)| {
+                let (n, _) = ORDINALS.iter().find(|(_, name)| *name == ordinal)?;
+                Some(Frequency::NthWeekday { n: *n, weekday: weekday.parse().ok()? })
+            })
+            .ok_or_else(|| format!(
+                "Unknown frequency '{}', expected daily, weekly, monthly, yearly, last business day or a weekday like 2nd tuesday", s))
+    }
+}
+
+/// When a schedule stops coming due.
+#[derive(Debug, PartialEq, Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub enum ScheduleEnd {
+    #[default]
+    Never,
+    /// Nothing comes due after this date
+    On(NaiveDate),
+    /// After this many occurrences, counting skipped ones
+    After(u32),
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Schedule {
+    id: ScheduleId,
+    account: AccountId,
+    amount: Money,
+    description: String,
+    category: Option<CategoryId>,
+    frequency: Frequency,
+    // Comes due every `interval` days, weeks, months or years
+    interval: u32,
+    start: NaiveDate,
+    end: ScheduleEnd,
+    // How many occurrences have been posted or skipped so far
+    done: u32,
+}
+
+impl Schedule {
+    /// A schedule that comes due on `start` and then at every `frequency` from then on. For the kinds that fall on a
+    /// particular day of the month, the first occurrence is the first such day on or after `start`.
+    pub fn new(
+        account: AccountId,
+        amount: Money,
+        description: String,
+        category: Option<CategoryId>,
+        frequency: Frequency,
+        start: NaiveDate,
+    ) -> Schedule {
+        Schedule {
+            id: ScheduleId(0),
+            account,
+            amount,
+            description,
+            category,
+            frequency,
+            interval: 1,
+            start,
+            end: ScheduleEnd::Never,
+            done: 0,
+        }
+    }
+
+    /// Come due every `interval` periods instead of every one, like every 2 weeks. Fails if `interval` is zero.
+    pub fn with_interval(mut self, interval: u32) -> Result<Schedule, String> {
+        if interval == 0 {
+            return Err("A schedule has to repeat at least every 1 period".to_string());
+        }
+        self.interval = interval;
+        Ok(self)
+    }
+
+    pub fn with_end(mut self, end: ScheduleEnd) -> Schedule {
+        self.end = end;
+        self
+    }
+
+    pub fn get_id(&self) -> ScheduleId {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: ScheduleId) {
+        self.id = id;
+    }
+
+    pub fn get_account(&self) -> AccountId {
+        self.account
+    }
+
+    pub fn get_amount(&self) -> &Money {
+        &self.amount
+    }
+
+    pub fn get_description(&self) -> &String {
+        &self.description
+    }
+
+    pub fn get_category(&self) -> Option<&CategoryId> {
+        self.category.as_ref()
+    }
+
+    pub fn get_frequency(&self) -> Frequency {
+        self.frequency
+    }
+
+    pub fn get_interval(&self) -> u32 {
+        self.interval
+    }
+
+    pub fn get_start(&self) -> NaiveDate {
+        self.start
+    }
+
+    pub fn get_end(&self) -> ScheduleEnd {
+        self.end
+    }
+
+    /// The date of the occurrence with the given index, counting from 0, whether or not the schedule has ended by
+    /// then. None if it's too far out to represent.
+    pub fn occurrence(&self, index: u32) -> Option<NaiveDate> {
+        let periods = index.checked_mul(self.interval)?;
+        match self.frequency {
+            Frequency::Daily => self.start.checked_add_days(Days::new(periods as u64)),
+            Frequency::Weekly => self.start.checked_add_days(Days::new(periods as u64 * 7)),
+            // Counting from the start each time keeps the 31st on the 31st after a short month
+            Frequency::Monthly => self.start.checked_add_months(Months::new(periods)),
+            Frequency::Yearly => self.start.checked_add_months(Months::new(periods.checked_mul(12)?)),
+            Frequency::NthWeekday { .. } | Frequency::LastBusinessDay => {
+                let first = self.start.with_day(1).expect("Every month has a first day");
+                let skip_first = self.frequency.day_in_month(first)? < self.start;
+                let months = periods.checked_add(skip_first as u32)?;
+                self.frequency.day_in_month(first.checked_add_months(Months::new(months))?)
+            },
+        }
+    }
+
+    /// The date of the next occurrence that hasn't been posted or skipped, or None if the schedule has ended.
+    pub fn get_next_due(&self) -> Option<NaiveDate> {
+        let date = self.occurrence(self.done)?;
+        match self.end {
+            ScheduleEnd::On(end) if date > end => None,
+            ScheduleEnd::After(count) if self.done >= count => None,
+            _ => Some(date),
+        }
+    }
+
+    /// Every occurrence on or before `today` that hasn't been posted or skipped, oldest first.
+    pub fn get_due(&self, today: NaiveDate) -> Vec<NaiveDate> {
+        let mut schedule = self.clone();
+        let mut due = Vec::new();
+        while let Some(date) = schedule.get_next_due().filter(|d| *d <= today) {
+            due.push(date);
+            schedule.done += 1;
+        }
+        due
+    }
+
+    /// A short description of how often this comes due, like "every 2 weeks" or "monthly on the last friday".
+    pub fn describe_frequency(&self) -> String {
+        let unit = match self.frequency {
+            Frequency::Daily => "day",
+            Frequency::Weekly => "week",
+            Frequency::Yearly => "year",
+            _ => "month",
+        };
+        let every = match self.interval {
+            1 => format!("every {}", unit),
+            n => format!("every {} {}s", n, unit),
+        };
+        match self.frequency {
+            Frequency::NthWeekday { .. } | Frequency::LastBusinessDay => format!("{} on the {}", every, self.frequency),
+            _ => every,
+        }
+    }
+
+    /// The transaction to post for an occurrence on `date`.
+    pub(crate) fn to_transaction(&self, date: NaiveDate) -> Transaction {
+        Transaction::new_on_date(self.amount, date, self.description.clone(), self.category.clone())
+    }
+
+    /// Move on to the next occurrence, after the current one has been posted or skipped.
+    pub(crate) fn advance(&mut self) {
+        self.done += 1;
+    }
+
+    pub(crate) fn update(&mut self, update: ScheduleUpdate) -> Result<(), String> {
+        if let Some(amount) = update.amount {
+            if amount.currency() != self.amount.currency() {
+                return Err(format!("The schedule is in {}, but the new amount is in {}", self.amount.currency(), amount.currency()));
+            }
+            self.amount = amount;
+        }
+        if let Some(description) = update.description {
+            self.description = description;
+        }
+        if let Some(category) = update.category {
+            self.category = category;
+        }
+        if let Some(end) = update.end {
+            self.end = end;
+        }
+        Ok(())
+    }
+
+    /// Move the schedule's transactions from one category to another, or out of any category if `to` is None.
+    /// Returns whether anything changed.
+    pub(crate) fn replace_category(&mut self, from: &CategoryId, to: Option<&CategoryId>) -> bool {
+        if self.category.as_ref() != Some(from) {
+            return false;
+        }
+        self.category = to.cloned();
+        true
+    }
+}
+
+/// Changes to make to an existing schedule. Fields left as `None` are kept as they are. Occurrences that have already
+/// been posted aren't affected.
+#[derive(Debug, Default, Clone)]
+pub struct ScheduleUpdate {
+    pub amount: Option<Money>,
+    pub description: Option<String>,
+    // Some(None) removes the category
+    pub category: Option<Option<CategoryId>>,
+    pub end: Option<ScheduleEnd>,
+}
+
+/// What posting due schedules did: the transactions it posted, and the occurrences it skipped along with why.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct PostedSchedules {
+    pub posted: Vec<TransactionId>,
+    pub skipped: Vec<(ScheduleId, NaiveDate, String)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common_types::Currency;
+
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn schedule(frequency: Frequency, start: NaiveDate) -> Schedule {
+        Schedule::new(AccountId::from(1), Money::new(-100000, Currency::USD), "Rent".to_string(), None, frequency, start)
+    }
+
+    fn occurrences(schedule: &Schedule, count: u32) -> Vec<NaiveDate> {
+        (0..count).map(|i| schedule.occurrence(i).unwrap()).collect()
+    }
+
+    #[test]
+    fn frequencies() {
+        let weekly = schedule(Frequency::Weekly, date(2024, 1, 29)).with_interval(2).unwrap();
+        assert_eq!(occurrences(&weekly, 3), vec![date(2024, 1, 29), date(2024, 2, 12), date(2024, 2, 26)]);
+
+        let monthly = schedule(Frequency::Monthly, date(2024, 1, 31));
+        assert_eq!(occurrences(&monthly, 3), vec![date(2024, 1, 31), date(2024, 2, 29), date(2024, 3, 31)]);
+
+        let yearly = schedule(Frequency::Yearly, date(2024, 2, 29));
+        assert_eq!(occurrences(&yearly, 2), vec![date(2024, 2, 29), date(2025, 2, 28)]);
+
+        // The 2nd Tuesday of January 2024 is the 9th, which is before the start
+        let second_tuesday = schedule(Frequency::NthWeekday { n: 2, weekday: Weekday::Tue }, date(2024, 1, 10));
+        assert_eq!(occurrences(&second_tuesday, 2), vec![date(2024, 2, 13), date(2024, 3, 12)]);
+
+        let last_friday = schedule(Frequency::NthWeekday { n: -1, weekday: Weekday::Fri }, date(2024, 1, 1));
+        assert_eq!(occurrences(&last_friday, 2), vec![date(2024, 1, 26), date(2024, 2, 23)]);
+
+        // March 2024 ends on a Sunday, and June on a Sunday too
+        let payday = schedule(Frequency::LastBusinessDay, date(2024, 3, 1)).with_interval(3).unwrap();
+        assert_eq!(occurrences(&payday, 2), vec![date(2024, 3, 29), date(2024, 6, 28)]);
+
+        assert!(schedule(Frequency::Daily, date(2024, 1, 1)).with_interval(0).is_err());
+    }
+
+    #[test]
+    fn parse_frequencies() {
+        assert_eq!("Monthly".parse::<Frequency>(), Ok(Frequency::Monthly));
+        assert_eq!("last  business day".parse::<Frequency>(), Ok(Frequency::LastBusinessDay));
+        assert_eq!("2nd tuesday".parse::<Frequency>(), Ok(Frequency::NthWeekday { n: 2, weekday: Weekday::Tue }));
+        assert_eq!("last fri".parse::<Frequency>(), Ok(Frequency::NthWeekday { n: -1, weekday: Weekday::Fri }));
+        assert!("5th monday".parse::<Frequency>().is_err());
+        assert!("fortnightly".parse::<Frequency>().is_err());
+        assert_eq!(Frequency::NthWeekday { n: 2, weekday: Weekday::Tue }.to_string(), "2nd Tue");
+    }
+
+    #[test]
+    fn invalid_nth_weekday() {
+        for n in [0, 5, -2, i8::MIN] {
+            let frequency = Frequency::NthWeekday { n, weekday: Weekday::Mon };
+            assert!(frequency.check().is_err());
+            assert_eq!(frequency.to_string(), format!("{} Mon", n));
+            assert_eq!(schedule(frequency, date(2024, 1, 1)).get_next_due(), None);
+        }
+        assert!(Frequency::NthWeekday { n: -1, weekday: Weekday::Mon }.check().is_ok());
+        assert!(Frequency::Daily.check().is_ok());
+    }
+
+    #[test]
+    fn due_and_end() {
+        let mut rent = schedule(Frequency::Monthly, date(2024, 1, 1)).with_end(ScheduleEnd::After(3));
+        assert_eq!(rent.get_due(date(2024, 2, 15)), vec![date(2024, 1, 1), date(2024, 2, 1)]);
+        rent.advance();
+        rent.advance();
+        rent.advance();
+        assert_eq!(rent.get_next_due(), None);
+
+        let rent = schedule(Frequency::Monthly, date(2024, 1, 1)).with_end(ScheduleEnd::On(date(2024, 2, 28)));
+        assert_eq!(rent.get_due(date(2025, 1, 1)), vec![date(2024, 1, 1), date(2024, 2, 1)]);
+        assert_eq!(rent.describe_frequency(), "every month");
+    }
+}
@@ -1,46 +1,467 @@
-use chrono::{DateTime, Utc};
-use serde::{de, Deserialize};
-use crate::category::CategoryId;
-use super::common_types::Money;
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
+
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone};
+use serde::de;
+use crate::{category::CategoryId, journal::EntryId, payee::PayeeId};
+use super::common_types::{Currency, Money};
+
+/// Uniquely identifies a transaction within a ledger. IDs are handed out by the ledger when a transaction is added
+/// to it, and are never reused.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub struct TransactionId(u64);
+
+impl From<u64> for TransactionId {
+    fn from(value: u64) -> Self {
+        TransactionId(value)
+    }
+}
+
+impl From<TransactionId> for u64 {
+    fn from(value: TransactionId) -> Self {
+        value.0
+    }
+}
+
+impl Display for TransactionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
 #[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Transaction {
+    // Unassigned (0) until the transaction is added to a ledger
+    id: TransactionId,
     amount: Money,
     time: Timestamp,
     description: String, 
     category: Option<CategoryId>,
+    // The other half of a transfer between accounts
+    #[serde(default)]
+    transfer: Option<TransactionId>,
+    // Empty unless the transaction is split across categories, in which case `category` isn't used
+    #[serde(default)]
+    splits: Vec<Split>,
+    // The journal entry this transaction is a posting of
+    #[serde(default)]
+    entry: Option<EntryId>,
+    #[serde(default)]
+    status: TransactionStatus,
+    // Labels that cut across categories, like "vacation-2026". Kept normalized, see `normalize_tag`.
+    #[serde(default)]
+    tags: BTreeSet<String>,
+    // Filled in by the ledger from the description, when it matches a payee
+    #[serde(default)]
+    payee: Option<PayeeId>,
 }
 
 impl Transaction {
-    pub fn new(
+    /// The time is stored along with its UTC offset, so the transaction stays on the calendar day it happened on
+    /// wherever it's viewed from.
+    pub fn new<Tz: TimeZone>(
         amount: Money, 
-        time: DateTime<Utc>, 
+        time: DateTime<Tz>, 
         description: String, 
         category: Option<CategoryId>,
     ) -> Transaction {
-        Transaction {amount, time: Timestamp::from(time), description, category}
+        Transaction {
+            id: TransactionId::default(),
+            amount,
+            time: Timestamp::from(time),
+            description,
+            category,
+            transfer: None,
+            splits: Vec::new(),
+            entry: None,
+            status: TransactionStatus::default(),
+            tags: BTreeSet::new(),
+            payee: None,
+        }
+    }
+
+    /// A transaction known only by its date, like most bank statement entries.
+    pub fn new_on_date(
+        amount: Money, 
+        date: NaiveDate, 
+        description: String, 
+        category: Option<CategoryId>,
+    ) -> Transaction {
+        Transaction {
+            id: TransactionId::default(),
+            amount,
+            time: Timestamp::Date(date),
+            description,
+            category,
+            transfer: None,
+            splits: Vec::new(),
+            entry: None,
+            status: TransactionStatus::default(),
+            tags: BTreeSet::new(),
+            payee: None,
+        }
+    }
+
+    pub fn get_id(&self) -> TransactionId {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: TransactionId) {
+        self.id = id;
+    }
+
+    pub fn get_amount(&self) -> &Money {
+        &self.amount
+    }
+
+    /// None for transactions that only have a date.
+    pub fn get_time(&self) -> Option<DateTime<FixedOffset>> {
+        match self.time {
+            Timestamp::At(time) => Some(time),
+            Timestamp::Date(_) => None,
+        }
+    }
+
+    /// The calendar day of the transaction, in the time zone it was recorded in.
+    pub fn get_date(&self) -> NaiveDate {
+        match self.time {
+            Timestamp::At(time) => time.date_naive(),
+            Timestamp::Date(date) => date,
+        }
+    }
+
+    /// Orders transactions by when they happened. Date-only transactions come before timed ones on the same day.
+    pub fn chronological_key(&self) -> (NaiveDate, Option<DateTime<FixedOffset>>, TransactionId) {
+        (self.get_date(), self.get_time(), self.id)
+    }
+
+    pub fn get_description(&self) -> &String {
+        &self.description
+    }
+
+    pub fn get_category(&self) -> Option<&CategoryId> {
+        self.category.as_ref()
+    }
+
+    /// Split the transaction across categories. The splits have to add up to the transaction's amount. 
+    pub fn with_splits(mut self, splits: Vec<Split>) -> Result<Transaction, String> {
+        check_splits(self.amount, &splits)?;
+        if !splits.is_empty() {
+            self.category = None;
+        }
+        self.splits = splits;
+        Ok(self)
+    }
+
+    pub fn get_splits(&self) -> &Vec<Split> {
+        &self.splits
+    }
+
+    /// How the amount breaks down by category: one entry per split, or just the whole amount if it isn't split.
+    pub fn get_category_amounts(&self) -> Vec<(Option<&CategoryId>, Money)> {
+        if self.splits.is_empty() {
+            vec![(self.category.as_ref(), self.amount)]
+        } else {
+            self.splits.iter().map(|s| (s.category.as_ref(), s.amount)).collect()
+        }
+    }
+
+    /// Tag the transaction. Tags are normalized (see `normalize_tag`), and fail if they aren't valid.
+    pub fn with_tags<S: AsRef<str>>(mut self, tags: &[S]) -> Result<Transaction, String> {
+        for tag in tags {
+            self.tags.insert(normalize_tag(tag.as_ref())?);
+        }
+        Ok(self)
+    }
+
+    pub fn get_tags(&self) -> &BTreeSet<String> {
+        &self.tags
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        normalize_tag(tag).is_ok_and(|tag| self.tags.contains(&tag))
+    }
+
+    /// Rename a tag, or remove it if `to` is `None`, returning whether the transaction had it. Both tags have to be
+    /// normalized already.
+    pub(crate) fn replace_tag(&mut self, from: &str, to: Option<&str>) -> bool {
+        if !self.tags.remove(from) {
+            return false;
+        }
+        if let Some(to) = to {
+            self.tags.insert(to.to_string());
+        }
+        true
+    }
+
+    /// The payee the description was matched to, if any.
+    pub fn get_payee(&self) -> Option<PayeeId> {
+        self.payee
+    }
+
+    pub(crate) fn set_payee(&mut self, payee: Option<PayeeId>) {
+        self.payee = payee;
+    }
+
+    /// Whether the transaction, or any of its splits, is in `category`.
+    pub fn uses_category(&self, category: &CategoryId) -> bool {
+        self.category.as_ref() == Some(category) || self.splits.iter().any(|s| s.category.as_ref() == Some(category))
+    }
+
+    /// Every category the transaction or its splits are in.
+    pub fn get_categories(&self) -> impl Iterator<Item = &CategoryId> {
+        self.category.iter().chain(self.splits.iter().filter_map(|s| s.category.as_ref()))
+    }
+
+    /// Put the transaction and its splits that are in `from` into `to` instead, returning whether anything changed.
+    pub(crate) fn replace_category(&mut self, from: &CategoryId, to: Option<&CategoryId>) -> bool {
+        let mut changed = false;
+        let categories = std::iter::once(&mut self.category).chain(self.splits.iter_mut().map(|s| &mut s.category));
+        for category in categories.filter(|c| c.as_ref() == Some(from)) {
+            *category = to.cloned();
+            changed = true;
+        }
+        changed
+    }
+
+    /// For a posting of a journal entry, the ID of the entry.
+    pub fn get_entry(&self) -> Option<EntryId> {
+        self.entry
+    }
+
+    pub(crate) fn set_entry(&mut self, entry: EntryId) {
+        self.entry = Some(entry);
+    }
+
+    /// For one half of a transfer, the ID of the transaction on the other side of it.
+    pub fn get_transfer(&self) -> Option<TransactionId> {
+        self.transfer
+    }
+
+    pub(crate) fn set_transfer(&mut self, other: TransactionId) {
+        self.transfer = Some(other);
+    }
+
+    /// How far the transaction has been checked against the bank's records.
+    pub fn get_status(&self) -> TransactionStatus {
+        self.status
+    }
+
+    pub(crate) fn set_status(&mut self, status: TransactionStatus) {
+        self.status = status;
+    }
+
+    /// Fails if the update would leave splits that don't add up to the amount, would give a split transaction a
+    /// single category, or adds an invalid tag.
+    pub(crate) fn check_update(&self, update: &TransactionUpdate) -> Result<(), String> {
+        let amount = update.amount.unwrap_or(self.amount);
+        let splits = update.splits.as_ref().unwrap_or(&self.splits);
+        if !splits.is_empty() && matches!(update.category, Some(Some(_))) {
+            return Err("A split transaction has a category for each split instead of a single category".to_string());
+        }
+        for tag in &update.add_tags {
+            normalize_tag(tag)?;
+        }
+        check_splits(amount, splits)
+    }
+
+    pub(crate) fn apply_update(&mut self, update: TransactionUpdate) {
+        if let Some(amount) = update.amount {
+            self.amount = amount;
+        }
+        if let Some(time) = update.time {
+            self.time = Timestamp::from(time);
+        } else if let Some(date) = update.date {
+            self.time = Timestamp::Date(date);
+        }
+        if let Some(description) = update.description {
+            self.description = description;
+        }
+        if let Some(category) = update.category {
+            self.category = category;
+        }
+        if let Some(splits) = update.splits {
+            if !splits.is_empty() {
+                self.category = None;
+            }
+            self.splits = splits;
+        }
+        // Tags to add were checked by `check_update`, and tags to remove that aren't valid can't be here anyway
+        for tag in update.remove_tags.iter().filter_map(|t| normalize_tag(t).ok()) {
+            self.tags.remove(&tag);
+        }
+        self.tags.extend(update.add_tags.iter().filter_map(|t| normalize_tag(t).ok()));
+    }
+}
+
+/// Part of a split transaction's amount, with its own category and a note about what it was for.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Split {
+    amount: Money,
+    category: Option<CategoryId>,
+    memo: String,
+}
+
+impl Split {
+    pub fn new(amount: Money, category: Option<CategoryId>, memo: String) -> Split {
+        Split {amount, category, memo}
     }
 
     pub fn get_amount(&self) -> &Money {
         &self.amount
     }
+
+    pub fn get_category(&self) -> Option<&CategoryId> {
+        self.category.as_ref()
+    }
+
+    pub fn get_memo(&self) -> &String {
+        &self.memo
+    }
+}
+
+/// Put a tag in the form it's stored in: without a leading `#`, and in lowercase. Fails if the tag is empty or has
+/// whitespace or control characters in it.
+pub fn normalize_tag(tag: &str) -> Result<String, String> {
+    let normalized = tag.trim().trim_start_matches('#').to_lowercase();
+    if normalized.is_empty() {
+        return Err("Tags can't be blank".to_string());
+    }
+    if normalized.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("Tag '{}' can't contain spaces or control characters", tag.trim()));
+    }
+    Ok(normalized)
+}
+
+/// Where a transaction is in reconciling its account against a bank statement.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub enum TransactionStatus {
+    /// Not seen on a statement yet.
+    #[default]
+    Pending,
+    /// Seen on a statement, but the statement hasn't been reconciled yet.
+    Cleared,
+    /// Part of a reconciled statement. Reconciled transactions can't be edited or deleted.
+    Reconciled,
+}
+
+static TRANSACTION_STATUS_NAMES: [(TransactionStatus, &str); 3] = [
+    (TransactionStatus::Pending, "pending"),
+    (TransactionStatus::Cleared, "cleared"),
+    (TransactionStatus::Reconciled, "reconciled"),
+];
+
+impl Display for TransactionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, name) = TRANSACTION_STATUS_NAMES.iter().find(|(s, _)| s == self).expect("Every status has a name");
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for TransactionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_ascii_lowercase();
+        TRANSACTION_STATUS_NAMES.iter()
+            .find(|(_, name)| *name == normalized)
+            .map(|(status, _)| *status)
+            .ok_or_else(|| format!("Unknown transaction status '{}'", s))
+    }
+}
+
+fn check_splits(amount: Money, splits: &[Split]) -> Result<(), String> {
+    if splits.is_empty() {
+        return Ok(());
+    }
+    let total = Money::total(amount.currency(), splits.iter().map(|s| &s.amount)).map_err(|e| e.to_string())?;
+    if total != amount {
+        return Err(format!("The splits add up to {}, but the transaction amount is {}", total, amount));
+    }
+    Ok(())
+}
+
+/// Changes to make to an existing transaction. Fields left as `None` are kept as they are.
+#[derive(Debug, Default, Clone)]
+pub struct TransactionUpdate {
+    pub amount: Option<Money>,
+    pub time: Option<DateTime<FixedOffset>>,
+    // Makes the transaction date-only. Ignored if `time` is set.
+    pub date: Option<NaiveDate>,
+    pub description: Option<String>,
+    // Some(None) removes the category
+    pub category: Option<Option<CategoryId>>,
+    // Some(vec![]) removes the splits
+    pub splits: Option<Vec<Split>>,
+    // Tags to add and remove. They're normalized when the update is applied.
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+}
+
+/// Criteria for picking out transactions. Transactions have to match every criterion that's set. 
+#[derive(Debug, Default, Clone)]
+pub struct TransactionFilter {
+    // Both ends of the date range are inclusive
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub category: Option<CategoryId>,
+    pub min_amount: Option<Money>,
+    pub max_amount: Option<Money>,
+    // Matched case-insensitively anywhere in the description
+    pub description: Option<String>,
+    pub tag: Option<String>,
+    pub currency: Option<Currency>,
+}
+
+impl TransactionFilter {
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        let date = transaction.get_date();
+        self.from.is_none_or(|from| date >= from)
+            && self.to.is_none_or(|to| date <= to)
+            && self.category.as_ref().is_none_or(|c| {
+                transaction.get_category_amounts().iter().any(|(category, _)| *category == Some(c))
+            })
+            && self.min_amount.is_none_or(|min| transaction.amount >= min)
+            && self.max_amount.is_none_or(|max| transaction.amount <= max)
+            && self.description.as_ref().is_none_or(|d| {
+                transaction.description.to_lowercase().contains(&d.to_lowercase())
+            })
+            && self.tag.as_ref().is_none_or(|tag| transaction.has_tag(tag))
+            && self.currency.is_none_or(|currency| transaction.amount.currency() == currency)
+    }
 }
 
-#[derive(Debug, PartialEq)]
-struct Timestamp(DateTime<Utc>);
+/// When a transaction happened. Bank statements often only give the day, so the time of day is optional.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Timestamp {
+    At(DateTime<FixedOffset>),
+    Date(NaiveDate),
+}
 
-impl From<DateTime<Utc>> for Timestamp {
-    fn from(value: DateTime<Utc>) -> Self {
-        Timestamp(value)
+impl<Tz: TimeZone> From<DateTime<Tz>> for Timestamp {
+    fn from(value: DateTime<Tz>) -> Self {
+        Timestamp::At(value.fixed_offset())
     }
 }
 
+// The stored form of a timestamp
+#[derive(serde::Deserialize, serde::Serialize)]
+enum SerializedTimestamp {
+    // Seconds since the epoch, nanoseconds within the second, UTC offset in seconds
+    At(i64, u32, i32),
+    Date(NaiveDate),
+}
+
 impl serde::Serialize for Timestamp {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer 
     {
-        self.0.timestamp().serialize(serializer)    
+        let serialized = match self {
+            Timestamp::At(time) => SerializedTimestamp::At(
+                time.timestamp(), time.timestamp_subsec_nanos(), time.offset().local_minus_utc()),
+            Timestamp::Date(date) => SerializedTimestamp::Date(*date),
+        };
+        serialized.serialize(serializer)
     }
 }
 
@@ -49,25 +470,254 @@ impl<'a> serde::Deserialize<'a> for Timestamp {
     where
         D: serde::Deserializer<'a> 
     {
-            let data: i64 = Deserialize::deserialize(deserializer)?;
-            DateTime::<Utc>::from_timestamp(data, 0)
-                .map(Timestamp::from)
-                .ok_or_else(|| de::Error::custom("invalid timestamp found"))
+        let (seconds, nanos, offset) = match SerializedTimestamp::deserialize(deserializer)? {
+            SerializedTimestamp::At(seconds, nanos, offset) => (seconds, nanos, offset),
+            SerializedTimestamp::Date(date) => return Ok(Timestamp::Date(date)),
+        };
+        let offset = FixedOffset::east_opt(offset)
+            .ok_or_else(|| de::Error::custom("invalid UTC offset found"))?;
+        DateTime::from_timestamp(seconds, nanos)
+            .map(|t| Timestamp::At(t.with_timezone(&offset)))
+            .ok_or_else(|| de::Error::custom("invalid timestamp found"))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use chrono::Utc;
+
+    use crate::common_types::Currency;
+
     use super::*;
 
     #[test]
     fn new_empty() {
-        let amount = Money::from_float(100.0);
+        let amount = Money::from_float(100.0, Currency::USD);
         let time = "2000-1-1T00:00:00Z".parse::<DateTime<Utc>>().expect("Failed to parse");
         let description = "Widgets Inc.".to_string();
 
         assert_eq!(
             Transaction::new(amount, time, description.clone(), None),
-            Transaction {amount, time: Timestamp(time), description, category: None});
+            Transaction {id: TransactionId(0), amount, time: Timestamp::At(time.fixed_offset()), description, category: None, transfer: None, splits: Vec::new(), entry: None,
+                status: TransactionStatus::Pending, tags: BTreeSet::new(), payee: None});
+    }
+
+    #[test]
+    fn replace_category() {
+        let groceries = CategoryId::from("groceries".to_string());
+        let food = CategoryId::from("food".to_string());
+        let mut transaction = test_transaction(-100, "2000-01-01", "Costco", None)
+            .with_splits(vec![test_split(-60, "groceries"), test_split(-40, "household")])
+            .unwrap();
+        assert!(transaction.uses_category(&groceries));
+        assert!(transaction.replace_category(&groceries, Some(&food)));
+        assert!(!transaction.uses_category(&groceries));
+        assert_eq!(transaction.get_categories().collect::<Vec<_>>(), vec![&food, &CategoryId::from("household".to_string())]);
+        assert!(!transaction.replace_category(&groceries, None));
+    }
+
+    #[test]
+    fn tags() {
+        let transaction = test_transaction(-100, "2000-01-01", "Flight", None)
+            .with_tags(&["#Vacation-2026", "reimbursable", "vacation-2026"])
+            .unwrap();
+        assert_eq!(transaction.get_tags().iter().collect::<Vec<_>>(), vec!["reimbursable", "vacation-2026"]);
+        assert!(transaction.has_tag("#VACATION-2026"));
+        assert!(test_transaction(-100, "2000-01-01", "Flight", None).with_tags(&["two words"]).is_err());
+        assert!(test_transaction(-100, "2000-01-01", "Flight", None).with_tags(&["#"]).is_err());
+
+        let filter = TransactionFilter {tag: Some("#reimbursable".to_string()), ..Default::default()};
+        assert!(filter.matches(&transaction));
+        assert!(!filter.matches(&test_transaction(-100, "2000-01-01", "Flight", None)));
+    }
+
+    #[test]
+    fn update_tags() {
+        let mut transaction = test_transaction(-100, "2000-01-01", "Flight", None).with_tags(&["gift"]).unwrap();
+        let update = TransactionUpdate {
+            add_tags: vec!["#Reimbursable".to_string()],
+            remove_tags: vec!["#gift".to_string()],
+            ..Default::default()
+        };
+        assert!(transaction.check_update(&update).is_ok());
+        transaction.apply_update(update);
+        assert_eq!(transaction.get_tags().iter().collect::<Vec<_>>(), vec!["reimbursable"]);
+
+        let invalid = TransactionUpdate {add_tags: vec!["no spaces".to_string()], ..Default::default()};
+        assert!(transaction.check_update(&invalid).is_err());
+    }
+
+    #[test]
+    fn status_names() {
+        assert_eq!(TransactionStatus::Cleared.to_string(), "cleared");
+        assert_eq!("Reconciled".parse::<TransactionStatus>(), Ok(TransactionStatus::Reconciled));
+        assert!("bounced".parse::<TransactionStatus>().is_err());
+    }
+
+    fn test_transaction(amount: i64, date: &str, description: &str, category: Option<&str>) -> Transaction {
+        let time = format!("{}T12:00:00Z", date).parse::<DateTime<Utc>>().expect("Failed to parse");
+        Transaction::new(
+            Money::new(amount, Currency::USD),
+            time,
+            description.to_string(),
+            category.map(|c| CategoryId::from(c.to_string())))
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        assert!(TransactionFilter::default().matches(&test_transaction(100, "2000-01-01", "Widgets Inc.", None)));
+    }
+
+    #[test]
+    fn filter_by_date() {
+        let filter = TransactionFilter {
+            from: NaiveDate::from_ymd_opt(2000, 1, 2),
+            to: NaiveDate::from_ymd_opt(2000, 1, 3),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&test_transaction(100, "2000-01-01", "Widgets Inc.", None)));
+        assert!(filter.matches(&test_transaction(100, "2000-01-02", "Widgets Inc.", None)));
+        assert!(filter.matches(&test_transaction(100, "2000-01-03", "Widgets Inc.", None)));
+        assert!(!filter.matches(&test_transaction(100, "2000-01-04", "Widgets Inc.", None)));
+    }
+
+    #[test]
+    fn filter_by_amount() {
+        let filter = TransactionFilter {
+            min_amount: Some(Money::new(-500, Currency::USD)),
+            max_amount: Some(Money::new(500, Currency::USD)),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&test_transaction(-501, "2000-01-01", "Widgets Inc.", None)));
+        assert!(filter.matches(&test_transaction(-500, "2000-01-01", "Widgets Inc.", None)));
+        assert!(filter.matches(&test_transaction(500, "2000-01-01", "Widgets Inc.", None)));
+        assert!(!filter.matches(&test_transaction(501, "2000-01-01", "Widgets Inc.", None)));
+    }
+
+    #[test]
+    fn filter_by_category_and_description() {
+        let filter = TransactionFilter {
+            category: Some(CategoryId::from("food".to_string())),
+            description: Some("widget".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&test_transaction(100, "2000-01-01", "Widgets Inc.", Some("food"))));
+        assert!(!filter.matches(&test_transaction(100, "2000-01-01", "Widgets Inc.", Some("rent"))));
+        assert!(!filter.matches(&test_transaction(100, "2000-01-01", "Widgets Inc.", None)));
+        assert!(!filter.matches(&test_transaction(100, "2000-01-01", "Gadgets Inc.", Some("food"))));
+    }
+
+    #[test]
+    fn filter_by_currency() {
+        let filter = TransactionFilter {currency: Some(Currency::USD), ..Default::default()};
+        assert!(filter.matches(&test_transaction(100, "2000-01-01", "Widgets Inc.", None)));
+        let transaction = Transaction::new_on_date(Money::new(100, Currency::EUR), NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(), "".to_string(), None);
+        assert!(!filter.matches(&transaction));
+    }
+
+    #[test]
+    fn date_is_local_to_offset() {
+        // 11pm on the 1st in New York is already the 2nd in UTC
+        let time = DateTime::parse_from_rfc3339("2000-01-01T23:00:00-05:00").expect("Failed to parse");
+        let transaction = Transaction::new(Money::new(100, Currency::USD), time, "Widgets Inc.".to_string(), None);
+        assert_eq!(transaction.get_date(), NaiveDate::from_ymd_opt(2000, 1, 1).unwrap());
+        assert_eq!(transaction.get_time().unwrap().offset().local_minus_utc(), -5 * 3600);
+    }
+
+    fn round_trip(timestamp: Timestamp) -> Timestamp {
+        let mut data = Vec::new();
+        ciborium::into_writer(&timestamp, &mut data).unwrap();
+        ciborium::from_reader(data.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn timestamp_round_trip() {
+        let time = DateTime::parse_from_rfc3339("2000-01-01T23:00:00.123456789-05:00").expect("Failed to parse");
+        let read = round_trip(Timestamp::At(time));
+        let Timestamp::At(read) = read else { panic!("Expected a time, got {:?}", read) };
+        assert_eq!(read, time);
+        assert_eq!(read.offset(), time.offset());
+        assert_eq!(read.timestamp_subsec_nanos(), 123456789);
+
+        let date = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        assert_eq!(round_trip(Timestamp::Date(date)), Timestamp::Date(date));
+    }
+
+    #[test]
+    fn date_only() {
+        let date = NaiveDate::from_ymd_opt(2000, 1, 2).unwrap();
+        let on_date = Transaction::new_on_date(Money::new(100, Currency::USD), date, "Widgets Inc.".to_string(), None);
+        let timed = test_transaction(100, "2000-01-02", "Widgets Inc.", None);
+        assert_eq!(on_date.get_date(), date);
+        assert_eq!(on_date.get_time(), None);
+        assert!(on_date.chronological_key() < timed.chronological_key());
+    }
+
+    fn test_split(amount: i64, category: &str) -> Split {
+        Split::new(Money::new(amount, Currency::USD), Some(CategoryId::from(category.to_string())), String::new())
+    }
+
+    #[test]
+    fn splits() {
+        let transaction = test_transaction(-10000, "2000-01-01", "Costco", Some("food"))
+            .with_splits(vec![test_split(-6000, "groceries"), test_split(-4000, "household")])
+            .unwrap();
+        assert_eq!(transaction.get_category(), None);
+        assert_eq!(transaction.get_category_amounts(), vec![
+            (Some(&CategoryId::from("groceries".to_string())), Money::new(-6000, Currency::USD)),
+            (Some(&CategoryId::from("household".to_string())), Money::new(-4000, Currency::USD)),
+        ]);
+
+        let filter = TransactionFilter {category: Some(CategoryId::from("household".to_string())), ..Default::default()};
+        assert!(filter.matches(&transaction));
+
+        assert!(test_transaction(-10000, "2000-01-01", "Costco", None)
+            .with_splits(vec![test_split(-6000, "groceries"), test_split(-3000, "household")])
+            .is_err());
+        assert!(test_transaction(-10000, "2000-01-01", "Costco", None)
+            .with_splits(vec![test_split(-6000, "groceries"), Split::new(Money::new(-4000, Currency::EUR), None, String::new())])
+            .is_err());
+    }
+
+    #[test]
+    fn check_update_with_splits() {
+        let transaction = test_transaction(-10000, "2000-01-01", "Costco", None)
+            .with_splits(vec![test_split(-6000, "groceries"), test_split(-4000, "household")])
+            .unwrap();
+
+        let amount_only = TransactionUpdate {amount: Some(Money::new(-5000, Currency::USD)), ..Default::default()};
+        assert!(transaction.check_update(&amount_only).is_err());
+        let with_splits = TransactionUpdate {
+            amount: Some(Money::new(-5000, Currency::USD)),
+            splits: Some(vec![test_split(-5000, "groceries")]),
+            ..Default::default()
+        };
+        assert!(transaction.check_update(&with_splits).is_ok());
+        let category = TransactionUpdate {category: Some(Some(CategoryId::from("food".to_string()))), ..Default::default()};
+        assert!(transaction.check_update(&category).is_err());
+        let unsplit = TransactionUpdate {
+            category: Some(Some(CategoryId::from("food".to_string()))),
+            splits: Some(Vec::new()),
+            ..Default::default()
+        };
+        assert!(transaction.check_update(&unsplit).is_ok());
+    }
+
+    #[test]
+    fn apply_update() {
+        let time = "2000-1-1T00:00:00Z".parse::<DateTime<Utc>>().expect("Failed to parse");
+        let mut transaction = Transaction::new(
+            Money::new(100, Currency::USD), time, "Widgets Inc.".to_string(), Some(CategoryId::from("stuff".to_string())));
+
+        transaction.apply_update(TransactionUpdate {
+            amount: Some(Money::new(250, Currency::USD)),
+            category: Some(None),
+            ..Default::default()
+        });
+
+        assert_eq!(transaction.amount, Money::new(250, Currency::USD));
+        assert_eq!(transaction.category, None);
+        assert_eq!(transaction.description, "Widgets Inc.");
+        assert_eq!(transaction.time, Timestamp::At(time.fixed_offset()));
     }
 }
\ No newline at end of file
@@ -1,11 +1,17 @@
+mod v0;
+
 use std::{fs::File, path::{Path, PathBuf}, error::Error};
 
 use ciborium::{into_writer, from_reader};
 use ledger::Ledger;
 
 static FILE_NAME: &str = "ledger.data";
-// For now only version is v0. Once we can commit to stop breaking the format, will change to v1.
-static CURRENT_VERSION: u32 = 0;
+// v1 stores transaction times to the nanosecond with their UTC offset, and allows date-only transactions.
+//
+// The version only changes when existing data is stored differently. Everything added to the ledger since v1 has a
+// `#[serde(default)]`, so files written before it was added still load as v1. New fields have to follow suit, or
+// come with a version bump and a migration like the one for v0.
+const CURRENT_VERSION: u32 = 1;
 static FILE_HEADER: FileHeader = FileHeader { version: CURRENT_VERSION };
 
 pub struct FileStore {
@@ -27,11 +33,15 @@ impl FileStore {
     pub fn load_ledger(&self) -> Result<Ledger, Box<dyn Error>> {
         let file_reader = File::open(self.get_store_file_path())?;
         let file_header: FileHeader = from_reader(&file_reader)?;
-        if file_header.version != CURRENT_VERSION {
-            return Err(Box::from("Version mismatch, cannot load file"));
+        match file_header.version {
+            CURRENT_VERSION => Ok(from_reader(&file_reader)?),
+            // Migrated files are written back out as the current version on the next store
+            0 => {
+                let ledger: v0::Ledger = from_reader(&file_reader)?;
+                Ok(ledger.migrate()?)
+            },
+            version => Err(Box::from(format!("Unsupported file version v{}, cannot load file", version))),
         }
-        let ledger: Ledger = from_reader(&file_reader)?;
-        Ok(ledger)
     }
 
     fn get_store_file_path(&self) -> PathBuf {
@@ -46,6 +56,9 @@ struct FileHeader {
 
 #[cfg(test)]
 mod tests {
+    use ciborium::Value;
+    use ledger::{common_types::{Currency, Money}, transaction::Transaction};
+
     use super::*;
 
     #[test]
@@ -53,4 +66,151 @@ mod tests {
         let file_store = FileStore::new(Path::new("foo"));
         assert_eq!(file_store.root_path, Path::new("foo"))
     }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("store-test-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_with_version<T: serde::Serialize>(file_store: &FileStore, version: u32, ledger: &T) {
+        let file_writer = File::create(file_store.get_store_file_path()).unwrap();
+        into_writer(&FileHeader { version }, &file_writer).unwrap();
+        into_writer(ledger, &file_writer).unwrap();
+    }
+
+    // A ledger laid out the way v0 files stored it
+    fn v0_ledger() -> Value {
+        let text = |s: &str| Value::Text(s.to_string());
+        let transaction = |cents: i64, seconds: i64, description: &str, category: Option<&str>| Value::Map(vec![
+            (text("amount"), Value::Integer(cents.into())),
+            (text("time"), Value::Integer(seconds.into())),
+            (text("description"), text(description)),
+            (text("category"), category.map_or(Value::Null, text)),
+        ]);
+        Value::Map(vec![
+            (text("accounts"), Value::Array(vec![
+                Value::Map(vec![
+                    (text("transactions"), Value::Array(vec![
+                        transaction(250000, 946684800, "Paycheck", None),
+                        transaction(-4599, 946771200, "Grocery store", Some("food")),
+                    ])),
+                    (text("name"), text("checking")),
+                ]),
+                Value::Map(vec![
+                    (text("transactions"), Value::Array(vec![])),
+                    (text("name"), text("savings")),
+                ]),
+            ])),
+            (text("categories"), Value::Map(vec![
+                (text("category_set"), Value::Map(vec![
+                    (text("food"), Value::Map(vec![(text("id"), text("food"))])),
+                ])),
+            ])),
+        ])
+    }
+
+    #[test]
+    fn migrates_v0() {
+        let dir = test_dir("v0");
+        let file_store = FileStore::new(&dir);
+        write_with_version(&file_store, 0, &v0_ledger());
+
+        let ledger = file_store.load_ledger().unwrap();
+        let names: Vec<&String> = ledger.get_accounts().iter().map(|a| a.get_name()).collect();
+        assert_eq!(names, vec!["checking", "savings"]);
+        let checking = ledger.get_account_by_name("checking").unwrap();
+        assert_eq!(checking.get_currency(), Currency::USD);
+        assert_eq!(checking.get_total(), Money::new(245401, Currency::USD));
+
+        let transactions: Vec<&Transaction> = checking.get_transactions().iter().collect();
+        assert_eq!(transactions[0].get_time().unwrap(), "2000-01-01T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().unwrap());
+        assert_eq!(transactions[1].get_description(), "Grocery store");
+        let food = transactions[1].get_category().unwrap();
+        assert_eq!(ledger.get_transaction_categories().get_category(food).unwrap().name(), "food");
+        assert_ne!(transactions[0].get_id(), transactions[1].get_id());
+
+        // It's written back out as the current version
+        file_store.store_ledger(&ledger).unwrap();
+        assert_eq!(file_store.load_ledger().unwrap().get_accounts().len(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // Drop everything from a map that isn't one of `keep`
+    fn retain_fields(value: &mut Value, keep: &[&str]) {
+        let Value::Map(fields) = value else { panic!("Expected a map, got {:?}", value) };
+        fields.retain(|(key, _)| keep.contains(&key.as_text().unwrap()));
+    }
+
+    fn field<'a>(value: &'a mut Value, name: &str) -> &'a mut Value {
+        let Value::Map(fields) = value else { panic!("Expected a map, got {:?}", value) };
+        &mut fields.iter_mut().find(|(key, _)| key.as_text() == Some(name)).unwrap().1
+    }
+
+    #[test]
+    fn loads_v1_without_later_fields() {
+        let dir = test_dir("v1");
+        let file_store = FileStore::new(&dir);
+        let mut ledger = Ledger::new_empty();
+        let account = ledger.add_new_account("checking".to_string(), Currency::USD).unwrap();
+        let food = ledger.get_transaction_categories_mut().create_category("food".to_string()).unwrap();
+        let time = chrono::DateTime::parse_from_rfc3339("2000-01-01T23:00:00.5+09:30").unwrap();
+        let amount = Money::new(-100, Currency::USD);
+        ledger.add_transaction(account, Transaction::new(amount, time, "Widgets Inc.".to_string(), Some(food.clone()))).unwrap();
+
+        // Cut the ledger down to what the first v1 files had
+        let mut value = Value::serialized(&ledger).unwrap();
+        retain_fields(&mut value, &["accounts", "categories", "exchange_rates", "next_account_id", "next_transaction_id"]);
+        let Value::Array(accounts) = field(&mut value, "accounts") else { panic!("Expected accounts") };
+        for account in accounts {
+            retain_fields(account, &["id", "transactions", "name", "currency"]);
+            let Value::Array(transactions) = field(account, "transactions") else { panic!("Expected transactions") };
+            for transaction in transactions {
+                retain_fields(transaction, &["id", "amount", "time", "description", "category"]);
+            }
+        }
+        let Value::Map(categories) = field(field(&mut value, "categories"), "category_set") else { panic!("Expected categories") };
+        for (_, category) in categories {
+            retain_fields(category, &["id"]);
+        }
+        write_with_version(&file_store, 1, &value);
+
+        let loaded = file_store.load_ledger().unwrap();
+        let checking = loaded.get_account_by_name("checking").unwrap();
+        assert_eq!(checking.get_total(), amount);
+        assert_eq!(checking.get_transactions()[0].get_time().unwrap(), time);
+        assert_eq!(loaded.get_transaction_categories().find_by_name("food").unwrap().get_id(), &food);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let dir = test_dir("versions");
+        let file_store = FileStore::new(&dir);
+        write_with_version(&file_store, 99, &Ledger::new_empty());
+        assert!(file_store.load_ledger().is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn store_and_load_keeps_times() {
+        let dir = test_dir("times");
+        let file_store = FileStore::new(&dir);
+        let mut ledger = Ledger::new_empty();
+        let account = ledger.add_new_account("checking".to_string(), ledger::common_types::Currency::USD).unwrap();
+        let time = chrono::DateTime::parse_from_rfc3339("2000-01-01T23:00:00.5+09:30").unwrap();
+        let amount = ledger::common_types::Money::new(100, ledger::common_types::Currency::USD);
+        let id = ledger.add_transaction(account, Transaction::new(amount, time, "Widgets Inc.".to_string(), None)).unwrap();
+
+        file_store.store_ledger(&ledger).unwrap();
+        let loaded = file_store.load_ledger().unwrap();
+        let loaded_time = loaded.get_transaction(id).unwrap().get_time().unwrap();
+        assert_eq!(loaded_time, time);
+        assert_eq!(loaded_time.offset(), time.offset());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,50 @@
+//! The ledger as it was stored in v0 files, before accounts and transactions had IDs. Amounts were whole cents with no
+//! currency (everything was USD), and times were whole seconds since the epoch in UTC.
+
+use chrono::DateTime;
+use ledger::{category::{CategoryId, TransactionCategories}, common_types::{Currency, Money}};
+
+#[derive(serde::Deserialize)]
+pub struct Ledger {
+    accounts: Vec<Account>,
+    // Categories were only ever an ID, which the current categories still read as the name
+    categories: TransactionCategories,
+}
+
+#[derive(serde::Deserialize)]
+struct Account {
+    transactions: Vec<Transaction>,
+    name: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Transaction {
+    amount: i64,
+    time: i64,
+    description: String,
+    category: Option<String>,
+}
+
+impl Ledger {
+    /// Rebuild the ledger in the current form, handing out new account and transaction IDs in the order they were
+    /// stored.
+    pub fn migrate(self) -> Result<ledger::Ledger, String> {
+        let mut ledger = ledger::Ledger::new_empty();
+        *ledger.get_transaction_categories_mut() = self.categories;
+
+        for account in self.accounts {
+            let account_id = ledger.add_new_account(account.name.clone(), Currency::USD)
+                .map_err(|e| format!("Can't migrate account '{}': {}", account.name, e))?;
+            for transaction in account.transactions {
+                let time = DateTime::from_timestamp(transaction.time, 0)
+                    .ok_or_else(|| format!("Invalid time {} in account '{}'", transaction.time, account.name))?;
+                let category = transaction.category.map(CategoryId::from);
+                let migrated = ledger::transaction::Transaction::new(
+                    Money::new(transaction.amount, Currency::USD), time, transaction.description, category);
+                ledger.add_transaction(account_id, migrated)
+                    .map_err(|e| format!("Can't migrate a transaction in account '{}': {}", account.name, e))?;
+            }
+        }
+        Ok(ledger)
+    }
+}